@@ -0,0 +1,273 @@
+//=================================================================================
+// Here we are defining the Animated Image AssetFile and Animation Backend. GIF
+// and APNG files are decoded into a texture atlas, and their frame delays are
+// turned into a single clip that plays every frame of the file.
+//=================================================================================
+
+use std::{fmt::Display, io::Cursor};
+use bevy::{asset::{AssetLoader, AsyncReadExt}, ecs::query::WorldQuery, prelude::*, sprite::Anchor};
+use image::{codecs::{gif::GifDecoder, png::PngDecoder}, imageops, AnimationDecoder, Delay, RgbaImage};
+
+use crate::{animation::{Animation, AnimationBackend, Animator}, clip::FrameClip, util::frame_image};
+
+//=================================================================================
+//    AnimatedImageAnimationPlugin
+//=================================================================================
+
+pub(crate) struct AnimatedImageAnimationPlugin;
+
+impl Plugin for AnimatedImageAnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_asset_loader::<AnimatedImageLoader>()
+            .init_asset::<AnimatedImage>()
+        ;
+    }
+}
+
+//=================================================================================
+//    Animated Image Asset
+//=================================================================================
+
+/// The Animated Image Asset. Stores every frame of a GIF or APNG in an atlas, along with the clip built from the frame delays.
+#[derive(Asset, TypePath)]
+pub struct AnimatedImage {
+    image : Handle<Image>,
+    layout : Handle<TextureAtlasLayout>,
+    clip : FrameClip,
+    dimensions : UVec2,
+}
+
+impl AnimatedImage {
+    /// The atlas that holds all of the frames.
+    pub fn image(&self) -> &Handle<Image> {
+        &self.image
+    }
+
+    /// The layout of the atlas.
+    pub fn layout(&self) -> &Handle<TextureAtlasLayout> {
+        &self.layout
+    }
+
+    /// The clip that plays every frame of the file.
+    pub fn clip(&self) -> &FrameClip {
+        &self.clip
+    }
+
+    /// The size of each frame in pixels.
+    pub fn dimensions(&self) -> UVec2 {
+        self.dimensions
+    }
+}
+
+//=================================================================================
+//    Animated Image Asset Loader
+//=================================================================================
+
+/// Asset Loader for GIF and APNG files. APNG files have to use the `.apng` extension, so that they don't replace bevy's PNG loader.
+#[derive(Default)]
+pub struct AnimatedImageLoader;
+
+/// The errors that can occur while loading an animated image.
+#[derive(Debug)]
+pub enum AnimatedImageLoaderError {
+    Io(std::io::Error),
+    Decode(image::ImageError),
+    AtlasTooLarge { frames : usize, dimensions : UVec2 },
+    NotAnimated,
+    Empty,
+}
+
+impl Display for AnimatedImageLoaderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AnimatedImageLoaderError::Io(error) => write!(f, "Could not read animated image: {}", error),
+            AnimatedImageLoaderError::Decode(error) => write!(f, "Could not decode animated image: {}", error),
+            AnimatedImageLoaderError::AtlasTooLarge { frames, dimensions } => write!(f, "{} frames of {}x{} do not fit in a {}x{} atlas", frames, dimensions.x, dimensions.y, MAX_ATLAS_SIZE, MAX_ATLAS_SIZE),
+            AnimatedImageLoaderError::NotAnimated => write!(f, "PNG file is not an APNG"),
+            AnimatedImageLoaderError::Empty => write!(f, "Animated image has no frames"),
+        }
+    }
+}
+
+impl std::error::Error for AnimatedImageLoaderError {}
+
+impl From<std::io::Error> for AnimatedImageLoaderError {
+    fn from(error: std::io::Error) -> Self {
+        AnimatedImageLoaderError::Io(error)
+    }
+}
+
+impl From<image::ImageError> for AnimatedImageLoaderError {
+    fn from(error: image::ImageError) -> Self {
+        AnimatedImageLoaderError::Decode(error)
+    }
+}
+
+impl AssetLoader for AnimatedImageLoader {
+    type Asset = AnimatedImage;
+
+    type Settings = ();
+
+    type Error = AnimatedImageLoaderError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut bevy::asset::io::Reader,
+        _: &'a Self::Settings,
+        load_context: &'a mut bevy::asset::LoadContext,
+    ) -> bevy::utils::BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            let is_gif = load_context.path().extension().is_some_and(|extension| extension == "gif");
+            let frames = if is_gif {
+                GifDecoder::new(Cursor::new(bytes))?.into_frames().collect_frames()?
+            } else {
+                let decoder = PngDecoder::new(Cursor::new(bytes))?;
+                if !decoder.is_apng() { return Err(AnimatedImageLoaderError::NotAnimated) }
+                decoder.apng().into_frames().collect_frames()?
+            };
+            let Some(first) = frames.first() else { return Err(AnimatedImageLoaderError::Empty) };
+            let dimensions = UVec2::new(first.buffer().width(), first.buffer().height());
+
+            let durations = frames.iter().map(|frame| frame_delay(frame.delay(), is_gif)).collect::<Vec<_>>();
+            let (layout, image) = pack_grid(frames.into_iter().map(|frame| frame.into_buffer()).collect(), dimensions)?;
+            let image = load_context.add_labeled_asset("atlas".to_string(), image);
+            let layout = load_context.add_labeled_asset("layout".to_string(), layout);
+
+            let clip = FrameClip::new((0..durations.len()).collect(), &durations);
+
+            Ok(AnimatedImage { image, layout, clip, dimensions })
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["gif", "apng"]
+    }
+}
+
+/// The largest atlas an animated image is packed into. This is the largest texture that most GPUs support.
+const MAX_ATLAS_SIZE : u32 = 8192;
+
+/// Returns the delay of a frame in milliseconds. Browsers show GIF frames with a delay of 10 milliseconds or less for 100
+/// milliseconds, and many GIFs are authored with that in mind, so these are clamped the same way.
+fn frame_delay(delay : Delay, is_gif : bool) -> u32 {
+    let (numerator, denominator) = delay.numer_denom_ms();
+    let delay = numerator / denominator.max(1);
+    if is_gif && delay <= 10 { 100 } else { delay }
+}
+
+/// Packs the frames into a grid that is about as wide as it is tall, in the order they are played.
+fn pack_grid(frames : Vec<RgbaImage>, dimensions : UVec2) -> Result<(TextureAtlasLayout, Image), AnimatedImageLoaderError> {
+    let columns = (frames.len() as f32).sqrt().ceil() as u32;
+    let rows = (frames.len() as u32).div_ceil(columns);
+    let size = dimensions * UVec2::new(columns, rows);
+    if size.max_element() > MAX_ATLAS_SIZE {
+        return Err(AnimatedImageLoaderError::AtlasTooLarge { frames : frames.len(), dimensions })
+    }
+
+    let mut sheet = RgbaImage::new(size.x, size.y);
+    for (index, frame) in frames.iter().enumerate() {
+        let cell = UVec2::new(index as u32 % columns, index as u32 / columns) * dimensions;
+        imageops::replace(&mut sheet, frame, cell.x as i64, cell.y as i64);
+    }
+    let layout = TextureAtlasLayout::from_grid(dimensions.as_vec2(), columns as usize, rows as usize, None, None);
+    Ok((layout, frame_image(size, sheet.into_raw())))
+}
+
+//=================================================================================
+//    Animated Image Animation
+//=================================================================================
+
+/// This trait will allow you to animate a sprite with a GIF or APNG. Since these files only have one clip, there is nothing to
+/// choose between, but the type can still be given state with `AnimationState` to change the speed or restart the clip. Implement
+/// `Animation` for the type with `impl_animation!(MyAnimation, AnimatedImageBackend)`.
+pub trait AnimatedImageAnimation : Sized {
+
+    /// The anchor of the sprite. This defaults to the center of the image.
+    fn get_anchor() -> Anchor { Anchor::Center }
+}
+
+/// The animation backend for GIF and APNG files. See `AnimatedImageAnimation`.
+pub struct AnimatedImageBackend;
+
+impl <A : AnimatedImageAnimation + Animation + FromWorld + Send + Sync + 'static> AnimationBackend<A> for AnimatedImageBackend {
+    type AsociatedAsset = AnimatedImage;
+
+    type Query<'w, 's> = &'w mut TextureAtlas;
+
+    fn apply(
+        animator : &Animator<A>,
+        items : &mut <Self::Query<'_, '_> as WorldQuery>::Item<'_>,
+        asset : &Self::AsociatedAsset,
+    ) {
+        items.index = asset.clip.frame(animator.progress());
+    }
+
+    fn spawn(animation : Option<A>, world : &mut World, path : String, entity : Entity) {
+        let animation_comp = animation.unwrap_or(A::from_world(world));
+        let asset_server = world.resource::<AssetServer>();
+        let animated_image : Handle<AnimatedImage> = asset_server.load(&path);
+        let image : Handle<Image> = asset_server.load(format!("{}#atlas", path));
+        let layout : Handle<TextureAtlasLayout> = asset_server.load(format!("{}#layout", path));
+
+        world.get_or_spawn(entity).unwrap()
+            .insert(Animator::new(animation_comp))
+            .insert(animated_image)
+            .insert(SpriteSheetBundle {
+                texture : image,
+                atlas : TextureAtlas { layout, index : 0 },
+                sprite : Sprite {
+                    anchor : A::get_anchor(),
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+        ;
+    }
+
+    fn duration(_animation : &A, asset : &Self::AsociatedAsset) -> f32 {
+        asset.clip.duration()
+    }
+    
+    fn debug_frame(_animation : &A, progress : f32, asset : &Self::AsociatedAsset) -> Option<usize> {
+        Some(asset.clip.frame(progress))
+    }
+}
+
+//=================================================================================
+//    Tests
+//=================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn clamps_short_gif_delays() {
+        assert_eq!(frame_delay(Delay::from_numer_denom_ms(0, 1), true), 100);
+        assert_eq!(frame_delay(Delay::from_numer_denom_ms(10, 1), true), 100);
+        assert_eq!(frame_delay(Delay::from_numer_denom_ms(20, 1), true), 20);
+        assert_eq!(frame_delay(Delay::from_numer_denom_ms(10, 1), false), 10);
+    }
+    
+    #[test]
+    fn packs_frames_into_a_grid() {
+        let dimensions = UVec2::new(4, 2);
+        let frames = (0..5).map(|index| RgbaImage::from_pixel(4, 2, image::Rgba([index, 0, 0, 255]))).collect();
+        let (layout, image) = pack_grid(frames, dimensions).unwrap();
+        assert_eq!(layout.size, Vec2::new(12.0, 4.0));
+        assert_eq!(layout.textures.len(), 6);
+        assert_eq!(layout.textures[4], Rect::new(4.0, 2.0, 8.0, 4.0));
+        assert_eq!(image.texture_descriptor.size.width, 12);
+        
+        let pixel = |x : u32, y : u32| image.data[((y * 12 + x) * 4) as usize];
+        assert_eq!(pixel(0, 0), 0);
+        assert_eq!(pixel(9, 1), 2);
+        assert_eq!(pixel(5, 3), 4);
+        
+        let frames = vec![RgbaImage::new(3000, 1); 5];
+        assert!(matches!(pack_grid(frames, UVec2::new(3000, 1)), Err(AnimatedImageLoaderError::AtlasTooLarge { frames : 5, .. })));
+    }
+}
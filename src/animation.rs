@@ -4,27 +4,69 @@
 // handle 2D animations for now but plan for 3D later.
 //=================================================================================
 
-use std::marker::PhantomData;
-use bevy::{ecs::query::{QueryData, WorldQuery}, prelude::*};
+use std::{collections::VecDeque, marker::PhantomData, time::Duration};
+use bevy::{ecs::query::{QueryData, WorldQuery}, prelude::*, reflect::GetTypeRegistration};
+
+use crate::{
+    debug::insert_animator_debug, 
+    easing::{AnimatorEasing, EasingCurve}, 
+    scene::{spawn_animation_sources, AnimationSource}, 
+    sync::{AnimationSyncGroup, AnimationSyncGroups, SyncStart}, 
+    time::{AnimationHitStop, AnimationTime, AnimationTimeSource},
+};
 
 //=================================================================================
 //    Animation Plugin
 //=================================================================================
 
 /// This plugin will register the required systems so that an animation of a type will work. Required to be implemented for each animation type.
-pub struct AnimationPlugin<A : Animation>(PhantomData<A>);
+pub struct AnimationPlugin<A : Animation> {
+    register : Option<fn(&mut App)>,
+    marker : PhantomData<A>,
+}
 
 impl <A : Animation + Send + Sync + 'static> Default for AnimationPlugin<A> {
     fn default() -> Self {
-        AnimationPlugin(PhantomData)
+        AnimationPlugin { register : None, marker : PhantomData }
+    }
+}
+
+impl <A : Animation + Reflect + FromReflect + TypePath + GetTypeRegistration + Clone + Send + Sync + 'static> AnimationPlugin<A> {
+    /// Creates the plugin, and registers the animation, its `Animator` and its `AnimationSource` for reflection so that they can
+    /// be shown in inspectors and saved in scenes. Entities given an `AnimationSource` are set up when it is added.
+    pub fn reflect() -> Self {
+        AnimationPlugin { 
+            register : Some(|app| { 
+                app
+                    .register_type::<A>()
+                    .register_type::<Animator<A>>()
+                    .register_type::<QueuedAnimation<A>>()
+                    .register_type::<VecDeque<QueuedAnimation<A>>>()
+                    .register_type::<AnimationSource<A>>()
+                    .add_systems(PreUpdate, spawn_animation_sources::<A>)
+                ;
+            }), 
+            marker : PhantomData,
+        }
     }
 }
 
 impl <A : Animation + Send + Sync + 'static> Plugin for AnimationPlugin<A> {
     fn build(&self, app: &mut App) {
         app
-            .add_systems(PostUpdate, update_animators::<A>)
+            .add_event::<AnimationQueueEvent>()
+            .init_resource::<AnimationSyncGroups>()
+            .init_resource::<AnimationTime>()
+            .configure_sets(PostUpdate, AnimationSet::Layer.after(AnimationSet::Animate))
+            .add_systems(FixedUpdate, update_fixed_animators::<A>)
+            .add_systems(PostUpdate, (
+                update_animators::<A>,
+                update_animator_debug::<A>.after(update_animators::<A>),
+            ).in_set(AnimationSet::Animate))
+            .add_systems(PostUpdate, insert_animator_debug::<A>.before(AnimationSet::Animate))
         ;
+        if let Some(register) = self.register { register(app) }
+        A::build(app);
     }
 
     fn is_unique(&self) -> bool {
@@ -36,19 +78,104 @@ impl <A : Animation + Send + Sync + 'static> Plugin for AnimationPlugin<A> {
 //    Animation Systems
 //=================================================================================
 
+/// The system sets that animators run in, so that other systems can be ordered around them.
+#[derive(SystemSet, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AnimationSet {
+    /// Every animator is advanced and applied to the entity it is attached to.
+    Animate,
+    /// Animation layers are mixed into the entities they target. This runs after `Animate`.
+    Layer,
+}
+
 /// This system will update all of the animators in the world and apply the animations to the components they are attached to.
+/// Animators that use `AnimationTimeSource::Fixed` are only applied here, since they are advanced in `FixedUpdate`.
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn update_animators<A : Animation + Send + Sync + 'static>(
-    mut animators : Query<(&mut Animator<A>, A::Query<'_, '_>, &Handle<A::AsociatedAsset>)>,
+    mut animators : Query<(Entity, &mut Animator<A>, AnimatorClock, A::Query<'_, '_>, &Handle<A::AsociatedAsset>)>,
     assets : Res<Assets<A::AsociatedAsset>>,
+    curves : Option<Res<Assets<EasingCurve>>>,
     time : Res<Time>,
+    real_time : Res<Time<Real>>,
+    animation_time : Res<AnimationTime>,
+    mut sync_groups : ResMut<AnimationSyncGroups>,
+    mut queue_events : EventWriter<AnimationQueueEvent>,
 ) {
-    for (mut animator, mut query, handle) in animators.iter_mut() {
+    for (entity, mut animator, (source, hit_stop, sync_group), mut query, handle) in animators.iter_mut() {
         let Some(asset) = assets.get(handle) else { continue };
-        animator.progress += time.delta_seconds() / animator.animation.duration(asset) * animator.speed;
+        let (unscaled, now) = match source.copied().unwrap_or_default() {
+            AnimationTimeSource::Virtual => (time.delta_seconds(), time.elapsed()),
+            AnimationTimeSource::Real => (real_time.delta_seconds(), real_time.elapsed()),
+            AnimationTimeSource::Fixed => (0.0, Duration::ZERO),
+        };
+        if unscaled > 0.0 {
+            let mut delta = match source {
+                Some(AnimationTimeSource::Real) => unscaled,
+                _ => animation_time.scale_delta(unscaled),
+            };
+            if let Some(mut hit_stop) = hit_stop {
+                if hit_stop.is_active() { delta = hit_stop.apply(unscaled, delta) }
+            }
+            let duration = animator.animation.duration(asset);
+            if let Some(event) = animator.advance(entity, delta, duration, sync_group, &mut sync_groups, now) {
+                queue_events.send(event);
+            }
+        }
+        animator.eased_progress = animator.easing.ease(animator.progress.fract(), curves.as_deref());
         A::apply(&mut animator, &mut query, asset);
     }
 }
 
+/// An animator that can be advanced on a fixed timestep, along with its asset.
+type FixedAnimator<A> = (Entity, &'static mut Animator<A>, AnimatorClock, &'static Handle<<A as Animation>::AsociatedAsset>);
+
+/// This system advances the animators that use `AnimationTimeSource::Fixed` once per fixed timestep.
+pub(crate) fn update_fixed_animators<A : Animation + Send + Sync + 'static>(
+    mut animators : Query<FixedAnimator<A>>,
+    assets : Res<Assets<A::AsociatedAsset>>,
+    time : Res<Time>,
+    animation_time : Res<AnimationTime>,
+    mut sync_groups : ResMut<AnimationSyncGroups>,
+    mut queue_events : EventWriter<AnimationQueueEvent>,
+) {
+    for (entity, mut animator, (source, hit_stop, sync_group), handle) in animators.iter_mut() {
+        if source != Some(&AnimationTimeSource::Fixed) { continue }
+        let Some(asset) = assets.get(handle) else { continue };
+        let mut delta = animation_time.scale_delta(time.delta_seconds());
+        if let Some(mut hit_stop) = hit_stop {
+            if hit_stop.is_active() { delta = hit_stop.apply(time.delta_seconds(), delta) }
+        }
+        let duration = animator.animation.duration(asset);
+        if let Some(event) = animator.advance(entity, delta, duration, sync_group, &mut sync_groups, time.elapsed()) {
+            queue_events.send(event);
+        }
+    }
+}
+
+/// An animator with an `AnimatorDebug` to fill in, along with its asset.
+type DebuggedAnimator<A> = (&'static Animator<A>, &'static Handle<<A as Animation>::AsociatedAsset>, &'static mut AnimatorDebug);
+
+/// This system fills in the `AnimatorDebug` of every animator that has one.
+pub(crate) fn update_animator_debug<A : Animation + Send + Sync + 'static>(
+    mut animators : Query<DebuggedAnimator<A>>,
+    assets : Res<Assets<A::AsociatedAsset>>,
+) {
+    for (animator, handle, mut debug) in animators.iter_mut() {
+        let Some(asset) = assets.get(handle) else { continue };
+        let duration = animator.animation.duration(asset);
+        let repititions = animator.queued_repititions.map_or(1.0 - animator.linear_progress(), |repititions| repititions as f32 - animator.progress);
+        *debug = AnimatorDebug {
+            animation : animator.animation.debug_name().map(str::to_string),
+            frame : animator.animation.debug_frame(animator.progress(), asset),
+            repititions : animator.repititions(),
+            progress : animator.linear_progress(),
+            time_remaining : if animator.speed > 0.0 { (repititions * duration / animator.speed).max(0.0) } else { f32::INFINITY },
+        };
+    }
+}
+
+/// The components that decide how fast an animator advances.
+type AnimatorClock = (Option<&'static AnimationTimeSource>, Option<&'static mut AnimationHitStop>, Option<&'static AnimationSyncGroup>);
+
 //=================================================================================
 //    Animation
 //=================================================================================
@@ -73,8 +200,67 @@ pub trait Animation : Sized {
     /// Describes how to spawn the animation in the world. This method is used by the `spawn_animation` method on the `Commands` struct.
     fn spawn(animation : Option<Self>, world: &mut World, path : String, entity : Entity);
     
-    /// Given the state of the animation, should return the duration of the animation in seconds.
+    /// Describes how to spawn the animation on a layer entity. See `AnimationLayer`. This defaults to `spawn`, but animations
+    /// that write to a pose should only add what the pose needs, since the layer entity is never shown.
+    fn spawn_layer(animation : Self, world : &mut World, path : String, entity : Entity) {
+        Self::spawn(Some(animation), world, path, entity)
+    }
+    
+    /// Given the state of the animation, should return the duration of the animation in seconds. A duration of 0.0 or less
+    /// will stop the animation from progressing.
     fn duration(&self, asset : &Self::AsociatedAsset) -> f32;
+    
+    /// The name of the animation in its asset, like the tag of an aseprite animation. This is only used by `AnimatorDebug`.
+    fn debug_name(&self) -> Option<&str> { None }
+    
+    /// The index of the frame that is shown at the given progress, for animations made of frames. This is only used by `AnimatorDebug`.
+    fn debug_frame(&self, _progress : f32, _asset : &Self::AsociatedAsset) -> Option<usize> { None }
+    
+    /// Called by the `AnimationPlugin` for this animation. Use this to add any systems that the animation type needs.
+    fn build(_app : &mut App) {}
+}
+
+//=================================================================================
+//    Animation Backend
+//=================================================================================
+
+/// An animation backend supplies the `Animation` implementation for a whole family of animations, for example every type that
+/// implements `SpriteSheetAnimation`. Rust only allows one blanket implementation of `Animation`, so formats other than aseprite
+/// implement this trait on a marker type instead. Use the `impl_animation!` macro to implement `Animation` through a backend.
+pub trait AnimationBackend<A : Animation> {
+    
+    /// The asset that is associated with animations of this backend.
+    type AsociatedAsset : Asset;
+    
+    /// A query that will allow the animation to effect the component it is attached to.
+    type Query<'w, 's> : QueryData;
+    
+    /// See `Animation::apply`.
+    fn apply(
+        animator : &Animator<A>, 
+        items : &mut <Self::Query<'_, '_> as WorldQuery>::Item<'_>,
+        asset : &Self::AsociatedAsset,
+    );
+    
+    /// See `Animation::spawn`.
+    fn spawn(animation : Option<A>, world: &mut World, path : String, entity : Entity);
+    
+    /// See `Animation::spawn_layer`.
+    fn spawn_layer(animation : A, world : &mut World, path : String, entity : Entity) {
+        Self::spawn(Some(animation), world, path, entity)
+    }
+    
+    /// See `Animation::duration`.
+    fn duration(animation : &A, asset : &Self::AsociatedAsset) -> f32;
+    
+    /// See `Animation::debug_name`.
+    fn debug_name(_animation : &A) -> Option<&str> { None }
+    
+    /// See `Animation::debug_frame`.
+    fn debug_frame(_animation : &A, _progress : f32, _asset : &Self::AsociatedAsset) -> Option<usize> { None }
+    
+    /// See `Animation::build`.
+    fn build(_app : &mut App) {}
 }
 
 //=================================================================================
@@ -82,21 +268,46 @@ pub trait Animation : Sized {
 //=================================================================================
 
 /// This is the component that will animate the entity it is attached to based on the embeded animation. 
-/// It will hold the progress of the animation.
-#[derive(Component)]
+/// It will hold the progress of the animation, and the easing that remaps the progress before the animation is applied.
+///
+/// An animator can be cloned, reflected and, with the `serialize` feature, serialized to save and restore its state, for example
+/// for rollback. The easing is not reflected or serialized, since it can hold a function. Use `Animator::restore` to load a
+/// snapshot while keeping the easing.
+#[derive(Component, Clone, Reflect)]
+#[reflect(Component)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct Animator<A : Animation> {
     pub animation: A,
     pub speed : f32,
     progress : f32,
+    #[reflect(ignore)]
+    #[cfg_attr(feature = "serialize", serde(skip))]
+    easing : AnimatorEasing,
+    eased_progress : f32,
+    ticks : Option<AnimatorTicks>,
+    queue : VecDeque<QueuedAnimation<A>>,
+    queued_repititions : Option<u32>,
+    sync_start : SyncStart,
 }
 
+/// Counts the progress of an animator in whole ticks instead of adding up float deltas. See `Animator::with_tick_rate`.
+#[derive(Reflect, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct AnimatorTicks {
+    /// The number of ticks in a second of animation.
+    pub rate : u32,
+    /// The number of ticks the current animation has played.
+    pub elapsed : u64,
+    /// The millionths of a tick that have been played on top of `elapsed`, so that deltas shorter than a tick still add up.
+    pub subticks : u32,
+}
+
+/// The number of subticks in a tick. See `AnimatorTicks::subticks`.
+const SUBTICKS : u64 = 1_000_000;
+
 impl <A : Animation + Default> Default for Animator<A> {
     fn default() -> Self {
-        Animator {
-            animation : A::default(),
-            progress : 0.0,
-            speed : 1.0,
-        }
+        Animator::new(A::default())
     }
 }
 
@@ -107,21 +318,83 @@ impl <A : Animation> Animator<A> {
             animation: current_state,
             progress : 0.0,
             speed : 1.0,
+            easing : AnimatorEasing::default(),
+            eased_progress : 0.0,
+            ticks : None,
+            queue : VecDeque::new(),
+            queued_repititions : None,
+            sync_start : SyncStart::Join,
         }
     }
     
+    /// Counts the progress in whole ticks at the given rate. See `set_tick_rate`.
+    pub fn with_tick_rate(mut self, rate : u32) -> Self {
+        self.set_tick_rate(rate);
+        self
+    }
+    
+    /// Counts the progress in whole ticks at the given rate, instead of adding up float deltas every frame. Every update adds the
+    /// delta, times the speed, rounded to the nearest millionth of a tick, and the progress is worked out from the whole ticks.
+    /// The remainder carries over, so any frame rate plays at the right speed. Two peers that feed the same deltas get the same
+    /// frames, which is what rollback needs. Pair it with `AnimationTimeSource::Fixed` and a rate that divides evenly into the
+    /// fixed timestep, like 60 ticks at 60Hz, to land on a new tick every step. A rate of 0 goes back to float progress.
+    pub fn set_tick_rate(&mut self, rate : u32) {
+        self.ticks = (rate > 0).then_some(AnimatorTicks { rate, elapsed : 0, subticks : 0 });
+    }
+    
+    /// The ticks of the animator, if it counts its progress in ticks.
+    pub fn ticks(&self) -> Option<&AnimatorTicks> {
+        self.ticks.as_ref()
+    }
+    
+    /// Restores the state of the animator from a snapshot, keeping the current easing.
+    pub fn restore(&mut self, snapshot : Animator<A>) {
+        let easing = std::mem::take(&mut self.easing);
+        *self = Animator { easing, ..snapshot };
+    }
+    
+    /// Sets the easing that remaps the progress of the animation. See `set_easing`.
+    pub fn with_easing(mut self, easing : impl Into<AnimatorEasing>) -> Self {
+        self.set_easing(easing);
+        self
+    }
+    
+    /// Sets the easing that remaps the progress of the animation before it is applied. Each repitition is eased on its own.
+    pub fn set_easing(&mut self, easing : impl Into<AnimatorEasing>) {
+        self.easing = easing.into();
+        self.eased_progress = self.easing.ease(self.progress.fract(), None);
+    }
+    
+    /// The easing that remaps the progress of the animation.
+    pub fn easing(&self) -> &AnimatorEasing {
+        &self.easing
+    }
+    
     /// Sets the speed of the animation. 1.0 is normal speed, 0.5 is half speed, 2.0 is double speed, etc.
     pub fn set_animation(&mut self, animation : A) {
         self.animation = animation;
     }
     
-    /// Sets the animation's progress to 0.0.
+    /// Sets the animation's progress to 0.0. A member of a sync group starts over from the current clock of its group.
     pub fn reset(&mut self) {
         self.progress = 0.0;
+        if let Some(ticks) = &mut self.ticks { 
+            ticks.elapsed = 0;
+            ticks.subticks = 0;
+        }
+        self.sync_start = SyncStart::Restart;
+        self.eased_progress = self.easing.ease(0.0, None);
     }
     
-    /// Gets the progress of the animation. This is a value between 0.0 and 1.0.
+    /// Gets the progress of the animation after it has been remapped by the easing. This is usually a value between 0.0 and 1.0,
+    /// but some easings like `Easing::ElasticOut` overshoot.
+    #[allow(clippy::misnamed_getters)]
     pub fn progress(&self) -> f32 {
+        self.eased_progress
+    }
+    
+    /// Gets the progress of the animation before easing. This is a value between 0.0 and 1.0.
+    pub fn linear_progress(&self) -> f32 {
         self.progress.fract()
     }
     
@@ -135,5 +408,434 @@ impl <A : Animation> Animator<A> {
     pub fn total_progress(&self) -> f32 {
         self.progress
     }
+    
+    /// Adds an animation to the end of the queue, to play once the current animation finishes its repitition. Returns a builder
+    /// so more animations can be chained, for example `animator.queue(Attack1).then(Attack2).then_loop(Idle)`.
+    pub fn queue(&mut self, animation : A) -> AnimationQueueBuilder<'_, A> {
+        self.queue.push_back(QueuedAnimation { animation, repititions : Some(1) });
+        AnimationQueueBuilder { animator : self }
+    }
+    
+    /// The animations that are waiting to play, in order.
+    pub fn queued(&self) -> impl Iterator<Item = &QueuedAnimation<A>> {
+        self.queue.iter()
+    }
+    
+    /// Removes every animation from the queue. The current animation keeps playing.
+    pub fn clear_queue(&mut self) {
+        self.queue.clear();
+        self.queued_repititions = None;
+    }
+    
+    /// Clears the queue and plays the given animation from the start right away.
+    pub fn interrupt(&mut self, animation : A) {
+        self.clear_queue();
+        self.animation = animation;
+        self.reset();
+    }
+    
+    /// Advances the progress of the animator by `delta` seconds, or to the clock of its sync group, and moves through the queue
+    /// when a repitition ends. A duration of 0.0 or less doesn't progress.
+    fn advance(
+        &mut self, 
+        entity : Entity, 
+        delta : f32, 
+        duration : f32, 
+        sync_group : Option<&AnimationSyncGroup>, 
+        sync_groups : &mut AnimationSyncGroups, 
+        now : Duration,
+    ) -> Option<AnimationQueueEvent> {
+        if duration <= 0.0 { return None }
+        let repititions = self.repititions();
+        match sync_group {
+            Some(member) => {
+                let (before, elapsed) = sync_groups.advance(member.group, delta, now);
+                let rate = self.speed / duration;
+                let start = self.sync_start.resolve(before, elapsed, rate);
+                self.sync_start = SyncStart::At(start);
+                self.progress = (elapsed - start) * rate + member.offset.rem_euclid(1.0);
+            },
+            None => match &mut self.ticks {
+                Some(ticks) => {
+                    let subticks = (delta as f64 * self.speed as f64 * ticks.rate as f64 * SUBTICKS as f64).round().max(0.0) as u64;
+                    let subticks = subticks + ticks.subticks as u64;
+                    ticks.elapsed += subticks / SUBTICKS;
+                    ticks.subticks = (subticks % SUBTICKS) as u32;
+                    self.progress = (ticks.elapsed as f64 / ticks.rate as f64 / duration as f64) as f32;
+                },
+                None => self.progress += delta / duration * self.speed,
+            },
+        }
+        if self.repititions() > repititions { self.advance_queue(entity) } else { None }
+    }
+    
+    /// Called when the animator finishes a repitition. Moves on to the next queued animation if the current one is done.
+    fn advance_queue(&mut self, entity : Entity) -> Option<AnimationQueueEvent> {
+        if let Some(repititions) = self.queued_repititions {
+            if self.repititions() < repititions { return None }
+        }
+        match self.queue.pop_front() {
+            Some(next) => {
+                self.animation = next.animation;
+                self.queued_repititions = next.repititions;
+                self.reset();
+                Some(AnimationQueueEvent::Advanced { entity, remaining : self.queue.len() })
+            },
+            None => self.queued_repititions.take().map(|_| AnimationQueueEvent::Finished { entity }),
+        }
+    }
+}
+
+//=================================================================================
+//    Animator Debug
+//=================================================================================
+
+/// A read-only view of an animator for inspectors and debug overlays. Add it to an entity with an `Animator` and it is filled in
+/// every frame. Changes made to it are overwritten.
+#[derive(Component, Reflect, Clone, Debug, Default, PartialEq)]
+#[reflect(Component)]
+pub struct AnimatorDebug {
+    animation : Option<String>,
+    frame : Option<usize>,
+    repititions : u32,
+    progress : f32,
+    time_remaining : f32,
+}
+
+impl AnimatorDebug {
+    /// The name of the animation in its asset, like the tag of an aseprite animation, if the animation type reports one.
+    pub fn animation(&self) -> Option<&str> {
+        self.animation.as_deref()
+    }
+    
+    /// The index of the frame that is shown, if the animation type is made of frames.
+    pub fn frame(&self) -> Option<usize> {
+        self.frame
+    }
+    
+    /// The number of repititions the animation has gone through.
+    pub fn repititions(&self) -> u32 {
+        self.repititions
+    }
+    
+    /// The progress through the current repitition, before easing.
+    pub fn progress(&self) -> f32 {
+        self.progress
+    }
+    
+    /// The seconds left until the current repitition ends, or until the current step of the queue ends. This is infinite when the
+    /// animator is stopped.
+    pub fn time_remaining(&self) -> f32 {
+        self.time_remaining
+    }
+}
+
+//=================================================================================
+//    Animation Queue
+//=================================================================================
+
+/// An animation waiting in the queue of an `Animator`.
+#[derive(Reflect, Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct QueuedAnimation<A> {
+    pub animation : A,
+    /// How many repititions to play before moving on. `None` loops until something else is queued.
+    pub repititions : Option<u32>,
+}
+
+/// Chains animations onto the queue of an `Animator`. See `Animator::queue`.
+pub struct AnimationQueueBuilder<'a, A : Animation> {
+    animator : &'a mut Animator<A>,
+}
+
+impl <'a, A : Animation> AnimationQueueBuilder<'a, A> {
+    /// Queues an animation to play once, after the previous one.
+    pub fn then(self, animation : A) -> Self {
+        self.animator.queue.push_back(QueuedAnimation { animation, repititions : Some(1) });
+        self
+    }
+    
+    /// Sets how many times the last queued animation plays before moving on.
+    pub fn times(self, repititions : u32) -> Self {
+        if let Some(last) = self.animator.queue.back_mut() {
+            last.repititions = Some(repititions.max(1));
+        }
+        self
+    }
+    
+    /// Queues an animation that loops after the previous one, until something else is queued.
+    pub fn then_loop(self, animation : A) -> Self {
+        self.animator.queue.push_back(QueuedAnimation { animation, repititions : None });
+        self
+    }
 }
 
+/// Sent when an animator moves through its queue.
+#[derive(Event, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AnimationQueueEvent {
+    /// The next queued animation started playing on the entity. `remaining` is the number of animations still waiting.
+    Advanced { entity : Entity, remaining : usize },
+    /// The last queued animation finished its repititions and nothing else is queued. It keeps looping until the animation is changed.
+    Finished { entity : Entity },
+}
+
+
+//=================================================================================
+//    Tests
+//=================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[derive(Reflect, Clone, Copy, Debug, Default, PartialEq)]
+    #[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+    enum Step {
+        #[default]
+        Idle,
+        Attack,
+        Recover,
+    }
+    
+    impl Animation for Step {
+        type AsociatedAsset = Image;
+        type Query<'w, 's> = ();
+        fn apply(_animator : &Animator<Self>, _items : &mut (), _asset : &Image) {}
+        fn spawn(_animation : Option<Self>, _world : &mut World, _path : String, _entity : Entity) {}
+        fn duration(&self, _asset : &Image) -> f32 { 1.0 }
+    }
+    
+    /// Advances the animator by `delta` seconds of a one second animation, outside of any sync group.
+    fn advance(animator : &mut Animator<Step>, delta : f32) -> Option<AnimationQueueEvent> {
+        animator.advance(Entity::from_raw(0), delta, 1.0, None, &mut AnimationSyncGroups::default(), Duration::ZERO)
+    }
+    
+    fn queued(animator : &Animator<Step>) -> Vec<(Step, Option<u32>)> {
+        animator.queued().map(|queued| (queued.animation, queued.repititions)).collect()
+    }
+    
+    #[test]
+    fn builds_queue_in_order() {
+        let mut animator = Animator::new(Step::Idle);
+        animator.queue(Step::Attack).times(2).then(Step::Recover).then_loop(Step::Idle).times(0);
+        assert_eq!(queued(&animator), vec![(Step::Attack, Some(2)), (Step::Recover, Some(1)), (Step::Idle, Some(1))]);
+        
+        animator.queue(Step::Attack).then_loop(Step::Idle);
+        assert_eq!(queued(&animator)[3..], [(Step::Attack, Some(1)), (Step::Idle, None)]);
+    }
+    
+    #[test]
+    fn plays_queue_and_sends_events() {
+        let entity = Entity::from_raw(0);
+        let mut animator = Animator::new(Step::Idle);
+        animator.queue(Step::Attack).times(2).then(Step::Recover).then_loop(Step::Idle);
+        
+        assert_eq!(advance(&mut animator, 0.5), None);
+        assert_eq!(advance(&mut animator, 0.5), Some(AnimationQueueEvent::Advanced { entity, remaining : 2 }));
+        assert_eq!(animator.animation, Step::Attack);
+        assert_eq!(animator.total_progress(), 0.0);
+        
+        assert_eq!(advance(&mut animator, 1.0), None);
+        assert_eq!(animator.animation, Step::Attack);
+        assert_eq!(advance(&mut animator, 1.0), Some(AnimationQueueEvent::Advanced { entity, remaining : 1 }));
+        assert_eq!(animator.animation, Step::Recover);
+        
+        assert_eq!(advance(&mut animator, 1.0), Some(AnimationQueueEvent::Advanced { entity, remaining : 0 }));
+        assert_eq!(animator.animation, Step::Idle);
+        
+        // A looping animation never finishes.
+        assert_eq!(advance(&mut animator, 1.0), None);
+        assert_eq!(advance(&mut animator, 1.0), None);
+        assert_eq!(animator.repititions(), 2);
+    }
+    
+    #[test]
+    fn finishes_queue_once() {
+        let entity = Entity::from_raw(0);
+        let mut animator = Animator::new(Step::Idle);
+        animator.queue(Step::Attack);
+        
+        assert_eq!(advance(&mut animator, 1.0), Some(AnimationQueueEvent::Advanced { entity, remaining : 0 }));
+        assert_eq!(advance(&mut animator, 1.0), Some(AnimationQueueEvent::Finished { entity }));
+        assert_eq!(animator.animation, Step::Attack);
+        assert_eq!(advance(&mut animator, 1.0), None);
+    }
+    
+    /// Advances a member of group 0 by `delta` seconds of a one second animation, on the frame at `frame` milliseconds.
+    fn advance_synced(
+        animator : &mut Animator<Step>, 
+        member : AnimationSyncGroup, 
+        groups : &mut AnimationSyncGroups, 
+        delta : f32, 
+        frame : u64,
+    ) -> Option<AnimationQueueEvent> {
+        animator.advance(Entity::from_raw(0), delta, 1.0, Some(&member), groups, Duration::from_millis(frame))
+    }
+    
+    #[test]
+    fn shares_phase_with_group() {
+        let mut groups = AnimationSyncGroups::default();
+        let member = AnimationSyncGroup::new(0);
+        let mut first = Animator::new(Step::Idle);
+        let mut late = Animator::new(Step::Idle);
+        let mut ahead = Animator::new(Step::Idle);
+        for frame in 1..=5 {
+            advance_synced(&mut first, member, &mut groups, 0.25, frame * 250);
+        }
+        assert_eq!(first.total_progress(), 1.25);
+        
+        // Members that join late play in phase, but count their own repititions.
+        advance_synced(&mut late, member, &mut groups, 0.25, 1250);
+        advance_synced(&mut ahead, member.with_offset(1.5), &mut groups, 0.25, 1250);
+        assert_eq!(late.total_progress(), 0.25);
+        assert_eq!(ahead.total_progress(), 0.75);
+        
+        // The clock is only advanced once per frame, however many members read it.
+        advance_synced(&mut first, member, &mut groups, 0.25, 1500);
+        advance_synced(&mut late, member, &mut groups, 0.25, 1500);
+        assert_eq!(groups.elapsed(0), 1.5);
+        assert_eq!(first.linear_progress(), late.linear_progress());
+    }
+    
+    #[test]
+    fn interrupts_synced_member() {
+        let mut groups = AnimationSyncGroups::default();
+        let member = AnimationSyncGroup::new(0);
+        let mut other = Animator::new(Step::Idle);
+        let mut animator = Animator::new(Step::Idle);
+        for frame in 1..=3 {
+            advance_synced(&mut other, member, &mut groups, 0.25, frame * 250);
+            advance_synced(&mut animator, member, &mut groups, 0.25, frame * 250);
+        }
+        
+        animator.interrupt(Step::Attack);
+        advance_synced(&mut other, member, &mut groups, 0.25, 1000);
+        advance_synced(&mut animator, member, &mut groups, 0.25, 1000);
+        assert_eq!(animator.animation, Step::Attack);
+        assert_eq!(animator.total_progress(), 0.25);
+        advance_synced(&mut animator, member, &mut groups, 0.25, 1250);
+        assert_eq!(animator.total_progress(), 0.5);
+    }
+    
+    #[test]
+    fn plays_queue_in_sync_group() {
+        let entity = Entity::from_raw(0);
+        let mut groups = AnimationSyncGroups::default();
+        let member = AnimationSyncGroup::new(0);
+        let mut animator = Animator::new(Step::Idle);
+        animator.queue(Step::Attack).times(2).then(Step::Recover);
+        
+        let mut events = Vec::new();
+        for frame in 1..=20 {
+            events.extend(advance_synced(&mut animator, member, &mut groups, 0.25, frame * 250));
+            if frame == 4 { assert_eq!(animator.animation, Step::Attack) }
+            if frame == 11 { assert_eq!(animator.animation, Step::Attack) }
+        }
+        assert_eq!(events, vec![
+            AnimationQueueEvent::Advanced { entity, remaining : 1 },
+            AnimationQueueEvent::Advanced { entity, remaining : 0 },
+            AnimationQueueEvent::Finished { entity },
+        ]);
+        assert_eq!(animator.animation, Step::Recover);
+    }
+    
+    #[test]
+    fn clears_queue() {
+        let mut animator = Animator::new(Step::Idle);
+        animator.queue(Step::Attack).then(Step::Recover);
+        assert!(advance(&mut animator, 1.0).is_some());
+        
+        animator.clear_queue();
+        assert!(queued(&animator).is_empty());
+        assert_eq!(advance(&mut animator, 1.0), None);
+        assert_eq!(advance(&mut animator, 1.0), None);
+        assert_eq!(animator.animation, Step::Attack);
+    }
+    
+    #[test]
+    fn interrupts_queue() {
+        let mut animator = Animator::new(Step::Idle);
+        animator.queue(Step::Attack).then_loop(Step::Recover);
+        advance(&mut animator, 0.75);
+        
+        animator.interrupt(Step::Recover);
+        assert_eq!(animator.animation, Step::Recover);
+        assert_eq!(animator.total_progress(), 0.0);
+        assert!(queued(&animator).is_empty());
+        assert_eq!(advance(&mut animator, 1.0), None);
+        assert_eq!(animator.animation, Step::Recover);
+    }
+    
+    #[test]
+    fn carries_ticks_between_frames() {
+        let played = |animator : &Animator<Step>| animator.ticks().map_or(0.0, |ticks| ticks.elapsed as f64 + ticks.subticks as f64 / SUBTICKS as f64);
+        for (hertz, speed) in [(144, 1.0), (90, 1.0), (60, 1.0), (30, 1.0), (144, 0.5)] {
+            let mut animator = Animator::new(Step::Idle).with_tick_rate(60);
+            animator.speed = speed;
+            for frame in 1..=hertz * 2 {
+                advance(&mut animator, 1.0 / hertz as f32);
+                let expected = frame as f64 * 60.0 * speed as f64 / hertz as f64;
+                assert!((played(&animator) - expected).abs() < 0.001, "{} ticks instead of {expected} at {hertz}Hz", played(&animator));
+            }
+            let whole = animator.ticks().unwrap().elapsed;
+            assert_eq!(animator.total_progress(), whole as f32 / 60.0);
+        }
+        
+        let mut animator = Animator::new(Step::Idle).with_tick_rate(60);
+        advance(&mut animator, 1.0 / 144.0);
+        assert_eq!(animator.ticks().map(|ticks| ticks.elapsed), Some(0));
+        assert_eq!(animator.total_progress(), 0.0);
+        advance(&mut animator, 1.0 / 144.0);
+        assert_eq!(animator.ticks().map(|ticks| ticks.elapsed), Some(0));
+        advance(&mut animator, 1.0 / 144.0);
+        assert_eq!(animator.ticks().map(|ticks| ticks.elapsed), Some(1));
+        
+        animator.reset();
+        assert_eq!(animator.ticks(), Some(&AnimatorTicks { rate : 60, elapsed : 0, subticks : 0 }));
+    }
+    
+    #[test]
+    fn restores_snapshot_keeping_easing() {
+        let mut animator = Animator::new(Step::Idle).with_tick_rate(60).with_easing(crate::easing::Easing::QuadIn);
+        advance(&mut animator, 0.25);
+        let snapshot = animator.clone();
+        advance(&mut animator, 0.5);
+        
+        let mut restored = Animator::new(Step::Attack);
+        restored.restore(snapshot);
+        assert_eq!(restored.animation, Step::Idle);
+        assert_eq!(restored.total_progress(), 0.25);
+        assert_eq!(restored.ticks().map(|ticks| ticks.elapsed), Some(15));
+        assert!(matches!(restored.easing(), AnimatorEasing::Curve(crate::easing::Easing::Linear)));
+        
+        animator.restore(restored.clone());
+        assert_eq!(animator.total_progress(), 0.25);
+        assert!(matches!(animator.easing(), AnimatorEasing::Curve(crate::easing::Easing::QuadIn)));
+    }
+    
+    #[cfg(feature = "serialize")]
+    #[test]
+    fn serializes_animator() {
+        use bevy::asset::ron;
+        
+        let mut animator = Animator::new(Step::Idle).with_tick_rate(60);
+        animator.speed = 2.0;
+        animator.queue(Step::Attack).times(2).then_loop(Step::Recover);
+        advance(&mut animator, 0.1);
+        
+        let text = ron::to_string(&animator).unwrap();
+        let loaded : Animator<Step> = ron::from_str(&text).unwrap();
+        assert_eq!(loaded.animation, Step::Idle);
+        assert_eq!(loaded.speed, 2.0);
+        assert_eq!(loaded.total_progress(), animator.total_progress());
+        assert_eq!(loaded.ticks(), animator.ticks());
+        assert_eq!(queued(&loaded), queued(&animator));
+        
+        let queued_animation = QueuedAnimation { animation : Step::Recover, repititions : None };
+        let loaded : QueuedAnimation<Step> = ron::from_str(&ron::to_string(&queued_animation).unwrap()).unwrap();
+        assert_eq!((loaded.animation, loaded.repititions), (Step::Recover, None));
+        
+        let ticks = AnimatorTicks { rate : 60, elapsed : 12, subticks : 500_000 };
+        assert_eq!(ron::from_str::<AnimatorTicks>(&ron::to_string(&ticks).unwrap()).unwrap(), ticks);
+    }
+}
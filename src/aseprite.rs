@@ -3,11 +3,34 @@
 // Animation ID.
 //=================================================================================
 
+use std::fmt::Display;
 use asefile::AsepriteFile;
-use bevy::{asset::{AssetLoader, AsyncReadExt}, ecs::query::WorldQuery, prelude::{Vec2, *}, render::{render_asset::RenderAssetUsages, render_resource::{Extent3d, TextureDimension, TextureFormat}, texture::ImageSampler}, sprite::Anchor, utils::HashMap};
+use bevy::{asset::{AssetLoader, AsyncReadExt}, ecs::query::WorldQuery, prelude::{Vec2, *}, sprite::Anchor, utils::HashMap};
 use btree_range_map::RangeMap;
 
-use crate::animation::{Animation, Animator};
+use crate::{animation::{update_animators, Animation, AnimationSet, Animator}, util::frame_image};
+
+mod atlas_group;
+pub use atlas_group::{AsepriteAtlasGroups, DEFAULT_ATLAS_PAGE_SIZE};
+
+mod tilemap;
+pub use tilemap::{AsepriteTile, AsepriteTileGrid, AsepriteTilemap, AsepriteTileset};
+pub(crate) use tilemap::AsepriteTilesetData;
+
+#[cfg(feature = "cli")]
+mod inspect;
+#[cfg(feature = "cli")]
+pub use inspect::{AsepriteReport, AsepriteLayerReport, AsepriteTagReport};
+
+#[cfg(feature = "aseprite_processor")]
+mod processor;
+#[cfg(feature = "aseprite_processor")]
+pub use processor::{AsepriteBakeError, AsepriteSaver, BakedAsepriteLoader};
+
+#[cfg(feature = "aseprite_json")]
+mod json;
+#[cfg(feature = "aseprite_json")]
+pub use json::{AsepriteJsonLoader, AsepriteJsonLoaderError};
 
 //=================================================================================
 //    AsepriteAnimationPlugin
@@ -20,6 +43,23 @@ impl Plugin for AsepriteAnimationPlugin {
         app
             .init_asset_loader::<AsepriteLoader>()
             .init_asset::<Aseprite>()
+            .init_resource::<AsepriteAtlasGroups>()
+            .register_type::<AsepriteTileGrid>()
+            .add_systems(Update, atlas_group::update_atlas_groups)
+            .add_systems(PostUpdate, tilemap::update_tile_grids.in_set(AnimationSet::Animate))
+        ;
+        
+        #[cfg(feature = "aseprite_json")]
+        app
+            .init_asset_loader::<AsepriteJsonLoader>()
+        ;
+        
+        #[cfg(feature = "aseprite_processor")]
+        app
+            .init_asset_loader::<BakedAsepriteLoader>()
+            .register_asset_processor(bevy::asset::processor::LoadAndSave::<AsepriteLoader, AsepriteSaver>::from(AsepriteSaver))
+            .set_default_asset_processor::<bevy::asset::processor::LoadAndSave<AsepriteLoader, AsepriteSaver>>("aseprite")
+            .set_default_asset_processor::<bevy::asset::processor::LoadAndSave<AsepriteLoader, AsepriteSaver>>("ase")
         ;
     }
 }
@@ -36,13 +76,119 @@ pub struct Aseprite {
     image : Handle<Image>,
     duration : Vec<u32>,
     anims : HashMap<String, Anim>,
-    dimensions : UVec2
+    tags : Vec<AsepriteTag>,
+    slices : Vec<AsepriteSlice>,
+    dimensions : UVec2,
+    tilesets : Vec<AsepriteTileset>,
+    tilemaps : Vec<AsepriteTilemap>,
+    shared : Option<SharedAtlas>,
+}
+
+/// A named region of an aseprite file. Slices can change over the course of the animation, so they have a key for each frame
+/// that they change on.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct AsepriteSlice {
+    pub name : String,
+    pub keys : Vec<AsepriteSliceKey>,
+}
+
+/// The bounds of a slice starting at `frame`, in pixels from the top left of the sprite.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct AsepriteSliceKey {
+    pub frame : usize,
+    pub rect : IRect,
+    pub pivot : Option<IVec2>,
+}
+
+impl AsepriteSlice {
+    /// Returns the key that is active on the given frame.
+    pub fn key_at(&self, frame : usize) -> Option<&AsepriteSliceKey> {
+        self.keys.iter().rev().find(|key| key.frame <= frame).or(self.keys.first())
+    }
+}
+
+/// The location of an aseprite's frames inside of a shared atlas page. See `AsepriteAtlasGroups`.
+#[derive(Clone, Debug)]
+struct SharedAtlas {
+    image : Handle<Image>,
+    layout : Handle<TextureAtlasLayout>,
+    frame_indices : Vec<usize>,
+}
+
+impl Aseprite {
+    /// The image that sprites should draw from. This is the shared atlas page if the file is part of an atlas group.
+    pub fn atlas_image(&self) -> &Handle<Image> {
+        self.shared.as_ref().map_or(&self.image, |shared| &shared.image)
+    }
+    
+    /// The layout that sprites should use. This is the shared atlas layout if the file is part of an atlas group.
+    pub fn atlas_layout(&self) -> &Handle<TextureAtlasLayout> {
+        self.shared.as_ref().map_or(&self.layout, |shared| &shared.layout)
+    }
+    
+    /// Maps a frame of the aseprite file to its index in the layout returned by `atlas_layout`.
+    pub fn atlas_index(&self, frame : usize) -> usize {
+        self.shared.as_ref().and_then(|shared| shared.frame_indices.get(frame).copied()).unwrap_or(frame)
+    }
+    
+    /// The number of frames in the aseprite file.
+    pub fn frame_count(&self) -> usize {
+        self.duration.len()
+    }
+    
+    /// The size of each frame in pixels.
+    pub fn dimensions(&self) -> UVec2 {
+        self.dimensions
+    }
+    
+    /// All of the slices in the aseprite file.
+    pub fn slices(&self) -> &[AsepriteSlice] {
+        &self.slices
+    }
+    
+    /// Returns the slice with the given name.
+    pub fn slice(&self, name : &str) -> Option<&AsepriteSlice> {
+        self.slices.iter().find(|slice| slice.name == name)
+    }
+    
+    /// All of the tilesets in the aseprite file, in the order of their ids.
+    pub fn tilesets(&self) -> &[AsepriteTileset] {
+        &self.tilesets
+    }
+    
+    /// All of the tilemap layers in the aseprite file.
+    pub fn tilemaps(&self) -> &[AsepriteTilemap] {
+        &self.tilemaps
+    }
+    
+    /// Returns the tilemap layer with the given name.
+    pub fn tilemap(&self, name : &str) -> Option<&AsepriteTilemap> {
+        self.tilemaps.iter().find(|tilemap| tilemap.name == name)
+    }
+    
+    /// Returns the frame the whole file is on after playing for the given number of seconds, looping from the last frame back
+    /// to the first.
+    pub fn frame_at(&self, seconds : f32) -> usize {
+        let total : u32 = self.duration.iter().sum();
+        if total == 0 { return 0 }
+        let mut time = (seconds * 1000.0).rem_euclid(total as f32);
+        for (frame, duration) in self.duration.iter().enumerate() {
+            if time < *duration as f32 { return frame }
+            time -= *duration as f32;
+        }
+        self.duration.len() - 1
+    }
 }
 
 #[derive(Clone, Debug, Default, PartialEq)]
 struct Anim {
     pub frame_map : RangeMap<f32, usize>,
-    duration : f32
+    duration : f32,
+    /// The frames that are shown first and last, in the order the tag plays them.
+    first : usize,
+    last : usize,
 }
 
 //=================================================================================
@@ -58,7 +204,7 @@ impl AssetLoader for AsepriteLoader {
 
     type Settings = ();
 
-    type Error = asefile::AsepriteParseError;
+    type Error = AsepriteLoaderError;
 
     fn load<'a>(
         &'a self,
@@ -69,65 +215,255 @@ impl AssetLoader for AsepriteLoader {
         Box::pin(async move {
             let mut bytes = Vec::new();
             reader.read_to_end(&mut bytes).await?;
-            let aseprite = AsepriteFile::read(bytes.as_slice())?;
-            
+            AsepriteData::read(&bytes)?.finish(load_context)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["ase", "aseprite"]
+    }
+}
+
+/// The errors that can occur while loading an aseprite file.
+#[derive(Debug)]
+pub enum AsepriteLoaderError {
+    /// The file couldn't be read from the asset source.
+    Io(std::io::Error),
+    /// The file was read, but isn't a valid aseprite file. This includes files that are cut short.
+    Parse(asefile::AsepriteParseError),
+    /// The frames don't fit into a single texture atlas.
+    AtlasTooLarge { frames : usize, dimensions : UVec2 },
+    /// The file uses a color depth other than RGBA, grayscale or indexed.
+    UnsupportedColorMode(u16),
+    /// The file has no frames.
+    Empty,
+}
+
+impl Display for AsepriteLoaderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AsepriteLoaderError::Io(error) => write!(f, "Could not read aseprite file: {}", error),
+            AsepriteLoaderError::Parse(error) => write!(f, "Could not parse aseprite file: {}", error),
+            AsepriteLoaderError::AtlasTooLarge { frames, dimensions } => write!(f, "Could not fit {} frames of {}x{} into a texture atlas", frames, dimensions.x, dimensions.y),
+            AsepriteLoaderError::UnsupportedColorMode(depth) => write!(f, "Aseprite color depth of {} bits is not supported", depth),
+            AsepriteLoaderError::Empty => write!(f, "Aseprite file has no frames"),
+        }
+    }
+}
+
+impl std::error::Error for AsepriteLoaderError {}
+
+impl From<std::io::Error> for AsepriteLoaderError {
+    fn from(error: std::io::Error) -> Self {
+        AsepriteLoaderError::Io(error)
+    }
+}
+
+impl From<asefile::AsepriteParseError> for AsepriteLoaderError {
+    fn from(error: asefile::AsepriteParseError) -> Self {
+        // The file is parsed from memory, so an IO error from the parser means the file was cut short.
+        AsepriteLoaderError::Parse(error)
+    }
+}
+
+/// Everything that is needed to build an `Aseprite` asset. Each loader reads its file format into this, so the assets they
+/// produce are interchangeable.
+pub(crate) struct AsepriteData {
+    pub frames : Vec<Image>,
+    pub durations : Vec<u32>,
+    pub tags : Vec<AsepriteTag>,
+    pub slices : Vec<AsepriteSlice>,
+    pub dimensions : UVec2,
+    pub tilesets : Vec<AsepriteTilesetData>,
+    pub tilemaps : Vec<AsepriteTilemap>,
+}
+
+/// A tag as it is stored in the file, before it is turned into an animation.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct AsepriteTag {
+    pub name : String,
+    pub from : usize,
+    pub to : usize,
+    pub direction : AsepriteDirection,
+}
+
+/// Describes the order a tag's frames are played in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum AsepriteDirection {
+    /// Plays from the first frame to the last frame.
+    #[default]
+    Forward,
+    /// Plays from the last frame to the first frame.
+    Reverse,
+    /// Plays from the first frame to the last frame, then back again.
+    PingPong,
+    /// Plays from the last frame to the first frame, then back again.
+    PingPongReverse,
+}
+
+impl From<asefile::AnimationDirection> for AsepriteDirection {
+    fn from(direction : asefile::AnimationDirection) -> Self {
+        match direction {
+            asefile::AnimationDirection::Forward => AsepriteDirection::Forward,
+            asefile::AnimationDirection::Reverse => AsepriteDirection::Reverse,
+            asefile::AnimationDirection::PingPong => AsepriteDirection::PingPong,
+        }
+    }
+}
+
+impl AsepriteData {
+    /// Reads the frames, tags and slices of an aseprite file.
+    pub(crate) fn read(bytes : &[u8]) -> Result<Self, AsepriteLoaderError> {
+        // The header is checked first, as asefile reports these as generic parse errors.
+        let header_word = |offset : usize| bytes.get(offset..offset + 2).map(|word| u16::from_le_bytes([word[0], word[1]]));
+        if bytes.is_empty() { return Err(AsepriteLoaderError::Empty) }
+        if header_word(4) == Some(0xA5E0) {
+            if header_word(6) == Some(0) { return Err(AsepriteLoaderError::Empty) }
+            if let Some(depth) = header_word(12).filter(|depth| ![8, 16, 32].contains(depth)) {
+                return Err(AsepriteLoaderError::UnsupportedColorMode(depth))
+            }
+        }
+        
+        let aseprite = AsepriteFile::read(bytes)?;
+        
+        let dimensions = UVec2::new(aseprite.width() as u32, aseprite.height() as u32);
+        let mut frames = Vec::new();
+        let mut durations = Vec::new();
+        for frame_index in 0..aseprite.num_frames() {
+            let frame = aseprite.frame(frame_index);
+            durations.push(frame.duration());
+            frames.push(frame_image(dimensions, frame.image().into_vec()));
+        }
+        
+        let tags = (0..aseprite.num_tags())
+            .map(|tag_index| {
+                let tag = aseprite.tag(tag_index);
+                AsepriteTag {
+                    name : tag.name().to_string(),
+                    from : tag.from_frame() as usize,
+                    to : tag.to_frame() as usize,
+                    direction : tag.animation_direction().into(),
+                }
+            })
+            .collect();
+        
+        let slices = aseprite.slices().iter()
+            .map(|slice| AsepriteSlice {
+                name : slice.name.clone(),
+                keys : slice.keys.iter().map(|key| AsepriteSliceKey {
+                    frame : key.from_frame as usize,
+                    rect : IRect::from_corners(
+                        IVec2::new(key.origin.0, key.origin.1),
+                        IVec2::new(key.origin.0 + key.size.0 as i32, key.origin.1 + key.size.1 as i32),
+                    ),
+                    pivot : key.pivot.map(|(x, y)| IVec2::new(x, y)),
+                }).collect(),
+            })
+            .collect();
+        
+        let (tilesets, tilemaps) = tilemap::read_tilemaps(&aseprite)?;
+        
+        Ok(AsepriteData { frames, durations, tags, slices, dimensions, tilesets, tilemaps })
+    }
+    
+    /// Packs the frames into an atlas, adds the atlas and layout as labeled assets and builds the animations for each tag. The
+    /// atlas of each tileset is added as `tileset_<index>`, with its layout as `tileset_<index>_layout`.
+    pub(crate) fn finish(self, load_context : &mut bevy::asset::LoadContext) -> Result<Aseprite, AsepriteLoaderError> {
+        self.build(|image_label, layout_label, image, layout| {
             let image_loader = load_context.begin_labeled_asset();
             let layout_loader = load_context.begin_labeled_asset();
-            let mut atlas = TextureAtlasBuilder::default();
-            let mut frames = Vec::new();
-            let mut durations = Vec::new();
-            for frame_index in 0..aseprite.num_frames() {
-                let frame = aseprite.frame(frame_index);
-                let mut image = Image::new(
-                    Extent3d {
-                       width: aseprite.width() as u32,
-                       height: aseprite.height() as u32,
-                       depth_or_array_layers: 1,
-                   }, 
-                   TextureDimension::D2, 
-                   frame.image().into_vec(), 
-                   TextureFormat::Rgba8UnormSrgb, 
-                   RenderAssetUsages::all()
-                );
-                image.sampler = ImageSampler::nearest();
-                durations.push(frame.duration());
-                frames.push(image);
-            }
-            for image in frames.iter() { atlas.add_texture(None, image); }
-            let (layout, image) = atlas.finish().expect("Failed to build texture atlas.");
-            
             let loaded_image = image_loader.finish(image, None);
             let loaded_layout = layout_loader.finish(layout, None);
-            
-            let image_handle = load_context.add_loaded_labeled_asset("atlas", loaded_image);
-            let layout_handle = load_context.add_loaded_labeled_asset("layout", loaded_layout);
-            
-            let mut anims = HashMap::default();
-            for tag_index in 0..aseprite.num_tags() {
-                let tag = aseprite.tag(tag_index);
-                let duration = (tag.from_frame()..=tag.to_frame())
-                    .map(|index| durations[index as usize])
-                    .sum::<u32>();
-                let mut frame_map = RangeMap::new();
-                let mut last : f32 = 0.0;
-                for frame_index in tag.from_frame()..=tag.to_frame() {
-                    let current_duration = durations[frame_index as usize] as f32 / duration as f32;
-                    frame_map.insert(last..last + current_duration, frame_index as usize);
-                    last += current_duration;
-                }
-                let anim = Anim { frame_map, duration: duration as f32 / 1000.0 };
-                anims.insert(tag.name().to_string(), anim);
-            }
-            
-            let mut frame_map = RangeMap::new();
-            frame_map.insert(0.0..1.0, "test");
-            
-            Ok(Aseprite { layout: layout_handle, duration: durations, image: image_handle, anims, dimensions: UVec2::new(aseprite.width() as u32, aseprite.height() as u32) })
+            (
+                load_context.add_loaded_labeled_asset(image_label, loaded_image),
+                load_context.add_loaded_labeled_asset(layout_label, loaded_layout),
+            )
         })
     }
+    
+    /// Packs the frames into an atlas, in the order of the frames.
+    pub(crate) fn pack(&self) -> Result<(TextureAtlasLayout, Image), AsepriteLoaderError> {
+        let mut atlas = TextureAtlasBuilder::default();
+        for image in self.frames.iter() { atlas.add_texture(None, image); }
+        atlas.finish().map_err(|_| AsepriteLoaderError::AtlasTooLarge { frames : self.frames.len(), dimensions : self.dimensions })
+    }
+    
+    /// Packs the frames and tilesets into atlases and builds the animations for each tag. `add` stores each atlas and layout
+    /// under the given labels, and returns their handles.
+    pub(crate) fn build(
+        self, 
+        mut add : impl FnMut(String, String, Image, TextureAtlasLayout) -> (Handle<Image>, Handle<TextureAtlasLayout>),
+    ) -> Result<Aseprite, AsepriteLoaderError> {
+        let (layout, image) = self.pack()?;
+        let (image, layout) = add("atlas".to_string(), "layout".to_string(), image, layout);
+        let mut tilesets = Vec::new();
+        for (index, tileset) in self.tilesets.iter().enumerate() {
+            let (tileset_layout, tileset_image) = tileset.pack()?;
+            tilesets.push(add(format!("tileset_{}", index), format!("tileset_{}_layout", index), tileset_image, tileset_layout));
+        }
+        Ok(self.assemble(image, layout, tilesets))
+    }
+    
+    /// Builds the animations for each tag, around atlases that have already been packed. The frames and tiles are not used.
+    pub(crate) fn assemble(
+        self, 
+        image : Handle<Image>, 
+        layout : Handle<TextureAtlasLayout>, 
+        tilesets : Vec<(Handle<Image>, Handle<TextureAtlasLayout>)>,
+    ) -> Aseprite {
+        let anims = self.tags.iter()
+            .map(|tag| (tag.name.clone(), Anim::new(tag, &self.durations)))
+            .collect();
+        let tilesets = self.tilesets.into_iter()
+            .zip(tilesets)
+            .map(|(tileset, (image, layout))| tileset.assemble(image, layout))
+            .collect();
+        
+        Aseprite { 
+            layout, 
+            duration: self.durations, 
+            image, 
+            anims, 
+            tags: self.tags,
+            slices: self.slices,
+            dimensions: self.dimensions, 
+            tilesets,
+            tilemaps: self.tilemaps,
+            shared: None,
+        }
+    }
+}
 
-    fn extensions(&self) -> &[&str] {
-        &["ase", "aseprite"]
+impl Anim {
+    /// Builds the frame map for a tag. Each frame takes up a share of the 0.0 to 1.0 range based on its duration.
+    fn new(tag : &AsepriteTag, durations : &[u32]) -> Self {
+        let frames : Vec<usize> = match tag.direction {
+            AsepriteDirection::Forward => (tag.from..=tag.to).collect(),
+            AsepriteDirection::Reverse => (tag.from..=tag.to).rev().collect(),
+            AsepriteDirection::PingPong => (tag.from..=tag.to).chain((tag.from + 1..tag.to).rev()).collect(),
+            AsepriteDirection::PingPongReverse => (tag.from..=tag.to).rev().chain(tag.from + 1..tag.to).collect(),
+        };
+        let duration = frames.iter()
+            .map(|index| durations[*index])
+            .sum::<u32>();
+        let (first, last_frame) = (frames.first().copied().unwrap_or(tag.from), frames.last().copied().unwrap_or(tag.to));
+        let mut frame_map = RangeMap::new();
+        let mut last : f32 = 0.0;
+        for frame_index in frames {
+            let current_duration = durations[frame_index] as f32 / duration as f32;
+            frame_map.insert(last..last + current_duration, frame_index);
+            last += current_duration;
+        }
+        Anim { frame_map, duration: duration as f32 / 1000.0, first, last : last_frame }
+    }
+    
+    /// Returns the frame shown at the given progress. Eased progress can overshoot the 0.0 to 1.0 range, so progress before the
+    /// start shows the first frame of the tag, and progress past the end shows the last one.
+    fn frame(&self, progress : f32) -> usize {
+        if progress <= 0.0 { return self.first }
+        self.frame_map.get(progress).copied().unwrap_or(self.last)
     }
 }
 
@@ -148,45 +484,64 @@ pub trait AsepriteAnimation : Sized + FromWorld {
     
     /// This is the size of each frame of the animation. With asesprite, all frames are the same size.
     fn get_dimensions() -> UVec2;
+    
+    /// The atlas group that this animation's file should be packed into. Files in the same group share atlas pages, which
+    /// allows their sprites to be batched. Defaults to no group, so the file keeps its own atlas.
+    fn get_atlas_group() -> Option<&'static str> { None }
 }
 
 impl <A : AsepriteAnimation + Send + Sync + 'static> Animation for A {
     type AsociatedAsset = Aseprite;
 
-    type Query<'w, 's> = &'w mut TextureAtlas;
+    type Query<'w, 's> = (&'w mut TextureAtlas, &'w mut Handle<Image>);
 
     fn apply(
         animator : &Animator<Self>, 
         items : &mut <Self::Query<'_, '_> as WorldQuery>::Item<'_>, 
         asset : &Self::AsociatedAsset,
     ) {
-        items.layout = asset.layout.clone();
+        let (atlas, texture) = items;
+        if atlas.layout != *asset.atlas_layout() { atlas.layout = asset.atlas_layout().clone(); }
+        if **texture != *asset.atlas_image() { **texture = asset.atlas_image().clone(); }
         
         let tag = animator.animation.get_tag_name();
         if let Some(anim) = asset.anims.get(tag) {
-            let frame = anim.frame_map.get(animator.progress()).unwrap_or(&0);
-            items.index = *frame;
+            atlas.index = asset.atlas_index(anim.frame(animator.progress()));
         }
     }
     
     fn duration(&self, asset : &Self::AsociatedAsset) -> f32 {
-        asset.anims.get(self.get_tag_name()).unwrap().duration
+        asset.anims.get(self.get_tag_name()).map_or(0.0, |anim| anim.duration)
+    }
+    
+    fn debug_name(&self) -> Option<&str> {
+        Some(self.get_tag_name())
+    }
+    
+    fn debug_frame(&self, progress : f32, asset : &Self::AsociatedAsset) -> Option<usize> {
+        asset.anims.get(self.get_tag_name()).map(|anim| anim.frame(progress))
+    }
+    
+    fn build(app : &mut App) {
+        app
+            .add_systems(PostUpdate, sync_reloaded_animators::<Self>.before(update_animators::<Self>))
+        ;
     }
 
     fn spawn(animation : Option<Self>, world : &mut World, path : String, entity : Entity) {
         let animation_comp = animation.unwrap_or(Self::from_world(world));
         let asset_server = world.get_resource::<AssetServer>().unwrap();
         let animation : Handle<Self::AsociatedAsset> = asset_server.load(&path);
-        let image : Handle<Image> = asset_server.load(format!("{}#atlas", path));
-        let layout : Handle<TextureAtlasLayout> = asset_server.load(format!("{}#layout", path));
+        // Grouped files draw from a shared page once they are loaded, so their own atlas isn't kept alive by the sprite.
+        let (image, layout) = match Self::get_atlas_group() {
+            Some(_) => (Handle::default(), Handle::default()),
+            None => (asset_server.load(format!("{}#atlas", path)), asset_server.load(format!("{}#layout", path))),
+        };
+        if let Some(group) = Self::get_atlas_group() {
+            world.resource_mut::<AsepriteAtlasGroups>().add(group, animation.clone());
+        }
         
-        let anchor_origin = Vec2::new(-0.5, 0.5);
-        let anchor_pixel = Self::get_anchor_pixel();
-        let dimensions = Self::get_dimensions();
-        let anchor_x = anchor_pixel.x / dimensions.x as f32;
-        let anchor_y = anchor_pixel.y / dimensions.y as f32;
-        let anchor_offset = Vec2::new(anchor_x, -anchor_y);
-        let anchor = Anchor::Custom(anchor_origin + anchor_offset);
+        let anchor = anchor::<Self>(Self::get_dimensions());
         
         world.get_or_spawn(entity).unwrap()
             .insert(Animator::new(animation_comp))
@@ -202,4 +557,282 @@ impl <A : AsepriteAnimation + Send + Sync + 'static> Animation for A {
             })
         ;
     }
-}
\ No newline at end of file
+}
+
+/// Computes the sprite anchor for an animation from its anchor pixel and the size of its frames.
+pub(crate) fn anchor<A : AsepriteAnimation>(dimensions : UVec2) -> Anchor {
+    let anchor_origin = Vec2::new(-0.5, 0.5);
+    let anchor_pixel = A::get_anchor_pixel();
+    let anchor_x = anchor_pixel.x / dimensions.x as f32;
+    let anchor_y = anchor_pixel.y / dimensions.y as f32;
+    let anchor_offset = Vec2::new(anchor_x, -anchor_y);
+    Anchor::Custom(anchor_origin + anchor_offset)
+}
+
+//=================================================================================
+//    Aseprite Hot Reloading
+//=================================================================================
+
+/// The components of an aseprite animator that are updated when its file is reloaded.
+type ReloadedAnimator<A> = (&'static mut Animator<A>, &'static Handle<Aseprite>, &'static mut TextureAtlas, &'static mut Handle<Image>, &'static mut Sprite);
+
+/// When an aseprite file is loaded or modified, this system will reconcile every animator that uses it with the file. The
+/// anchor is recomputed from the size of the frames in the file, since `AsepriteAnimation::get_dimensions` is only a guess
+/// made before the file is loaded. On a reload, the atlas is also swapped and the frame index is clamped. If the current tag
+/// no longer exists, the animator is reset and will show the first frame until its animation is changed.
+pub(crate) fn sync_reloaded_animators<A : AsepriteAnimation + Send + Sync + 'static>(
+    mut events : EventReader<AssetEvent<Aseprite>>,
+    aseprites : Res<Assets<Aseprite>>,
+    mut animators : Query<ReloadedAnimator<A>>,
+) {
+    let mut loaded = Vec::new();
+    let mut modified = Vec::new();
+    for event in events.read() {
+        match event {
+            AssetEvent::Added { id } | AssetEvent::LoadedWithDependencies { id } => loaded.push(*id),
+            AssetEvent::Modified { id } => modified.push(*id),
+            _ => {}
+        }
+    }
+    if loaded.is_empty() && modified.is_empty() { return }
+    
+    let mut warned = Vec::new();
+    for (mut animator, handle, mut atlas, mut texture, mut sprite) in animators.iter_mut() {
+        let is_modified = modified.contains(&handle.id());
+        if !is_modified && !loaded.contains(&handle.id()) { continue }
+        let Some(asset) = aseprites.get(handle) else { continue };
+        
+        if asset.dimensions() != A::get_dimensions() && !warned.contains(&handle.id()) {
+            warn!(
+                "Aseprite frames are {}x{}, but the animation expects {}x{}. The anchor is placed for the frames in the file.",
+                asset.dimensions().x, asset.dimensions().y, A::get_dimensions().x, A::get_dimensions().y,
+            );
+            warned.push(handle.id());
+        }
+        let anchor = anchor::<A>(asset.dimensions());
+        if sprite.anchor != anchor { sprite.anchor = anchor; }
+        if !is_modified { continue }
+        
+        let tag = animator.animation.get_tag_name();
+        let frame = match asset.anims.get(tag) {
+            Some(anim) => anim.frame(animator.progress()),
+            None => {
+                warn!("Aseprite tag '{}' no longer exists after reload.", tag);
+                animator.reset();
+                0
+            }
+        };
+        
+        let last_frame = asset.frame_count().saturating_sub(1);
+        atlas.layout = asset.atlas_layout().clone();
+        atlas.index = asset.atlas_index(frame.min(last_frame));
+        *texture = asset.atlas_image().clone();
+    }
+}
+
+//=================================================================================
+//    Tests
+//=================================================================================
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use super::*;
+    use crate::testing::AnimationTestApp;
+    
+    fn tag(from : usize, to : usize, direction : AsepriteDirection) -> AsepriteTag {
+        AsepriteTag { name : String::new(), from, to, direction }
+    }
+    
+    #[test]
+    fn reads_knight() {
+        let data = AsepriteData::read(include_bytes!("../assets/knight.aseprite")).unwrap();
+        assert_eq!(data.dimensions, UVec2::new(48, 48));
+        assert_eq!(data.frames.len(), 10);
+        assert_eq!(data.tags.len(), 1);
+        assert_eq!(data.tags[0].name, "run");
+        
+        let anim = Anim::new(&data.tags[0], &data.durations);
+        assert_eq!(anim.duration, 1.0);
+        assert_eq!(anim.frame_map.get(0.0), Some(&0));
+        assert_eq!(anim.frame_map.get(0.55), Some(&5));
+        assert_eq!(anim.frame_map.get(0.95), Some(&9));
+    }
+    
+    #[test]
+    fn reads_character() {
+        let data = AsepriteData::read(include_bytes!("../assets/character.aseprite")).unwrap();
+        assert_eq!(data.dimensions, UVec2::new(16, 16));
+        assert_eq!(data.frames.len(), 80);
+        
+        let idle = data.tags.iter().find(|tag| tag.name == "idle-right").unwrap();
+        assert_eq!((idle.from, idle.to), (64, 67));
+        let anim = Anim::new(idle, &data.durations);
+        assert!((anim.duration - 0.4).abs() < 1e-6);
+        assert_eq!(anim.frame_map.get(0.1), Some(&64));
+        assert_eq!(anim.frame_map.get(0.3), Some(&65));
+        assert_eq!(anim.frame_map.get(0.8), Some(&67));
+    }
+    
+    #[test]
+    fn reads_tilemaps() {
+        let data = AsepriteData::read(include_bytes!("../assets/tiles.aseprite")).unwrap();
+        assert_eq!(data.tilesets.len(), 1);
+        assert_eq!(data.tilesets[0].name, "terrain");
+        assert_eq!(data.tilesets[0].tile_size, UVec2::new(8, 8));
+        assert_eq!(data.tilesets[0].tiles.len(), 3);
+        assert_eq!(data.tilemaps.len(), 1);
+        
+        let tilemap = &data.tilemaps[0];
+        assert_eq!((tilemap.name.as_str(), tilemap.tileset, tilemap.size), ("ground", 0, UVec2::new(2, 1)));
+        assert_eq!(tilemap.frames, vec![vec![Some(1), Some(2)], vec![Some(2), None]]);
+        assert_eq!(tilemap.tile(1, UVec2::new(0, 0)), Some(2));
+        assert_eq!(tilemap.tile(0, UVec2::new(2, 0)), None);
+        
+        let aseprite = data.build(|_, _, _, _| (Handle::default(), Handle::default())).unwrap();
+        assert_eq!(aseprite.tilesets().len(), 1);
+        assert_eq!(aseprite.frame_at(0.05), 0);
+        assert_eq!(aseprite.frame_at(0.15), 1);
+        assert_eq!(aseprite.frame_at(0.25), 1);
+        assert_eq!(aseprite.frame_at(0.35), 0);
+    }
+    
+    #[test]
+    fn reports_load_errors() {
+        assert!(matches!(AsepriteData::read(&[]), Err(AsepriteLoaderError::Empty)));
+        
+        let knight = include_bytes!("../assets/knight.aseprite");
+        let mut bytes = knight.to_vec();
+        bytes[12..14].copy_from_slice(&24u16.to_le_bytes());
+        assert!(matches!(AsepriteData::read(&bytes), Err(AsepriteLoaderError::UnsupportedColorMode(24))));
+        assert!(matches!(AsepriteData::read(&knight[..knight.len() / 2]), Err(AsepriteLoaderError::Parse(_))));
+        
+        let dimensions = UVec2::new(4096, 1);
+        let data = AsepriteData { frames : vec![frame_image(dimensions, vec![0; 4096 * 4])], durations : vec![100], tags : Vec::new(), slices : Vec::new(), dimensions, tilesets : Vec::new(), tilemaps : Vec::new() };
+        assert!(matches!(data.pack(), Err(AsepriteLoaderError::AtlasTooLarge { frames : 1, .. })));
+    }
+    
+    #[test]
+    fn frame_map_weights_frames_by_duration() {
+        let anim = Anim::new(&tag(0, 2, AsepriteDirection::Forward), &[100, 200, 100]);
+        assert_eq!(anim.duration, 0.4);
+        assert_eq!(anim.frame_map.get(0.2), Some(&0));
+        assert_eq!(anim.frame_map.get(0.3), Some(&1));
+        assert_eq!(anim.frame_map.get(0.7), Some(&1));
+        assert_eq!(anim.frame_map.get(0.8), Some(&2));
+    }
+    
+    #[test]
+    fn frame_map_follows_direction() {
+        let reverse = Anim::new(&tag(0, 2, AsepriteDirection::Reverse), &[100, 100, 100]);
+        assert_eq!(reverse.frame_map.get(0.1), Some(&2));
+        assert_eq!(reverse.frame_map.get(0.9), Some(&0));
+        
+        let ping_pong = Anim::new(&tag(0, 2, AsepriteDirection::PingPong), &[100, 200, 100]);
+        assert_eq!(ping_pong.duration, 0.6);
+        assert_eq!(ping_pong.frame_map.get(0.1), Some(&0));
+        assert_eq!(ping_pong.frame_map.get(0.3), Some(&1));
+        assert_eq!(ping_pong.frame_map.get(0.6), Some(&2));
+        assert_eq!(ping_pong.frame_map.get(0.9), Some(&1));
+        
+        let ping_pong_reverse = Anim::new(&tag(0, 2, AsepriteDirection::PingPongReverse), &[100, 200, 100]);
+        assert_eq!(ping_pong_reverse.duration, 0.6);
+        assert_eq!(ping_pong_reverse.frame_map.get(0.1), Some(&2));
+        assert_eq!(ping_pong_reverse.frame_map.get(0.3), Some(&1));
+        assert_eq!(ping_pong_reverse.frame_map.get(0.6), Some(&0));
+        assert_eq!(ping_pong_reverse.frame_map.get(0.9), Some(&1));
+    }
+    
+    #[derive(Clone, Copy, Debug, Default, PartialEq)]
+    enum Hero {
+        #[default]
+        Idle,
+    }
+    
+    impl AsepriteAnimation for Hero {
+        fn get_tag_name(&self) -> &str {
+            match self {
+                Hero::Idle => "idle-right",
+            }
+        }
+        fn get_anchor_pixel() -> Vec2 { Vec2::new(8.0, 8.0) }
+        fn get_dimensions() -> UVec2 { UVec2::new(16, 16) }
+    }
+    
+    /// Replaces an aseprite that has been added to the app, which sends `AssetEvent::Modified` like a hot reload does.
+    fn replace(test : &mut AnimationTestApp, aseprite : &Handle<Aseprite>, data : AsepriteData) -> (Handle<Image>, Handle<TextureAtlasLayout>) {
+        let world = &mut test.app.world;
+        let reloaded = data.build(|_, _, image, layout| (
+            world.resource_mut::<Assets<Image>>().add(image),
+            world.resource_mut::<Assets<TextureAtlasLayout>>().add(layout),
+        )).unwrap();
+        let handles = (reloaded.image.clone(), reloaded.layout.clone());
+        world.resource_mut::<Assets<Aseprite>>().insert(aseprite.id(), reloaded);
+        handles
+    }
+    
+    #[test]
+    fn reconciles_animators_on_reload() {
+        let mut test = AnimationTestApp::new();
+        test.add_animation::<Hero>();
+        let aseprite = test.load_aseprite(include_bytes!("../assets/character.aseprite"));
+        let entity = test.spawn_aseprite(Hero::Idle, aseprite.clone());
+        let anchor = |test : &AnimationTestApp| test.app.world.get::<Sprite>(entity).unwrap().anchor;
+        
+        // Asset events are sent at the end of an update, so they are handled on the next one.
+        test.advance(Duration::ZERO);
+        test.advance(Duration::from_millis(250));
+        assert_eq!(test.atlas_index(entity), 66);
+        assert_eq!(anchor(&test), Anchor::Custom(Vec2::ZERO));
+        
+        // The tag still exists, but with fewer and larger frames.
+        let dimensions = UVec2::new(32, 32);
+        let data = AsepriteData {
+            frames : vec![frame_image(dimensions, vec![0; 32 * 32 * 4]); 2],
+            durations : vec![100, 100],
+            tags : vec![AsepriteTag { name : "idle-right".to_string(), from : 0, to : 1, direction : AsepriteDirection::Forward }],
+            slices : Vec::new(),
+            dimensions,
+            tilesets : Vec::new(),
+            tilemaps : Vec::new(),
+        };
+        let (image, layout) = replace(&mut test, &aseprite, data);
+        test.advance_by(Duration::ZERO, 2);
+        assert_eq!(test.atlas_index(entity), 1);
+        assert_eq!(test.app.world.get::<TextureAtlas>(entity).unwrap().layout, layout);
+        assert_eq!(*test.app.world.get::<Handle<Image>>(entity).unwrap(), image);
+        assert_eq!(anchor(&test), Anchor::Custom(Vec2::new(-0.25, 0.25)));
+        
+        // The tag is gone, so the animator starts over on the first frame.
+        let (image, layout) = replace(&mut test, &aseprite, AsepriteData::read(include_bytes!("../assets/knight.aseprite")).unwrap());
+        test.advance_by(Duration::ZERO, 2);
+        assert_eq!(test.atlas_index(entity), 0);
+        assert_eq!(test.animator::<Hero>(entity).progress(), 0.0);
+        assert_eq!(test.app.world.get::<TextureAtlas>(entity).unwrap().layout, layout);
+        assert_eq!(*test.app.world.get::<Handle<Image>>(entity).unwrap(), image);
+        assert_eq!(anchor(&test), Anchor::Custom(Vec2::new(8.0 / 48.0 - 0.5, 0.5 - 8.0 / 48.0)));
+    }
+    
+    #[test]
+    fn overshooting_progress_stays_in_the_tag() {
+        let anim = Anim::new(&tag(4, 6, AsepriteDirection::Forward), &[100; 8]);
+        assert_eq!(anim.frame(-0.2), 4);
+        assert_eq!(anim.frame(0.5), 5);
+        assert_eq!(anim.frame(1.2), 6);
+        let reverse = Anim::new(&tag(4, 6, AsepriteDirection::Reverse), &[100; 8]);
+        assert_eq!((reverse.frame(-0.2), reverse.frame(1.2)), (6, 4));
+        
+        let mut test = AnimationTestApp::new();
+        test.add_animation::<Hero>();
+        let aseprite = test.load_aseprite(include_bytes!("../assets/character.aseprite"));
+        let entity = test.spawn_aseprite(Hero::Idle, aseprite);
+        test.animator_mut::<Hero>(entity).set_easing(crate::easing::Easing::ElasticOut);
+        
+        // Elastic out overshoots past 1.0 early on, which is past the end of the frame map.
+        test.advance(Duration::from_millis(40));
+        assert!(test.animator::<Hero>(entity).progress() > 1.0);
+        assert_eq!(test.atlas_index(entity), 67);
+        let debug_frame = Hero::Idle.debug_frame(test.animator::<Hero>(entity).progress(), test.app.world.resource::<Assets<Aseprite>>().iter().next().unwrap().1);
+        assert_eq!(debug_frame, Some(67));
+    }
+}
@@ -0,0 +1,436 @@
+//=================================================================================
+// Atlas groups pack the frames of many aseprite files into a few shared texture
+// atlases. Sprites that draw from the same atlas can be batched together, which
+// is not possible when every file brings its own '#atlas' image.
+//=================================================================================
+
+use bevy::{prelude::*, render::{render_asset::RenderAssetUsages, render_resource::{Extent3d, TextureDimension}, texture::{ImageSampler, TextureFormatPixelInfo}}, utils::{HashMap, HashSet}};
+
+use super::{Aseprite, SharedAtlas};
+
+//=================================================================================
+//    AsepriteAtlasGroups
+//=================================================================================
+
+/// The default maximum size of a single page in an atlas group.
+pub const DEFAULT_ATLAS_PAGE_SIZE : UVec2 = UVec2::new(4096, 4096);
+
+/// This resource holds all of the atlas groups. An atlas group is a named set of aseprite files whose frames are packed into
+/// shared atlas pages. Once a file has been placed on a page, it points its sprites at that page and the atlas it was loaded
+/// with is removed, so each frame is only kept in memory once.
+#[derive(Resource)]
+pub struct AsepriteAtlasGroups {
+    groups : HashMap<String, AtlasGroup>,
+    max_page_size : UVec2,
+    pub(crate) ignore_modified : HashSet<AssetId<Aseprite>>,
+    /// Files that were removed from their group and still have to be given an atlas of their own.
+    released : Vec<AssetId<Aseprite>>,
+}
+
+#[derive(Default)]
+struct AtlasGroup {
+    members : Vec<Handle<Aseprite>>,
+    pages : Vec<AtlasPage>,
+    /// Members that were added, loaded or reloaded and still have to be placed on a page.
+    changed : HashSet<AssetId<Aseprite>>,
+    /// Pages that have to be repacked, because a member was removed or the page size changed.
+    repack : HashSet<usize>,
+}
+
+struct AtlasPage {
+    image : Handle<Image>,
+    layout : Handle<TextureAtlasLayout>,
+    /// The members on this page, with the index of each of their frames in `layout`.
+    members : Vec<(AssetId<Aseprite>, Vec<usize>)>,
+}
+
+impl Default for AsepriteAtlasGroups {
+    fn default() -> Self {
+        AsepriteAtlasGroups {
+            groups : HashMap::default(),
+            max_page_size : DEFAULT_ATLAS_PAGE_SIZE,
+            ignore_modified : HashSet::default(),
+            released : Vec::new(),
+        }
+    }
+}
+
+impl AsepriteAtlasGroups {
+    /// Adds an aseprite file to a group. It is placed on a page once it has been loaded.
+    pub fn add(&mut self, group : impl Into<String>, aseprite : Handle<Aseprite>) {
+        let group = self.groups.entry(group.into()).or_default();
+        if group.members.iter().any(|member| member.id() == aseprite.id()) { return }
+        group.changed.insert(aseprite.id());
+        group.members.push(aseprite);
+    }
+
+    /// Removes an aseprite file from a group. Its page is repacked without it, and the aseprite is given an atlas of its own
+    /// again, with its frames copied out of the page.
+    pub fn remove(&mut self, group : &str, aseprite : impl Into<AssetId<Aseprite>>) {
+        let aseprite = aseprite.into();
+        let Some(group) = self.groups.get_mut(group) else { return };
+        group.members.retain(|member| member.id() != aseprite);
+        group.changed.remove(&aseprite);
+        if let Some(page) = group.page_of(aseprite) {
+            group.repack.insert(page);
+            self.released.push(aseprite);
+        }
+    }
+
+    /// Sets the maximum size of a single atlas page. Every page is repacked, and pages that no longer fit are split.
+    pub fn set_max_page_size(&mut self, size : UVec2) {
+        self.max_page_size = size;
+        for group in self.groups.values_mut() { group.repack.extend(0..group.pages.len()); }
+    }
+
+    /// Returns the name of the group the aseprite belongs to, if any.
+    pub fn group_of(&self, aseprite : impl Into<AssetId<Aseprite>>) -> Option<&str> {
+        let aseprite = aseprite.into();
+        self.groups.iter()
+            .find(|(_, group)| group.members.iter().any(|member| member.id() == aseprite))
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// Returns the image and layout of every page that has been built for a group.
+    pub fn pages(&self, group : &str) -> impl Iterator<Item = (&Handle<Image>, &Handle<TextureAtlasLayout>)> {
+        self.groups.get(group).into_iter().flat_map(|group| group.pages.iter().map(|page| (&page.image, &page.layout)))
+    }
+
+    fn mark_changed(&mut self, aseprite : AssetId<Aseprite>) {
+        for group in self.groups.values_mut() {
+            if group.members.iter().any(|member| member.id() == aseprite) { group.changed.insert(aseprite); }
+        }
+    }
+}
+
+impl AtlasGroup {
+    fn page_of(&self, aseprite : AssetId<Aseprite>) -> Option<usize> {
+        self.pages.iter().position(|page| page.members.iter().any(|(member, _)| *member == aseprite))
+    }
+}
+
+//=================================================================================
+//    Atlas Group Systems
+//=================================================================================
+
+/// This system places every member that was loaded or reloaded on a page of its group. A reloaded file whose frames didn't
+/// change size is copied over its old frames, so nothing is repacked. Otherwise only the page it is on, or the page it is
+/// added to, is repacked, and the other members of that page are copied out of the page instead of their own files.
+pub(crate) fn update_atlas_groups(
+    mut groups : ResMut<AsepriteAtlasGroups>,
+    mut events : EventReader<AssetEvent<Aseprite>>,
+    mut aseprites : ResMut<Assets<Aseprite>>,
+    mut images : ResMut<Assets<Image>>,
+    mut layouts : ResMut<Assets<TextureAtlasLayout>>,
+) {
+    for event in events.read() {
+        match event {
+            AssetEvent::Modified { id } => {
+                if groups.ignore_modified.remove(id) { continue }
+                groups.mark_changed(*id);
+            },
+            AssetEvent::Added { id } | AssetEvent::LoadedWithDependencies { id } => groups.mark_changed(*id),
+            _ => {}
+        }
+    }
+
+    // Removed files are copied out of their page before it is repacked without them.
+    for id in std::mem::take(&mut groups.released) {
+        if groups.group_of(id).is_some() { continue }
+        let Some(aseprite) = aseprites.get_mut(id) else { continue };
+        let Some(shared) = aseprite.shared.as_ref() else { continue };
+        let (Some(page_image), Some(page_layout)) = (images.get(&shared.image), layouts.get(&shared.layout)) else { continue };
+        let frames : Vec<Image> = shared.frame_indices.iter().map(|index| extract_frame(page_image, page_layout.textures[*index])).collect();
+        let mut builder = TextureAtlasBuilder::default();
+        for frame in frames.iter() { builder.add_texture(None, frame); }
+        match builder.finish() {
+            Ok((layout, image)) => {
+                aseprite.image = images.add(image);
+                aseprite.layout = layouts.add(layout);
+                aseprite.shared = None;
+                groups.ignore_modified.insert(id);
+            }
+            Err(error) => error!("Failed to rebuild the atlas of an aseprite removed from its atlas group: {:?}", error),
+        }
+    }
+
+    let max_page_size = groups.max_page_size;
+    let mut placed = Vec::new();
+    for (name, group) in groups.groups.iter_mut() {
+        if group.changed.is_empty() && group.repack.is_empty() { continue }
+
+        // The frames of each changed member that has been loaded, copied out of the atlas it was loaded with.
+        let mut fresh = HashMap::new();
+        for id in group.changed.iter() {
+            let Some(aseprite) = aseprites.get(*id) else { continue };
+            let (Some(image), Some(layout)) = (images.get(&aseprite.image), layouts.get(&aseprite.layout)) else { continue };
+            fresh.insert(*id, layout.textures.iter().map(|rect| extract_frame(image, *rect)).collect::<Vec<_>>());
+        }
+        group.changed.retain(|id| !fresh.contains_key(id));
+
+        for (id, frames) in fresh.iter() {
+            let Some(page_index) = group.page_of(*id) else { continue };
+            let page = &group.pages[page_index];
+            let frame_indices = page.members.iter().find(|(member, _)| member == id).map(|(_, indices)| indices.clone()).unwrap_or_default();
+            let same_size = layouts.get(&page.layout).is_some_and(|layout| {
+                frame_indices.len() == frames.len() && frame_indices.iter().zip(frames.iter())
+                    .all(|(index, frame)| layout.textures[*index].size().as_uvec2() == frame.size())
+            });
+            if !same_size {
+                group.repack.insert(page_index);
+                continue
+            }
+            let (Some(layout), Some(page_image)) = (layouts.get(&page.layout), images.get_mut(&page.image)) else { continue };
+            for (index, frame) in frame_indices.iter().zip(frames.iter()) { copy_frame(page_image, layout.textures[*index], frame); }
+            placed.push((*id, page.image.clone(), page.layout.clone(), frame_indices));
+        }
+
+        // New members go on the last page if there is room for them, otherwise on a new page.
+        let max_area = (max_page_size.x * max_page_size.y) as f32 * 0.75;
+        for member in group.members.iter() {
+            let id = member.id();
+            let Some(frames) = fresh.get(&id) else { continue };
+            if group.page_of(id).is_some() { continue }
+            let last_area = group.pages.last().map_or(f32::INFINITY, |page| {
+                let layout = layouts.get(&page.layout);
+                page.members.iter()
+                    .map(|(member, indices)| match fresh.get(member) {
+                        Some(frames) => frames_area(frames),
+                        None => layout.map_or(0.0, |layout| indices.iter().map(|index| layout.textures[*index].width() * layout.textures[*index].height()).sum()),
+                    })
+                    .sum::<f32>()
+            });
+            if last_area + frames_area(frames) > max_area {
+                group.pages.push(AtlasPage { image : Handle::default(), layout : Handle::default(), members : Vec::new() });
+            }
+            let page_index = group.pages.len() - 1;
+            group.pages[page_index].members.push((id, Vec::new()));
+            group.repack.insert(page_index);
+        }
+
+        let mut repack : Vec<usize> = group.repack.drain().collect();
+        repack.sort_unstable();
+        for page_index in repack.into_iter().rev() {
+            let page = group.pages.remove(page_index);
+            let members : Vec<(AssetId<Aseprite>, Vec<Image>)> = page.members.iter()
+                .filter(|(id, _)| group.members.iter().any(|member| member.id() == *id))
+                .filter_map(|(id, indices)| match fresh.get(id) {
+                    Some(frames) => Some((*id, frames.clone())),
+                    None => {
+                        let (image, layout) = (images.get(&page.image)?, layouts.get(&page.layout)?);
+                        Some((*id, indices.iter().map(|index| extract_frame(image, layout.textures[*index])).collect()))
+                    }
+                })
+                .collect();
+            for new_page in pack_pages(name, members, max_page_size, &mut images, &mut layouts) {
+                for (id, frame_indices) in new_page.members.iter() {
+                    placed.push((*id, new_page.image.clone(), new_page.layout.clone(), frame_indices.clone()));
+                }
+                group.pages.insert(page_index, new_page);
+            }
+        }
+    }
+
+    for (id, image, layout, frame_indices) in placed {
+        let Some(aseprite) = aseprites.get_mut(id) else { continue };
+        // The page owns the frames now, so the atlas the file was loaded with is no longer needed.
+        if aseprite.image != image { images.remove(&aseprite.image); }
+        if aseprite.layout != layout { layouts.remove(&aseprite.layout); }
+        aseprite.shared = Some(SharedAtlas { image, layout, frame_indices });
+        groups.ignore_modified.insert(id);
+    }
+}
+
+/// The number of pixels that some frames take up in an atlas.
+fn frames_area(frames : &[Image]) -> f32 {
+    frames.iter().map(|frame| (frame.width() * frame.height()) as f32).sum()
+}
+
+/// Packs the frames of some members into one page, or splits them over several pages if they don't fit into one.
+fn pack_pages(
+    name : &str,
+    members : Vec<(AssetId<Aseprite>, Vec<Image>)>,
+    max_page_size : UVec2,
+    images : &mut Assets<Image>,
+    layouts : &mut Assets<TextureAtlasLayout>,
+) -> Vec<AtlasPage> {
+    if members.is_empty() { return Vec::new() }
+    let mut builder = TextureAtlasBuilder::default()
+        .initial_size(max_page_size.as_vec2().min(Vec2::splat(256.0)))
+        .max_size(max_page_size.as_vec2());
+    for (_, frames) in members.iter() {
+        for frame in frames.iter() { builder.add_texture(None, frame); }
+    }
+    match builder.finish() {
+        Ok((layout, mut image)) => {
+            image.sampler = ImageSampler::nearest();
+            let mut next_index = 0;
+            let page_members = members.iter()
+                .map(|(id, frames)| {
+                    let frame_indices = (next_index..next_index + frames.len()).collect();
+                    next_index += frames.len();
+                    (*id, frame_indices)
+                })
+                .collect();
+            vec![AtlasPage { image : images.add(image), layout : layouts.add(layout), members : page_members }]
+        }
+        Err(error) if members.len() == 1 => {
+            error!("Failed to build page for aseprite atlas group '{}': {:?}", name, error);
+            Vec::new()
+        }
+        Err(_) => {
+            let mut first = members;
+            let second = first.split_off(first.len() / 2);
+            let mut pages = pack_pages(name, first, max_page_size, images, layouts);
+            pages.extend(pack_pages(name, second, max_page_size, images, layouts));
+            pages
+        }
+    }
+}
+
+/// Copies the pixels inside of `rect` out of an atlas image.
+fn extract_frame(atlas : &Image, rect : Rect) -> Image {
+    let format = atlas.texture_descriptor.format;
+    let pixel_size = format.pixel_size();
+    let stride = atlas.width() as usize * pixel_size;
+    let (min, max) = (rect.min.as_uvec2(), rect.max.as_uvec2());
+    let row_length = (max.x - min.x) as usize * pixel_size;
+
+    let mut data = Vec::with_capacity(row_length * (max.y - min.y) as usize);
+    for y in min.y..max.y {
+        let start = y as usize * stride + min.x as usize * pixel_size;
+        data.extend_from_slice(&atlas.data[start..start + row_length]);
+    }
+
+    Image::new(
+        Extent3d { width : max.x - min.x, height : max.y - min.y, depth_or_array_layers : 1 },
+        TextureDimension::D2,
+        data,
+        format,
+        RenderAssetUsages::all(),
+    )
+}
+
+/// Copies a frame into `rect` of an atlas image. The frame has to be the same size as `rect`.
+fn copy_frame(atlas : &mut Image, rect : Rect, frame : &Image) {
+    let pixel_size = atlas.texture_descriptor.format.pixel_size();
+    let stride = atlas.width() as usize * pixel_size;
+    let min = rect.min.as_uvec2();
+    let row_length = frame.width() as usize * pixel_size;
+    for y in 0..frame.height() as usize {
+        let start = (min.y as usize + y) * stride + min.x as usize * pixel_size;
+        atlas.data[start..start + row_length].copy_from_slice(&frame.data[y * row_length..(y + 1) * row_length]);
+    }
+}
+
+//=================================================================================
+//    Tests
+//=================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{aseprite::AsepriteData, testing::AnimationTestApp};
+    
+    fn reload(test : &mut AnimationTestApp, aseprite : &Handle<Aseprite>, bytes : &[u8]) {
+        let world = &mut test.app.world;
+        let reloaded = AsepriteData::read(bytes).unwrap().build(|_, _, image, layout| (
+            world.resource_mut::<Assets<Image>>().add(image),
+            world.resource_mut::<Assets<TextureAtlasLayout>>().add(layout),
+        )).unwrap();
+        world.resource_mut::<Assets<Aseprite>>().insert(aseprite.id(), reloaded);
+    }
+    
+    fn pages(test : &AnimationTestApp) -> Vec<AssetId<TextureAtlasLayout>> {
+        test.app.world.resource::<AsepriteAtlasGroups>().pages("units").map(|(_, layout)| layout.id()).collect()
+    }
+    
+    #[test]
+    fn shares_pages_that_fit() {
+        let mut test = AnimationTestApp::new();
+        let knight = test.load_aseprite(include_bytes!("../../assets/knight.aseprite"));
+        let character = test.load_aseprite(include_bytes!("../../assets/character.aseprite"));
+        let mut groups = test.app.world.resource_mut::<AsepriteAtlasGroups>();
+        groups.add("units", knight.clone());
+        groups.add("units", character.clone());
+        test.advance_by(std::time::Duration::ZERO, 2);
+        
+        assert_eq!(pages(&test).len(), 1);
+        let aseprites = test.app.world.resource::<Assets<Aseprite>>();
+        let character = aseprites.get(&character).unwrap();
+        assert_eq!(character.atlas_layout(), aseprites.get(&knight).unwrap().atlas_layout());
+        assert_eq!(character.atlas_index(0), 10);
+    }
+    
+    #[test]
+    fn only_repacks_changed_pages() {
+        let knight_bytes = include_bytes!("../../assets/knight.aseprite");
+        let character_bytes = include_bytes!("../../assets/character.aseprite");
+        let mut test = AnimationTestApp::new();
+        let knight = test.load_aseprite(knight_bytes);
+        let character = test.load_aseprite(character_bytes);
+        let mut groups = test.app.world.resource_mut::<AsepriteAtlasGroups>();
+        groups.set_max_page_size(UVec2::new(192, 192));
+        groups.add("units", knight.clone());
+        groups.add("units", character.clone());
+        test.advance_by(std::time::Duration::ZERO, 2);
+        
+        let placed = |test : &AnimationTestApp, aseprite : &Handle<Aseprite>| {
+            let aseprite = test.app.world.resource::<Assets<Aseprite>>().get(aseprite).unwrap();
+            let page = aseprite.shared.as_ref().map(|shared| shared.layout.id());
+            (page, test.app.world.resource::<Assets<Image>>().contains(&aseprite.image))
+        };
+        let before = pages(&test);
+        assert_eq!(before.len(), 2);
+        assert_eq!(placed(&test, &knight), (Some(before[0]), false));
+        assert_eq!(placed(&test, &character), (Some(before[1]), false));
+        
+        // Frames of the same size are copied over the old ones.
+        reload(&mut test, &knight, knight_bytes);
+        test.advance_by(std::time::Duration::ZERO, 2);
+        assert_eq!(pages(&test), before);
+        assert_eq!(placed(&test, &knight), (Some(before[0]), false));
+        
+        // Frames of a different size only repack the page they are on.
+        reload(&mut test, &knight, character_bytes);
+        test.advance_by(std::time::Duration::ZERO, 2);
+        let after = pages(&test);
+        assert_eq!(after.len(), 2);
+        assert!(!after.contains(&before[0]));
+        assert!(after.contains(&before[1]));
+        assert_eq!(placed(&test, &character), (Some(before[1]), false));
+        assert_eq!(test.app.world.resource::<Assets<Aseprite>>().get(&knight).unwrap().shared.as_ref().unwrap().frame_indices.len(), 80);
+    }
+    
+    #[test]
+    fn removed_files_get_their_own_atlas_back() {
+        let mut test = AnimationTestApp::new();
+        let knight = test.load_aseprite(include_bytes!("../../assets/knight.aseprite"));
+        let character = test.load_aseprite(include_bytes!("../../assets/character.aseprite"));
+        let mut groups = test.app.world.resource_mut::<AsepriteAtlasGroups>();
+        groups.add("units", knight.clone());
+        groups.add("units", character.clone());
+        test.advance_by(std::time::Duration::ZERO, 2);
+        let before = pages(&test);
+        
+        test.app.world.resource_mut::<AsepriteAtlasGroups>().remove("units", &character);
+        test.advance_by(std::time::Duration::ZERO, 2);
+        let after = pages(&test);
+        assert_eq!(after.len(), 1);
+        assert_ne!(after, before);
+        assert_eq!(test.app.world.resource::<AsepriteAtlasGroups>().group_of(&character), None);
+        
+        let aseprites = test.app.world.resource::<Assets<Aseprite>>();
+        let character = aseprites.get(&character).unwrap();
+        assert!(character.shared.is_none());
+        assert_eq!(character.atlas_index(10), 10);
+        let layout = test.app.world.resource::<Assets<TextureAtlasLayout>>().get(character.atlas_layout()).unwrap();
+        assert_eq!(layout.textures.len(), 80);
+        assert_eq!(layout.textures[0].size(), Vec2::new(16.0, 16.0));
+        assert!(test.app.world.resource::<Assets<Image>>().contains(character.atlas_image()));
+        
+        let knight = aseprites.get(&knight).unwrap();
+        assert_eq!(knight.shared.as_ref().unwrap().layout.id(), after[0]);
+        assert_eq!(knight.atlas_index(9), 9);
+    }
+}
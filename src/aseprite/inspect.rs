@@ -0,0 +1,189 @@
+//=================================================================================
+// Reports describe everything the aseprite loader reads from a file, along with
+// the parts of the file it doesn't use, like layers and user data. They are used
+// by the `aseprite-inspect` binary to check files without running a game.
+//=================================================================================
+
+use std::fmt::Display;
+use asefile::{AsepriteFile, UserData};
+use bevy::prelude::*;
+
+use super::{Anim, AsepriteData, AsepriteDirection, AsepriteLoaderError, AsepriteSlice};
+
+//=================================================================================
+//    Aseprite Report
+//=================================================================================
+
+/// A summary of an aseprite file, read with the same parsing as the `AsepriteLoader`.
+pub struct AsepriteReport {
+    pub dimensions : UVec2,
+    /// The duration of each frame in milliseconds.
+    pub frames : Vec<u32>,
+    pub tags : Vec<AsepriteTagReport>,
+    pub slices : Vec<AsepriteSlice>,
+    pub layers : Vec<AsepriteLayerReport>,
+    /// The user data in the file, next to a description of what it is attached to.
+    pub user_data : Vec<(String, String)>,
+    data : AsepriteData,
+}
+
+/// A tag of an aseprite file, and the duration of the clip the loader builds for it.
+pub struct AsepriteTagReport {
+    pub name : String,
+    pub from : usize,
+    pub to : usize,
+    pub direction : AsepriteDirection,
+    /// The duration of one repitition of the clip in seconds.
+    pub duration : f32,
+}
+
+/// A layer of an aseprite file. Layers are flattened by the loader, so they are only reported.
+pub struct AsepriteLayerReport {
+    pub name : String,
+    pub visible : bool,
+    pub opacity : u8,
+    pub blend_mode : String,
+}
+
+impl AsepriteReport {
+    /// Reads a report from the bytes of an aseprite file.
+    pub fn read(bytes : &[u8]) -> Result<Self, AsepriteLoaderError> {
+        let data = AsepriteData::read(bytes)?;
+        let file = AsepriteFile::read(bytes)?;
+        
+        let tags = data.tags.iter()
+            .map(|tag| AsepriteTagReport {
+                name : tag.name.clone(),
+                from : tag.from,
+                to : tag.to,
+                direction : tag.direction,
+                duration : Anim::new(tag, &data.durations).duration,
+            })
+            .collect();
+        
+        let layers = file.layers()
+            .map(|layer| AsepriteLayerReport {
+                name : layer.name().to_string(),
+                visible : layer.is_visible(),
+                opacity : layer.opacity(),
+                blend_mode : format!("{:?}", layer.blend_mode()),
+            })
+            .collect();
+        
+        let mut user_data = Vec::new();
+        for layer in file.layers() {
+            push_user_data(&mut user_data, format!("layer '{}'", layer.name()), layer.user_data());
+        }
+        for tag_index in 0..file.num_tags() {
+            let tag = file.tag(tag_index);
+            push_user_data(&mut user_data, format!("tag '{}'", tag.name()), tag.user_data());
+        }
+        for slice in file.slices() {
+            push_user_data(&mut user_data, format!("slice '{}'", slice.name), slice.user_data.as_ref());
+        }
+        for frame_index in 0..file.num_frames() {
+            let frame = file.frame(frame_index);
+            for layer in file.layers() {
+                let cel = frame.layer(layer.id());
+                push_user_data(&mut user_data, format!("cel {} of '{}'", frame_index, layer.name()), cel.user_data());
+            }
+        }
+        
+        Ok(AsepriteReport {
+            dimensions : data.dimensions,
+            frames : data.durations.clone(),
+            tags,
+            slices : data.slices.clone(),
+            layers,
+            user_data,
+            data,
+        })
+    }
+    
+    /// Packs the frames into the same atlas that the loader builds.
+    pub fn atlas(&self) -> Result<(TextureAtlasLayout, Image), AsepriteLoaderError> {
+        self.data.pack()
+    }
+}
+
+fn push_user_data(user_data : &mut Vec<(String, String)>, owner : String, data : Option<&UserData>) {
+    let Some(data) = data else { return };
+    let mut parts = Vec::new();
+    if let Some(text) = &data.text { parts.push(format!("\"{}\"", text)); }
+    if let Some(color) = &data.color {
+        let [r, g, b, a] = color.0;
+        parts.push(format!("#{:02x}{:02x}{:02x}{:02x}", r, g, b, a));
+    }
+    if !parts.is_empty() { user_data.push((owner, parts.join(" "))); }
+}
+
+impl Display for AsepriteReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "dimensions: {}x{}", self.dimensions.x, self.dimensions.y)?;
+        
+        writeln!(f, "frames: {}", self.frames.len())?;
+        for (index, duration) in self.frames.iter().enumerate() {
+            writeln!(f, "  {:>4}  {}ms", index, duration)?;
+        }
+        
+        writeln!(f, "tags: {}", self.tags.len())?;
+        for tag in self.tags.iter() {
+            writeln!(f, "  {}  frames {}..={}  {:?}  {:.3}s", tag.name, tag.from, tag.to, tag.direction, tag.duration)?;
+        }
+        
+        writeln!(f, "slices: {}", self.slices.len())?;
+        for slice in self.slices.iter() {
+            writeln!(f, "  {}", slice.name)?;
+            for key in slice.keys.iter() {
+                let size = key.rect.size();
+                write!(f, "    from frame {}  {},{} {}x{}", key.frame, key.rect.min.x, key.rect.min.y, size.x, size.y)?;
+                match key.pivot {
+                    Some(pivot) => writeln!(f, "  pivot {},{}", pivot.x, pivot.y)?,
+                    None => writeln!(f)?,
+                }
+            }
+        }
+        
+        writeln!(f, "layers: {}", self.layers.len())?;
+        for layer in self.layers.iter() {
+            let visibility = if layer.visible { "visible" } else { "hidden" };
+            writeln!(f, "  {}  {}  opacity {}  {}", layer.name, visibility, layer.opacity, layer.blend_mode)?;
+        }
+        
+        writeln!(f, "user data: {}", self.user_data.len())?;
+        for (owner, data) in self.user_data.iter() {
+            writeln!(f, "  {}  {}", owner, data)?;
+        }
+        Ok(())
+    }
+}
+
+//=================================================================================
+//    Tests
+//=================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn reports_tags_and_layers() {
+        let report = AsepriteReport::read(include_bytes!("../../assets/character.aseprite")).unwrap();
+        assert_eq!(report.dimensions, UVec2::new(16, 16));
+        assert_eq!(report.frames.len(), 80);
+        assert!(report.frames.iter().all(|&duration| duration == 100));
+        
+        assert_eq!(report.tags.len(), 12);
+        let walk = &report.tags[0];
+        assert_eq!((walk.name.as_str(), walk.from, walk.to, walk.direction), ("walk-right", 0, 7, AsepriteDirection::Forward));
+        assert!((walk.duration - 0.8).abs() < 1e-6);
+        let idle = report.tags.iter().find(|tag| tag.name == "idle-up").unwrap();
+        assert_eq!((idle.from, idle.to), (76, 79));
+        assert!((idle.duration - 0.4).abs() < 1e-6);
+        
+        assert_eq!(report.layers.len(), 1);
+        let layer = &report.layers[0];
+        assert_eq!((layer.name.as_str(), layer.visible, layer.opacity, layer.blend_mode.as_str()), ("Sprite Sheet", true, 255, "Normal"));
+        assert!(report.to_string().contains("idle-right  frames 64..=67  Forward  0.400s"));
+    }
+}
@@ -0,0 +1,241 @@
+//=================================================================================
+// Aseprite can export a sprite sheet as a PNG with a JSON file that describes the
+// frames, tags and slices. This loader reads that export into the same Aseprite
+// asset as the '.aseprite' loader, so the two can be used interchangeably.
+//=================================================================================
+
+use std::fmt::Display;
+use bevy::{asset::{AssetLoader, AsyncReadExt}, prelude::*};
+use serde::Deserialize;
+
+use crate::util::{frame_image, sheets::untrim_frame};
+use super::{Aseprite, AsepriteData, AsepriteDirection, AsepriteSlice, AsepriteSliceKey, AsepriteTag};
+
+//=================================================================================
+//    Aseprite JSON Format
+//=================================================================================
+
+#[derive(Deserialize)]
+struct AsepriteJson {
+    frames : AsepriteJsonFrames,
+    meta : AsepriteJsonMeta,
+}
+
+/// Aseprite can write frames either as an object keyed by file name (hash) or as a list (array).
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum AsepriteJsonFrames {
+    Hash(serde_json::Map<String, serde_json::Value>),
+    Array(Vec<AsepriteJsonFrame>),
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AsepriteJsonFrame {
+    frame : JsonRect,
+    #[serde(default)]
+    rotated : bool,
+    sprite_source_size : JsonRect,
+    source_size : JsonSize,
+    duration : u32,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AsepriteJsonMeta {
+    image : String,
+    #[serde(default)]
+    frame_tags : Vec<AsepriteJsonTag>,
+    #[serde(default)]
+    slices : Vec<AsepriteJsonSlice>,
+}
+
+#[derive(Deserialize)]
+struct AsepriteJsonTag {
+    name : String,
+    from : usize,
+    to : usize,
+    #[serde(default)]
+    direction : String,
+}
+
+#[derive(Deserialize)]
+struct AsepriteJsonSlice {
+    name : String,
+    keys : Vec<AsepriteJsonSliceKey>,
+}
+
+#[derive(Deserialize)]
+struct AsepriteJsonSliceKey {
+    frame : usize,
+    bounds : JsonRect,
+    pivot : Option<JsonPoint>,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+struct JsonRect { x : i32, y : i32, w : u32, h : u32 }
+
+#[derive(Deserialize, Clone, Copy)]
+struct JsonSize { w : u32, h : u32 }
+
+#[derive(Deserialize, Clone, Copy)]
+struct JsonPoint { x : i32, y : i32 }
+
+impl AsepriteJsonFrames {
+    fn into_frames(self) -> Result<Vec<AsepriteJsonFrame>, serde_json::Error> {
+        match self {
+            AsepriteJsonFrames::Array(frames) => Ok(frames),
+            AsepriteJsonFrames::Hash(frames) => frames.into_iter().map(|(_, frame)| serde_json::from_value(frame)).collect(),
+        }
+    }
+}
+
+impl AsepriteJsonTag {
+    /// Checks that the tag's frames exist and that its direction is one aseprite writes.
+    fn into_tag(self, frame_count : usize) -> Result<AsepriteTag, AsepriteJsonLoaderError> {
+        if self.from > self.to || self.to >= frame_count {
+            return Err(AsepriteJsonLoaderError::InvalidTag { tag : self.name, from : self.from, to : self.to, frames : frame_count })
+        }
+        let direction = match self.direction.as_str() {
+            "" | "forward" => AsepriteDirection::Forward,
+            "reverse" => AsepriteDirection::Reverse,
+            "pingpong" => AsepriteDirection::PingPong,
+            "pingpong_reverse" => AsepriteDirection::PingPongReverse,
+            _ => return Err(AsepriteJsonLoaderError::UnknownDirection { tag : self.name, direction : self.direction }),
+        };
+        Ok(AsepriteTag { name : self.name, from : self.from, to : self.to, direction })
+    }
+}
+
+//=================================================================================
+//    Aseprite JSON Loader
+//=================================================================================
+
+/// Asset Loader for sprite sheets exported from aseprite as JSON. The PNG is loaded from the `meta.image` path, relative to the
+/// JSON file. To keep it apart from other JSON files, the export has to be named `*.aseprite.json` or `*.ase.json`.
+#[derive(Default)]
+pub struct AsepriteJsonLoader;
+
+/// The errors that can occur while loading an aseprite JSON export.
+#[derive(Debug)]
+pub enum AsepriteJsonLoaderError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    ReadImage(bevy::asset::ReadAssetBytesError),
+    Image(image::ImageError),
+    Atlas(super::AsepriteLoaderError),
+    Empty,
+    InvalidTag { tag : String, from : usize, to : usize, frames : usize },
+    UnknownDirection { tag : String, direction : String },
+}
+
+impl Display for AsepriteJsonLoaderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AsepriteJsonLoaderError::Io(error) => write!(f, "Could not read aseprite JSON: {}", error),
+            AsepriteJsonLoaderError::Json(error) => write!(f, "Could not parse aseprite JSON: {}", error),
+            AsepriteJsonLoaderError::ReadImage(error) => write!(f, "Could not read aseprite sprite sheet: {}", error),
+            AsepriteJsonLoaderError::Image(error) => write!(f, "Could not decode aseprite sprite sheet: {}", error),
+            AsepriteJsonLoaderError::Atlas(error) => write!(f, "Could not pack aseprite sprite sheet: {}", error),
+            AsepriteJsonLoaderError::Empty => write!(f, "Aseprite JSON has no frames"),
+            AsepriteJsonLoaderError::InvalidTag { tag, from, to, frames } => write!(f, "Tag '{}' uses frames {}..={} but the sheet has {} frames", tag, from, to, frames),
+            AsepriteJsonLoaderError::UnknownDirection { tag, direction } => write!(f, "Tag '{}' has an unknown direction '{}'", tag, direction),
+        }
+    }
+}
+
+impl std::error::Error for AsepriteJsonLoaderError {}
+
+impl From<std::io::Error> for AsepriteJsonLoaderError {
+    fn from(error: std::io::Error) -> Self {
+        AsepriteJsonLoaderError::Io(error)
+    }
+}
+
+impl AssetLoader for AsepriteJsonLoader {
+    type Asset = Aseprite;
+
+    type Settings = ();
+
+    type Error = AsepriteJsonLoaderError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut bevy::asset::io::Reader,
+        _: &'a Self::Settings,
+        load_context: &'a mut bevy::asset::LoadContext,
+    ) -> bevy::utils::BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            let json : AsepriteJson = serde_json::from_slice(&bytes).map_err(AsepriteJsonLoaderError::Json)?;
+            let frames = json.frames.into_frames().map_err(AsepriteJsonLoaderError::Json)?;
+            let Some(first) = frames.first() else { return Err(AsepriteJsonLoaderError::Empty) };
+            let dimensions = UVec2::new(first.source_size.w, first.source_size.h);
+
+            let image_path = load_context.path().parent().map(|parent| parent.join(&json.meta.image)).unwrap_or(json.meta.image.clone().into());
+            let image_bytes = load_context.read_asset_bytes(image_path).await.map_err(AsepriteJsonLoaderError::ReadImage)?;
+            let sheet = image::load_from_memory(&image_bytes).map_err(AsepriteJsonLoaderError::Image)?.to_rgba8();
+
+            let durations = frames.iter().map(|frame| frame.duration).collect();
+            let frames : Vec<_> = frames.iter()
+                .map(|frame| {
+                    let rect = URect::new(frame.frame.x as u32, frame.frame.y as u32, (frame.frame.x as u32) + frame.frame.w, (frame.frame.y as u32) + frame.frame.h);
+                    let offset = IVec2::new(frame.sprite_source_size.x, frame.sprite_source_size.y);
+                    frame_image(dimensions, untrim_frame(&sheet, rect, frame.rotated, offset, dimensions).into_vec())
+                })
+                .collect();
+
+            let tags = json.meta.frame_tags.into_iter()
+                .map(|tag| tag.into_tag(frames.len()))
+                .collect::<Result<_, _>>()?;
+
+            let slices = json.meta.slices.into_iter()
+                .map(|slice| AsepriteSlice {
+                    name : slice.name,
+                    keys : slice.keys.into_iter().map(|key| AsepriteSliceKey {
+                        frame : key.frame,
+                        rect : IRect::new(key.bounds.x, key.bounds.y, key.bounds.x + key.bounds.w as i32, key.bounds.y + key.bounds.h as i32),
+                        pivot : key.pivot.map(|pivot| IVec2::new(pivot.x, pivot.y)),
+                    }).collect(),
+                })
+                .collect();
+
+            AsepriteData { frames, durations, tags, slices, dimensions, tilesets : Vec::new(), tilemaps : Vec::new() }.finish(load_context).map_err(AsepriteJsonLoaderError::Atlas)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["aseprite.json", "ase.json"]
+    }
+}
+
+//=================================================================================
+//    Tests
+//=================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn tag(from : usize, to : usize, direction : &str) -> AsepriteJsonTag {
+        AsepriteJsonTag { name : "run".to_string(), from, to, direction : direction.to_string() }
+    }
+    
+    #[test]
+    fn reads_tag_directions() {
+        assert_eq!(tag(0, 3, "").into_tag(4).unwrap().direction, AsepriteDirection::Forward);
+        assert_eq!(tag(0, 3, "forward").into_tag(4).unwrap().direction, AsepriteDirection::Forward);
+        assert_eq!(tag(0, 3, "reverse").into_tag(4).unwrap().direction, AsepriteDirection::Reverse);
+        assert_eq!(tag(0, 3, "pingpong").into_tag(4).unwrap().direction, AsepriteDirection::PingPong);
+        assert_eq!(tag(0, 3, "pingpong_reverse").into_tag(4).unwrap().direction, AsepriteDirection::PingPongReverse);
+        assert!(matches!(tag(0, 3, "sideways").into_tag(4), Err(AsepriteJsonLoaderError::UnknownDirection { .. })));
+    }
+    
+    #[test]
+    fn rejects_tags_outside_of_frames() {
+        assert!(tag(3, 3, "forward").into_tag(4).is_ok());
+        assert!(matches!(tag(0, 4, "forward").into_tag(4), Err(AsepriteJsonLoaderError::InvalidTag { to : 4, frames : 4, .. })));
+        assert!(matches!(tag(2, 1, "forward").into_tag(4), Err(AsepriteJsonLoaderError::InvalidTag { from : 2, .. })));
+    }
+}
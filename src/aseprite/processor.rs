@@ -0,0 +1,329 @@
+//=================================================================================
+// Baking turns an aseprite file into its packed atlas ahead of time, so that the
+// atlas doesn't have to be built at load time. The baked file holds the atlas and
+// tilesets as PNGs, with the layouts, tags, slices and tilemaps in a RON header.
+//=================================================================================
+
+use std::{fmt::Display, io::Cursor};
+use bevy::{
+    asset::{io::Writer, saver::{AssetSaver, SavedAsset}, AssetLoader, AsyncReadExt, AsyncWriteExt}, 
+    prelude::*, 
+    render::{render_asset::RenderAssetUsages, render_resource::{Extent3d, TextureDimension, TextureFormat}},
+};
+use serde::{Deserialize, Serialize};
+
+use super::{Aseprite, AsepriteData, AsepriteSlice, AsepriteTag, AsepriteTilemap, AsepriteTilesetData};
+
+/// The bytes every baked file starts with, followed by the version of the format.
+const MAGIC : &[u8; 8] = b"BAKEDASE";
+
+const VERSION : u32 = 2;
+
+//=================================================================================
+//    Baked Aseprite Format
+//=================================================================================
+
+/// Everything in an `Aseprite` asset but its atlas image.
+#[derive(Serialize, Deserialize)]
+struct BakedAseprite {
+    dimensions : UVec2,
+    durations : Vec<u32>,
+    tags : Vec<AsepriteTag>,
+    slices : Vec<AsepriteSlice>,
+    atlas_size : UVec2,
+    textures : Vec<URect>,
+    tilesets : Vec<BakedTileset>,
+    tilemaps : Vec<AsepriteTilemap>,
+}
+
+/// A tileset without its atlas image. The PNGs of the tilesets come before the PNG of the frame atlas, in this order.
+#[derive(Serialize, Deserialize)]
+struct BakedTileset {
+    name : String,
+    tile_size : UVec2,
+    atlas_size : UVec2,
+    textures : Vec<URect>,
+    png_length : u32,
+}
+
+/// The atlas and layout of each tileset, in the order of `Aseprite::tilesets`.
+type BakedAtlases = Vec<(Image, TextureAtlasLayout)>;
+
+/// The errors that can occur while baking an aseprite file, or loading a baked one.
+#[derive(Debug)]
+pub enum AsepriteBakeError {
+    Io(std::io::Error),
+    Ron(ron::Error),
+    Image(image::ImageError),
+    /// The asset being baked doesn't have its atlas image or layout.
+    MissingAtlas,
+    /// The file isn't a baked aseprite, or was baked by a different version.
+    InvalidHeader,
+}
+
+impl Display for AsepriteBakeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AsepriteBakeError::Io(error) => write!(f, "Could not read or write baked aseprite: {}", error),
+            AsepriteBakeError::Ron(error) => write!(f, "Could not parse baked aseprite header: {}", error),
+            AsepriteBakeError::Image(error) => write!(f, "Could not encode or decode baked aseprite atlas: {}", error),
+            AsepriteBakeError::MissingAtlas => write!(f, "Aseprite has no atlas to bake"),
+            AsepriteBakeError::InvalidHeader => write!(f, "File is not a baked aseprite of version {}", VERSION),
+        }
+    }
+}
+
+impl std::error::Error for AsepriteBakeError {}
+
+impl From<std::io::Error> for AsepriteBakeError {
+    fn from(error: std::io::Error) -> Self {
+        AsepriteBakeError::Io(error)
+    }
+}
+
+/// Encodes an atlas image as a PNG.
+fn encode_png(image : &Image) -> Result<Vec<u8>, AsepriteBakeError> {
+    let size = image.texture_descriptor.size;
+    let atlas = image::RgbaImage::from_raw(size.width, size.height, image.data.clone()).ok_or(AsepriteBakeError::MissingAtlas)?;
+    let mut png = Cursor::new(Vec::new());
+    atlas.write_to(&mut png, image::ImageFormat::Png).map_err(AsepriteBakeError::Image)?;
+    Ok(png.into_inner())
+}
+
+/// Decodes a PNG into an atlas image, as `TextureAtlasBuilder` would have built it.
+fn decode_png(bytes : &[u8]) -> Result<Image, AsepriteBakeError> {
+    let atlas = image::load_from_memory_with_format(bytes, image::ImageFormat::Png).map_err(AsepriteBakeError::Image)?.to_rgba8();
+    Ok(Image::new(
+        Extent3d { width : atlas.width(), height : atlas.height(), depth_or_array_layers : 1 },
+        TextureDimension::D2,
+        atlas.into_raw(),
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::all(),
+    ))
+}
+
+fn layout_from(size : UVec2, textures : Vec<URect>) -> TextureAtlasLayout {
+    let mut layout = TextureAtlasLayout::new_empty(size.as_vec2());
+    for rect in textures { layout.add_texture(rect.as_rect()); }
+    layout
+}
+
+/// Writes an aseprite asset and its atlases into the baked format. `tilesets` holds the atlas and layout of each tileset.
+fn bake(
+    aseprite : &Aseprite, 
+    image : &Image, 
+    layout : &TextureAtlasLayout, 
+    tilesets : &[(&Image, &TextureAtlasLayout)],
+) -> Result<Vec<u8>, AsepriteBakeError> {
+    let mut pngs = Vec::new();
+    let mut baked_tilesets = Vec::new();
+    for (tileset, (tileset_image, tileset_layout)) in aseprite.tilesets.iter().zip(tilesets) {
+        let png = encode_png(tileset_image)?;
+        baked_tilesets.push(BakedTileset {
+            name : tileset.name.clone(),
+            tile_size : tileset.tile_size,
+            atlas_size : tileset_layout.size.as_uvec2(),
+            textures : tileset_layout.textures.iter().map(|rect| rect.as_urect()).collect(),
+            png_length : png.len() as u32,
+        });
+        pngs.extend(png);
+    }
+    pngs.extend(encode_png(image)?);
+    
+    let size = image.texture_descriptor.size;
+    let header = BakedAseprite {
+        dimensions : aseprite.dimensions,
+        durations : aseprite.duration.clone(),
+        tags : aseprite.tags.clone(),
+        slices : aseprite.slices.clone(),
+        atlas_size : UVec2::new(size.width, size.height),
+        textures : layout.textures.iter().map(|rect| rect.as_urect()).collect(),
+        tilesets : baked_tilesets,
+        tilemaps : aseprite.tilemaps.clone(),
+    };
+    let header = ron::to_string(&header).map_err(AsepriteBakeError::Ron)?;
+    
+    let mut bytes = Vec::with_capacity(MAGIC.len() + 8 + header.len() + pngs.len());
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&VERSION.to_le_bytes());
+    bytes.extend_from_slice(&(header.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(header.as_bytes());
+    bytes.extend_from_slice(&pngs);
+    Ok(bytes)
+}
+
+/// Reads the baked format back into the data of an aseprite, its atlas, its layout and the atlases of its tilesets.
+fn unbake(bytes : &[u8]) -> Result<(AsepriteData, Image, TextureAtlasLayout, BakedAtlases), AsepriteBakeError> {
+    let read_u32 = |offset : usize| bytes.get(offset..offset + 4).map(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]]));
+    if bytes.get(..MAGIC.len()) != Some(MAGIC) || read_u32(MAGIC.len()) != Some(VERSION) {
+        return Err(AsepriteBakeError::InvalidHeader)
+    }
+    let header_start = MAGIC.len() + 8;
+    let header_end = header_start + read_u32(MAGIC.len() + 4).ok_or(AsepriteBakeError::InvalidHeader)? as usize;
+    let header = bytes.get(header_start..header_end).ok_or(AsepriteBakeError::InvalidHeader)?;
+    let header : BakedAseprite = ron::de::from_bytes(header).map_err(|error| AsepriteBakeError::Ron(error.code))?;
+    
+    let mut png_start = header_end;
+    let mut tilesets = Vec::new();
+    let mut tileset_atlases = Vec::new();
+    for tileset in header.tilesets {
+        let png_end = png_start + tileset.png_length as usize;
+        let png = bytes.get(png_start..png_end).ok_or(AsepriteBakeError::InvalidHeader)?;
+        tileset_atlases.push((decode_png(png)?, layout_from(tileset.atlas_size, tileset.textures)));
+        tilesets.push(AsepriteTilesetData { name : tileset.name, tile_size : tileset.tile_size, tiles : Vec::new() });
+        png_start = png_end;
+    }
+    let image = decode_png(&bytes[png_start..])?;
+    let layout = layout_from(header.atlas_size, header.textures);
+    
+    let data = AsepriteData {
+        frames : Vec::new(),
+        durations : header.durations,
+        tags : header.tags,
+        slices : header.slices,
+        dimensions : header.dimensions,
+        tilesets,
+        tilemaps : header.tilemaps,
+    };
+    Ok((data, image, layout, tileset_atlases))
+}
+
+//=================================================================================
+//    Aseprite Saver
+//=================================================================================
+
+/// Saves an `Aseprite` asset in the baked format, which is loaded by the `BakedAsepriteLoader`. This is registered as the default
+/// processor for `.aseprite` and `.ase` files, so when the app runs with `AssetMode::Processed` and bevy's `asset_processor`
+/// feature, aseprite files are baked into the processed folder and the game loads them without packing an atlas.
+#[derive(Default)]
+pub struct AsepriteSaver;
+
+impl AssetSaver for AsepriteSaver {
+    type Asset = Aseprite;
+
+    type Settings = ();
+
+    type OutputLoader = BakedAsepriteLoader;
+
+    type Error = AsepriteBakeError;
+
+    fn save<'a>(
+        &'a self,
+        writer : &'a mut Writer,
+        asset : SavedAsset<'a, Self::Asset>,
+        _ : &'a Self::Settings,
+    ) -> bevy::utils::BoxedFuture<'a, Result<(), Self::Error>> {
+        Box::pin(async move {
+            let image = asset.get_labeled::<Image, _>("atlas").ok_or(AsepriteBakeError::MissingAtlas)?;
+            let layout = asset.get_labeled::<TextureAtlasLayout, _>("layout").ok_or(AsepriteBakeError::MissingAtlas)?;
+            let mut tilesets = Vec::new();
+            for index in 0..asset.tilesets.len() {
+                let tileset_image = asset.get_labeled::<Image, _>(format!("tileset_{}", index).as_str()).ok_or(AsepriteBakeError::MissingAtlas)?;
+                let tileset_layout = asset.get_labeled::<TextureAtlasLayout, _>(format!("tileset_{}_layout", index).as_str()).ok_or(AsepriteBakeError::MissingAtlas)?;
+                tilesets.push((tileset_image.get(), tileset_layout.get()));
+            }
+            let bytes = bake(&asset, &image, &layout, &tilesets)?;
+            writer.write_all(&bytes).await?;
+            Ok(())
+        })
+    }
+}
+
+//=================================================================================
+//    Baked Aseprite Loader
+//=================================================================================
+
+/// Asset Loader for aseprite files that were baked by the `AsepriteSaver`. The asset it produces is the same as the one the
+/// `AsepriteLoader` produces, with the same `atlas` and `layout` labels. Baked files keep the extension of the source file,
+/// so this loader is picked by the meta file the processor writes, and has no extensions of its own.
+#[derive(Default)]
+pub struct BakedAsepriteLoader;
+
+impl AssetLoader for BakedAsepriteLoader {
+    type Asset = Aseprite;
+
+    type Settings = ();
+
+    type Error = AsepriteBakeError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut bevy::asset::io::Reader,
+        _: &'a Self::Settings,
+        load_context: &'a mut bevy::asset::LoadContext,
+    ) -> bevy::utils::BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            let (data, image, layout, tileset_atlases) = unbake(&bytes)?;
+            let image = load_context.add_labeled_asset("atlas".to_string(), image);
+            let layout = load_context.add_labeled_asset("layout".to_string(), layout);
+            let tilesets = tileset_atlases.into_iter()
+                .enumerate()
+                .map(|(index, (image, layout))| (
+                    load_context.add_labeled_asset(format!("tileset_{}", index), image),
+                    load_context.add_labeled_asset(format!("tileset_{}_layout", index), layout),
+                ))
+                .collect();
+            Ok(data.assemble(image, layout, tilesets))
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &[]
+    }
+}
+
+//=================================================================================
+//    Tests
+//=================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn baking_round_trips() {
+        let data = AsepriteData::read(include_bytes!("../../assets/character.aseprite")).unwrap();
+        let (layout, image) = data.pack().unwrap();
+        let aseprite = data.assemble(Handle::default(), Handle::default(), Vec::new());
+        
+        let bytes = bake(&aseprite, &image, &layout, &[]).unwrap();
+        let (baked_data, baked_image, baked_layout, _) = unbake(&bytes).unwrap();
+        let baked = baked_data.assemble(Handle::default(), Handle::default(), Vec::new());
+        
+        assert_eq!(baked.anims, aseprite.anims);
+        assert_eq!(baked.tags, aseprite.tags);
+        assert_eq!(baked.slices, aseprite.slices);
+        assert_eq!(baked.duration, aseprite.duration);
+        assert_eq!(baked.dimensions, aseprite.dimensions);
+        assert_eq!(baked_image.data, image.data);
+        assert_eq!(baked_image.texture_descriptor.size, image.texture_descriptor.size);
+        assert_eq!(baked_layout.size, layout.size);
+        assert_eq!(baked_layout.textures, layout.textures);
+    }
+    
+    #[test]
+    fn baking_keeps_tilemaps() {
+        let data = AsepriteData::read(include_bytes!("../../assets/tiles.aseprite")).unwrap();
+        let (layout, image) = data.pack().unwrap();
+        let (tileset_layout, tileset_image) = data.tilesets[0].pack().unwrap();
+        let aseprite = data.assemble(Handle::default(), Handle::default(), vec![(Handle::default(), Handle::default())]);
+        
+        let bytes = bake(&aseprite, &image, &layout, &[(&tileset_image, &tileset_layout)]).unwrap();
+        let (baked_data, baked_image, _, baked_tilesets) = unbake(&bytes).unwrap();
+        let baked = baked_data.assemble(Handle::default(), Handle::default(), vec![(Handle::default(), Handle::default())]);
+        
+        assert_eq!(baked.tilemaps, aseprite.tilemaps);
+        assert_eq!(baked.tilesets[0].name, "terrain");
+        assert_eq!(baked.tilesets[0].tile_size, UVec2::new(8, 8));
+        assert_eq!(baked_image.data, image.data);
+        assert_eq!(baked_tilesets[0].0.data, tileset_image.data);
+        assert_eq!(baked_tilesets[0].1.textures, tileset_layout.textures);
+    }
+    
+    #[test]
+    fn rejects_other_files() {
+        assert!(matches!(unbake(include_bytes!("../../assets/knight.aseprite")), Err(AsepriteBakeError::InvalidHeader)));
+    }
+}
@@ -0,0 +1,216 @@
+//=================================================================================
+// Tilemaps are the tilemap layers of an aseprite file. Each tileset is packed into
+// its own atlas, and each tilemap layer keeps the tile in every cell for every
+// frame, so a tile grid can play the layer back with the frame durations.
+//=================================================================================
+
+use asefile::{AsepriteFile, LayerType};
+use bevy::prelude::*;
+
+use crate::{time::AnimationTime, util::frame_image};
+use super::{Aseprite, AsepriteLoaderError};
+
+//=================================================================================
+//    Tilesets and Tilemaps
+//=================================================================================
+
+/// A tileset of an aseprite file. Its tiles are packed into an atlas in the order of their ids, so the id of a tile is also its
+/// index in `layout`.
+#[derive(Clone, Debug)]
+pub struct AsepriteTileset {
+    pub name : String,
+    pub tile_size : UVec2,
+    pub image : Handle<Image>,
+    pub layout : Handle<TextureAtlasLayout>,
+}
+
+/// A tilemap layer of an aseprite file. The tiles of each frame are stored row by row from the top left, and empty cells are `None`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct AsepriteTilemap {
+    /// The name of the layer.
+    pub name : String,
+    /// The index of the tileset in `Aseprite::tilesets`.
+    pub tileset : usize,
+    /// The size of the layer in tiles.
+    pub size : UVec2,
+    pub frames : Vec<Vec<Option<u32>>>,
+}
+
+impl AsepriteTilemap {
+    /// Returns the tile at the given cell on the given frame.
+    pub fn tile(&self, frame : usize, cell : UVec2) -> Option<u32> {
+        if cell.x >= self.size.x || cell.y >= self.size.y { return None }
+        self.frames.get(frame)?.get((cell.y * self.size.x + cell.x) as usize).copied().flatten()
+    }
+}
+
+/// A tileset as it is stored in the file, before its tiles are packed into an atlas.
+pub(crate) struct AsepriteTilesetData {
+    pub name : String,
+    pub tile_size : UVec2,
+    pub tiles : Vec<Image>,
+}
+
+impl AsepriteTilesetData {
+    /// Packs the tiles into an atlas, in the order of their ids.
+    pub(crate) fn pack(&self) -> Result<(TextureAtlasLayout, Image), AsepriteLoaderError> {
+        let mut atlas = TextureAtlasBuilder::default();
+        for image in self.tiles.iter() { atlas.add_texture(None, image); }
+        atlas.finish().map_err(|_| AsepriteLoaderError::AtlasTooLarge { frames : self.tiles.len(), dimensions : self.tile_size })
+    }
+    
+    pub(crate) fn assemble(self, image : Handle<Image>, layout : Handle<TextureAtlasLayout>) -> AsepriteTileset {
+        AsepriteTileset { name : self.name, tile_size : self.tile_size, image, layout }
+    }
+}
+
+/// Reads the tilesets and tilemap layers of an aseprite file. Tilesets are sorted by their id.
+pub(crate) fn read_tilemaps(file : &AsepriteFile) -> Result<(Vec<AsepriteTilesetData>, Vec<AsepriteTilemap>), AsepriteLoaderError> {
+    let mut sets : Vec<_> = file.tilesets().iter().collect();
+    sets.sort_by_key(|tileset| tileset.id());
+    
+    let mut tilesets = Vec::new();
+    for tileset in sets.iter() {
+        if tileset.external_file().is_some() {
+            return Err(AsepriteLoaderError::Parse(asefile::AsepriteParseError::UnsupportedFeature(
+                format!("Tileset '{}' links to an external file", tileset.name())
+            )))
+        }
+        let tile_size = UVec2::new(tileset.tile_size().width() as u32, tileset.tile_size().height() as u32);
+        tilesets.push(AsepriteTilesetData {
+            name : tileset.name().to_string(),
+            tile_size,
+            tiles : (0..tileset.tile_count()).map(|tile| frame_image(tile_size, tileset.tile_image(tile).into_vec())).collect(),
+        });
+    }
+    
+    let mut tilemaps = Vec::new();
+    for layer in file.layers() {
+        let LayerType::Tilemap(tileset_id) = layer.layer_type() else { continue };
+        let Some(index) = sets.iter().position(|tileset| tileset.id() == tileset_id) else { continue };
+        let tileset = sets[index];
+        let empty = if tileset.empty_tile_is_id_zero() { 0 } else { u32::MAX };
+        
+        let tile_size = tilesets[index].tile_size;
+        let size = UVec2::new(
+            (file.width() as u32).div_ceil(tile_size.x),
+            (file.height() as u32).div_ceil(tile_size.y),
+        );
+        let frames = (0..file.num_frames())
+            .map(|frame| match file.tilemap(layer.id(), frame) {
+                Some(tilemap) => (0..size.y)
+                    .flat_map(|y| (0..size.x).map(move |x| (x, y)))
+                    .map(|(x, y)| tilemap.tile(x, y).id())
+                    .map(|id| (id != empty && id < tileset.tile_count()).then_some(id))
+                    .collect(),
+                None => vec![None; (size.x * size.y) as usize],
+            })
+            .collect();
+        
+        tilemaps.push(AsepriteTilemap { name : layer.name().to_string(), tileset : index, size, frames });
+    }
+    
+    Ok((tilesets, tilemaps))
+}
+
+//=================================================================================
+//    Tile Grid
+//=================================================================================
+
+/// Draws a tilemap layer of the aseprite file on this entity as a grid of sprites, centered on the entity like the frames of the
+/// file are. The grid plays through the frames of the file on a loop, with the durations of each frame, so animated tiles stay
+/// in step with sprites of the same file. The entity needs a `Handle<Aseprite>` and a `SpatialBundle`.
+#[derive(Component, Reflect, Clone, Debug)]
+#[reflect(Component)]
+pub struct AsepriteTileGrid {
+    pub layer : String,
+    pub speed : f32,
+    pub elapsed : f32,
+    #[reflect(ignore)]
+    tiles : Vec<Entity>,
+    #[reflect(ignore)]
+    frame : Option<usize>,
+}
+
+impl Default for AsepriteTileGrid {
+    fn default() -> Self {
+        AsepriteTileGrid { layer : String::new(), speed : 1.0, elapsed : 0.0, tiles : Vec::new(), frame : None }
+    }
+}
+
+impl AsepriteTileGrid {
+    /// Creates a tile grid that draws the tilemap layer with the given name.
+    pub fn new(layer : impl Into<String>) -> Self {
+        AsepriteTileGrid { layer : layer.into(), ..default() }
+    }
+    
+    /// The frame of the file that the grid is showing.
+    pub fn frame(&self) -> Option<usize> {
+        self.frame
+    }
+}
+
+/// A cell of a tile grid. These are spawned as children of the grid.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct AsepriteTile {
+    pub cell : UVec2,
+}
+
+/// Advances every tile grid, spawning its tiles once the aseprite has loaded, and shows the tiles of the current frame.
+pub(crate) fn update_tile_grids(
+    mut commands : Commands,
+    mut grids : Query<(Entity, &mut AsepriteTileGrid, &Handle<Aseprite>)>,
+    mut tiles : Query<(&AsepriteTile, &mut TextureAtlas, &mut Visibility)>,
+    aseprites : Res<Assets<Aseprite>>,
+    animation_time : Res<AnimationTime>,
+    time : Res<Time>,
+) {
+    for (entity, mut grid, handle) in grids.iter_mut() {
+        grid.elapsed += animation_time.scale_delta(time.delta_seconds()) * grid.speed;
+        
+        let Some(aseprite) = aseprites.get(handle) else { continue };
+        let Some(tilemap) = aseprite.tilemap(&grid.layer) else { continue };
+        let Some(tileset) = aseprite.tilesets().get(tilemap.tileset) else { continue };
+        
+        if grid.tiles.len() != (tilemap.size.x * tilemap.size.y) as usize {
+            for tile in grid.tiles.drain(..) { commands.entity(tile).despawn_recursive(); }
+            let origin = Vec2::new(-(aseprite.dimensions().x as f32), aseprite.dimensions().y as f32) / 2.0;
+            for y in 0..tilemap.size.y {
+                for x in 0..tilemap.size.x {
+                    let offset = (UVec2::new(x, y) * tileset.tile_size).as_vec2() + tileset.tile_size.as_vec2() / 2.0;
+                    let tile = commands.spawn((
+                        SpriteSheetBundle {
+                            texture : tileset.image.clone(),
+                            atlas : TextureAtlas { layout : tileset.layout.clone(), index : 0 },
+                            transform : Transform::from_translation((origin + Vec2::new(offset.x, -offset.y)).extend(0.0)),
+                            visibility : Visibility::Hidden,
+                            ..default()
+                        },
+                        AsepriteTile { cell : UVec2::new(x, y) },
+                    )).id();
+                    grid.tiles.push(tile);
+                }
+            }
+            commands.entity(entity).push_children(&grid.tiles);
+            // The tiles only exist once the commands are applied, so they are shown on the next update.
+            grid.frame = None;
+            continue;
+        }
+        
+        let frame = aseprite.frame_at(grid.elapsed);
+        if grid.frame == Some(frame) { continue }
+        grid.frame = Some(frame);
+        
+        let mut tile_iter = tiles.iter_many_mut(&grid.tiles);
+        while let Some((tile, mut atlas, mut visibility)) = tile_iter.fetch_next() {
+            match tilemap.tile(frame, tile.cell) {
+                Some(id) => {
+                    atlas.index = id as usize;
+                    *visibility = Visibility::Inherited;
+                }
+                None => *visibility = Visibility::Hidden,
+            }
+        }
+    }
+}
@@ -0,0 +1,64 @@
+//=================================================================================
+// Prints what the aseprite loader reads from a file, and can dump the atlas it
+// builds. Run with `cargo run --features cli --bin aseprite-inspect -- <file>`.
+//=================================================================================
+
+use std::process::ExitCode;
+use bevy_animator::aseprite::AsepriteReport;
+
+const USAGE : &str = "usage: aseprite-inspect <file.aseprite> [--atlas <atlas.png>] [--layout <layout.json>]";
+
+fn main() -> ExitCode {
+    match run(std::env::args().skip(1).collect()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("{}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(args : Vec<String>) -> Result<(), String> {
+    let mut path = None;
+    let mut atlas_path = None;
+    let mut layout_path = None;
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--atlas" => atlas_path = Some(args.next().ok_or(USAGE)?),
+            "--layout" => layout_path = Some(args.next().ok_or(USAGE)?),
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return Ok(())
+            },
+            _ if path.is_none() => path = Some(arg),
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+    let path = path.ok_or(USAGE)?;
+    
+    let bytes = std::fs::read(&path).map_err(|error| format!("Could not read '{}': {}", path, error))?;
+    let report = AsepriteReport::read(&bytes).map_err(|error| format!("Could not parse '{}': {}", path, error))?;
+    print!("{}", report);
+    
+    if atlas_path.is_none() && layout_path.is_none() { return Ok(()) }
+    let (layout, image) = report.atlas().map_err(|error| format!("Could not pack '{}': {}", path, error))?;
+    
+    if let Some(atlas_path) = atlas_path {
+        let size = image.texture_descriptor.size;
+        image::save_buffer(&atlas_path, &image.data, size.width, size.height, image::ColorType::Rgba8)
+            .map_err(|error| format!("Could not write '{}': {}", atlas_path, error))?;
+        println!("wrote atlas to {}", atlas_path);
+    }
+    
+    if let Some(layout_path) = layout_path {
+        let textures = layout.textures.iter()
+            .map(|rect| serde_json::json!({ "x" : rect.min.x, "y" : rect.min.y, "w" : rect.width(), "h" : rect.height() }))
+            .collect::<Vec<_>>();
+        let json = serde_json::json!({ "size" : [layout.size.x, layout.size.y], "textures" : textures });
+        let json = serde_json::to_string_pretty(&json).map_err(|error| error.to_string())?;
+        std::fs::write(&layout_path, json).map_err(|error| format!("Could not write '{}': {}", layout_path, error))?;
+        println!("wrote layout to {}", layout_path);
+    }
+    Ok(())
+}
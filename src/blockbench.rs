@@ -0,0 +1,723 @@
+//=================================================================================
+// Here we are defining the BlockBench Model AssetFile and Animation Backend. A
+// '.bbmodel' file is loaded into a scene of bone entities with cube meshes, and
+// the animations in the file pose the bones based on the animator's progress.
+//=================================================================================
+
+use std::{f32::consts::PI, fmt::Display};
+use base64::Engine;
+use bevy::{asset::{AssetLoader, AsyncReadExt}, ecs::query::WorldQuery, prelude::*, render::{mesh::{Indices, PrimitiveTopology}, render_asset::RenderAssetUsages, texture::ImageSampler}, utils::HashMap};
+use serde::Deserialize;
+
+use crate::{animation::{Animation, AnimationBackend, Animator}, skeleton::{SkeletonBone, SkeletonPlugin, SkeletonPose}};
+
+/// BlockBench measures everything in pixels. There are 16 pixels in one unit.
+const PIXELS_PER_UNIT : f32 = 16.0;
+
+//=================================================================================
+//    BlockBenchAnimationPlugin
+//=================================================================================
+
+pub(crate) struct BlockBenchAnimationPlugin;
+
+impl Plugin for BlockBenchAnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_asset_loader::<BlockBenchLoader>()
+            .init_asset::<BlockBenchModel>()
+        ;
+        
+        if !app.is_plugin_added::<SkeletonPlugin>() {
+            app.add_plugins(SkeletonPlugin);
+        }
+    }
+}
+
+//=================================================================================
+//    BlockBench Asset
+//=================================================================================
+
+/// The BlockBench Model Asset. Stores the scene that was built from the model, the rest pose of every bone and the animations.
+#[derive(Asset, TypePath)]
+pub struct BlockBenchModel {
+    scene : Handle<Scene>,
+    bones : HashMap<String, RestBone>,
+    animations : HashMap<String, BlockBenchClip>,
+}
+
+/// An animation inside of a BlockBench model.
+#[derive(Clone, Debug, Default)]
+pub struct BlockBenchClip {
+    length : f32,
+    loop_mode : BlockBenchLoop,
+    bones : HashMap<String, BoneTimeline>,
+}
+
+/// What an animation does once it reaches its end.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BlockBenchLoop {
+    /// The animation plays once and then returns to its first frame.
+    #[default]
+    Once,
+    /// The animation plays once and holds the last frame.
+    Hold,
+    /// The animation repeats.
+    Loop,
+}
+
+/// How the value between two keyframes is computed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BlockBenchInterpolation {
+    #[default]
+    Linear,
+    CatmullRom,
+    Step,
+}
+
+/// The rest pose of a bone. BlockBench adds the angles of a keyframe to the rest angles of the bone, so those are kept as well.
+#[derive(Clone, Copy, Debug)]
+struct RestBone {
+    transform : Transform,
+    angles : Vec3,
+}
+
+#[derive(Clone, Debug, Default)]
+struct BoneTimeline {
+    position : Vec<Keyframe>,
+    rotation : Vec<Keyframe>,
+    scale : Vec<Keyframe>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Keyframe {
+    time : f32,
+    value : Vec3,
+    interpolation : BlockBenchInterpolation,
+}
+
+impl BlockBenchModel {
+    /// The scene that holds the bones and meshes of the model.
+    pub fn scene(&self) -> &Handle<Scene> {
+        &self.scene
+    }
+
+    /// Returns the animation with the given name.
+    pub fn animation(&self, name : &str) -> Option<&BlockBenchClip> {
+        self.animations.get(name)
+    }
+
+    /// Computes the local transform of every bone at the given time in seconds. Bones that the clip doesn't animate are put back
+    /// into their rest pose. Like BlockBench, the X position and the X and Y angles of each keyframe are flipped.
+    fn pose(&self, clip : &BlockBenchClip, time : f32, pose : &mut HashMap<String, Transform>) {
+        pose.clear();
+        pose.extend(self.bones.iter().map(|(name, bone)| (name.clone(), bone.transform)));
+        for (bone, timeline) in clip.bones.iter() {
+            let (Some(rest), Some(transform)) = (self.bones.get(bone), pose.get_mut(bone)) else { continue };
+            if let Some(position) = sample(&timeline.position, time) {
+                transform.translation += Vec3::new(-position.x, position.y, position.z) / PIXELS_PER_UNIT;
+            }
+            if let Some(rotation) = sample(&timeline.rotation, time) {
+                transform.rotation = euler(rest.angles + Vec3::new(-rotation.x, -rotation.y, rotation.z));
+            }
+            if let Some(scale) = sample(&timeline.scale, time) {
+                transform.scale *= scale;
+            }
+        }
+    }
+}
+
+impl BlockBenchClip {
+    /// The length of the animation in seconds.
+    pub fn length(&self) -> f32 {
+        self.length
+    }
+
+    /// What the animation does once it reaches its end.
+    pub fn loop_mode(&self) -> BlockBenchLoop {
+        self.loop_mode
+    }
+
+    /// The time in seconds to pose the bones at, given the progress and repititions of an animator.
+    fn time(&self, progress : f32, repititions : u32) -> f32 {
+        match self.loop_mode {
+            BlockBenchLoop::Once if repititions > 0 => 0.0,
+            BlockBenchLoop::Hold if repititions > 0 => self.length,
+            _ => progress * self.length,
+        }
+    }
+}
+
+/// Samples a channel at the given time. Returns `None` if the channel has no keyframes.
+fn sample(keyframes : &[Keyframe], time : f32) -> Option<Vec3> {
+    let next = keyframes.partition_point(|keyframe| keyframe.time <= time);
+    if next == 0 { return keyframes.first().map(|keyframe| keyframe.value) }
+    if next == keyframes.len() { return keyframes.last().map(|keyframe| keyframe.value) }
+
+    let (from, to) = (&keyframes[next - 1], &keyframes[next]);
+    let t = (time - from.time) / (to.time - from.time).max(f32::EPSILON);
+    let catmull_rom = from.interpolation == BlockBenchInterpolation::CatmullRom || to.interpolation == BlockBenchInterpolation::CatmullRom;
+    Some(match from.interpolation {
+        BlockBenchInterpolation::Step => from.value,
+        _ if catmull_rom => {
+            let before = if next >= 2 { keyframes[next - 2].value } else { from.value };
+            let after = keyframes.get(next + 1).map_or(to.value, |keyframe| keyframe.value);
+            catmull_rom_spline(before, from.value, to.value, after, t)
+        },
+        _ => from.value.lerp(to.value, t),
+    })
+}
+
+fn catmull_rom_spline(p0 : Vec3, p1 : Vec3, p2 : Vec3, p3 : Vec3, t : f32) -> Vec3 {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    0.5 * ((2.0 * p1) + (p2 - p0) * t + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t2 + (3.0 * p1 - p0 - 3.0 * p2 + p3) * t3)
+}
+
+/// BlockBench rotations are euler angles in degrees, applied in ZYX order.
+fn euler(degrees : Vec3) -> Quat {
+    let radians = degrees * PI / 180.0;
+    Quat::from_euler(EulerRot::ZYX, radians.z, radians.y, radians.x)
+}
+
+//=================================================================================
+//    BlockBench File Format
+//=================================================================================
+
+#[derive(Deserialize)]
+struct BbModel {
+    #[serde(default)]
+    resolution : BbResolution,
+    #[serde(default)]
+    elements : Vec<BbElement>,
+    #[serde(default)]
+    outliner : Vec<BbOutlinerNode>,
+    #[serde(default)]
+    textures : Vec<BbTexture>,
+    #[serde(default)]
+    animations : Vec<BbAnimation>,
+}
+
+#[derive(Deserialize)]
+struct BbResolution {
+    width : f32,
+    height : f32,
+}
+
+impl Default for BbResolution {
+    fn default() -> Self {
+        BbResolution { width : 16.0, height : 16.0 }
+    }
+}
+
+#[derive(Deserialize)]
+struct BbElement {
+    uuid : String,
+    from : [f32; 3],
+    to : [f32; 3],
+    #[serde(default)]
+    origin : [f32; 3],
+    #[serde(default)]
+    rotation : [f32; 3],
+    #[serde(default)]
+    faces : HashMap<String, BbFace>,
+}
+
+#[derive(Deserialize)]
+struct BbFace {
+    uv : [f32; 4],
+    texture : Option<usize>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum BbOutlinerNode {
+    Element(String),
+    Group(BbGroup),
+}
+
+#[derive(Deserialize)]
+struct BbGroup {
+    name : String,
+    #[serde(default)]
+    origin : [f32; 3],
+    #[serde(default)]
+    rotation : [f32; 3],
+    #[serde(default)]
+    children : Vec<BbOutlinerNode>,
+}
+
+#[derive(Deserialize)]
+struct BbTexture {
+    source : String,
+    uv_width : Option<f32>,
+    uv_height : Option<f32>,
+}
+
+#[derive(Deserialize)]
+struct BbAnimation {
+    name : String,
+    #[serde(default, rename = "loop")]
+    loop_mode : BlockBenchLoop,
+    length : f32,
+    #[serde(default)]
+    animators : HashMap<String, BbAnimator>,
+}
+
+#[derive(Deserialize)]
+struct BbAnimator {
+    name : String,
+    #[serde(default)]
+    keyframes : Vec<BbKeyframe>,
+}
+
+#[derive(Deserialize)]
+struct BbKeyframe {
+    channel : String,
+    time : f32,
+    data_points : Vec<BbDataPoint>,
+    #[serde(default)]
+    interpolation : String,
+}
+
+/// Keyframe values can be numbers or molang expressions. Only plain numbers are supported, anything else is read as 0.
+#[derive(Deserialize)]
+struct BbDataPoint {
+    x : serde_json::Value,
+    y : serde_json::Value,
+    z : serde_json::Value,
+}
+
+impl BbDataPoint {
+    fn value(&self) -> Vec3 {
+        let number = |value : &serde_json::Value| match value {
+            serde_json::Value::Number(number) => number.as_f64().unwrap_or_default() as f32,
+            serde_json::Value::String(string) => string.trim().parse().unwrap_or_default(),
+            _ => 0.0,
+        };
+        Vec3::new(number(&self.x), number(&self.y), number(&self.z))
+    }
+}
+
+//=================================================================================
+//    BlockBench Asset Loader
+//=================================================================================
+
+/// Asset Loader for BlockBench `.bbmodel` files. The model is added as the labeled asset `#scene`.
+#[derive(Default)]
+pub struct BlockBenchLoader;
+
+/// The errors that can occur while loading a BlockBench model.
+#[derive(Debug)]
+pub enum BlockBenchLoaderError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    Texture(String),
+}
+
+impl Display for BlockBenchLoaderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BlockBenchLoaderError::Io(error) => write!(f, "Could not read BlockBench model: {}", error),
+            BlockBenchLoaderError::Json(error) => write!(f, "Could not parse BlockBench model: {}", error),
+            BlockBenchLoaderError::Texture(error) => write!(f, "Could not decode BlockBench texture: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for BlockBenchLoaderError {}
+
+impl From<std::io::Error> for BlockBenchLoaderError {
+    fn from(error: std::io::Error) -> Self {
+        BlockBenchLoaderError::Io(error)
+    }
+}
+
+impl AssetLoader for BlockBenchLoader {
+    type Asset = BlockBenchModel;
+
+    type Settings = ();
+
+    type Error = BlockBenchLoaderError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut bevy::asset::io::Reader,
+        _: &'a Self::Settings,
+        load_context: &'a mut bevy::asset::LoadContext,
+    ) -> bevy::utils::BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            let model : BbModel = serde_json::from_slice(&bytes).map_err(BlockBenchLoaderError::Json)?;
+
+            let mut materials = Vec::with_capacity(model.textures.len());
+            let mut uv_sizes = Vec::with_capacity(model.textures.len());
+            for (index, texture) in model.textures.iter().enumerate() {
+                let image = decode_texture(&texture.source)?;
+                uv_sizes.push(Vec2::new(
+                    texture.uv_width.unwrap_or(model.resolution.width),
+                    texture.uv_height.unwrap_or(model.resolution.height),
+                ));
+                let image = load_context.add_labeled_asset(format!("texture{}", index), image);
+                let material = StandardMaterial {
+                    base_color_texture : Some(image),
+                    alpha_mode : AlphaMode::Mask(0.5),
+                    perceptual_roughness : 1.0,
+                    ..Default::default()
+                };
+                materials.push(load_context.add_labeled_asset(format!("material{}", index), material));
+            }
+            let default_uv_size = Vec2::new(model.resolution.width, model.resolution.height);
+
+            let mut meshes = HashMap::default();
+            for element in model.elements.iter() {
+                let texture = element.faces.values().find_map(|face| face.texture);
+                let uv_size = texture.and_then(|texture| uv_sizes.get(texture)).copied().unwrap_or(default_uv_size);
+                let mesh = load_context.add_labeled_asset(format!("mesh-{}", element.uuid), cube_mesh(element, uv_size));
+                let material = texture.and_then(|texture| materials.get(texture)).cloned().unwrap_or_default();
+                meshes.insert(element.uuid.clone(), (mesh, material));
+            }
+
+            let mut world = World::default();
+            let root = world.spawn(SpatialBundle::INHERITED_IDENTITY).id();
+            let mut bones = HashMap::default();
+            let elements = model.elements.iter().map(|element| (element.uuid.clone(), element)).collect::<HashMap<_, _>>();
+            for node in model.outliner.iter() {
+                spawn_node(&mut world, root, Vec3::ZERO, node, &elements, &meshes, &mut bones);
+            }
+            let scene = load_context.add_labeled_asset("scene".to_string(), Scene::new(world));
+
+            let animations = model.animations.into_iter()
+                .map(|animation| {
+                    let clip = BlockBenchClip {
+                        length : animation.length,
+                        loop_mode : animation.loop_mode,
+                        bones : animation.animators.into_values().map(|animator| (animator.name, timeline(animator.keyframes))).collect(),
+                    };
+                    (animation.name, clip)
+                })
+                .collect();
+
+            Ok(BlockBenchModel { scene, bones, animations })
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["bbmodel"]
+    }
+}
+
+/// Textures are usually embedded in the model as a base64 PNG data url.
+fn decode_texture(source : &str) -> Result<Image, BlockBenchLoaderError> {
+    let data = source.split_once("base64,").map_or(source, |(_, data)| data);
+    let bytes = base64::engine::general_purpose::STANDARD.decode(data).map_err(|error| BlockBenchLoaderError::Texture(error.to_string()))?;
+    let image = image::load_from_memory(&bytes).map_err(|error| BlockBenchLoaderError::Texture(error.to_string()))?;
+    let mut image = Image::from_dynamic(image, true, RenderAssetUsages::all());
+    image.sampler = ImageSampler::nearest();
+    Ok(image)
+}
+
+/// Spawns a group as a bone entity, or an element as a mesh entity, under `parent`. `parent_origin` is the absolute pivot of the
+/// parent, since BlockBench stores every position in model space.
+fn spawn_node(
+    world : &mut World,
+    parent : Entity,
+    parent_origin : Vec3,
+    node : &BbOutlinerNode,
+    elements : &HashMap<String, &BbElement>,
+    meshes : &HashMap<String, (Handle<Mesh>, Handle<StandardMaterial>)>,
+    bones : &mut HashMap<String, RestBone>,
+) {
+    match node {
+        BbOutlinerNode::Group(group) => {
+            let origin = Vec3::from(group.origin);
+            let transform = Transform::from_translation((origin - parent_origin) / PIXELS_PER_UNIT).with_rotation(euler(group.rotation.into()));
+            let bone = world.spawn((
+                Name::new(group.name.clone()),
+                SkeletonBone { name : group.name.clone() },
+                SpatialBundle::from_transform(transform),
+            )).id();
+            world.entity_mut(parent).add_child(bone);
+            bones.insert(group.name.clone(), RestBone { transform, angles : group.rotation.into() });
+            for child in group.children.iter() {
+                spawn_node(world, bone, origin, child, elements, meshes, bones);
+            }
+        },
+        BbOutlinerNode::Element(uuid) => {
+            let (Some(element), Some((mesh, material))) = (elements.get(uuid), meshes.get(uuid)) else { return };
+            let origin = Vec3::from(element.origin);
+            let transform = Transform::from_translation((origin - parent_origin) / PIXELS_PER_UNIT).with_rotation(euler(element.rotation.into()));
+            let cube = world.spawn(PbrBundle {
+                mesh : mesh.clone(),
+                material : material.clone(),
+                transform,
+                ..Default::default()
+            }).id();
+            world.entity_mut(parent).add_child(cube);
+        },
+    }
+}
+
+/// Builds the mesh of a cube element. The vertices are relative to the element's origin.
+fn cube_mesh(element : &BbElement, uv_size : Vec2) -> Mesh {
+    let origin = Vec3::from(element.origin);
+    let min = (Vec3::from(element.from) - origin) / PIXELS_PER_UNIT;
+    let max = (Vec3::from(element.to) - origin) / PIXELS_PER_UNIT;
+    let (x0, y0, z0, x1, y1, z1) = (min.x, min.y, min.z, max.x, max.y, max.z);
+
+    // The corners of each face as seen from outside of the cube: top left, top right, bottom right, bottom left.
+    let faces = [
+        ("north", Vec3::NEG_Z, [[x1, y1, z0], [x0, y1, z0], [x0, y0, z0], [x1, y0, z0]]),
+        ("south", Vec3::Z, [[x0, y1, z1], [x1, y1, z1], [x1, y0, z1], [x0, y0, z1]]),
+        ("east", Vec3::X, [[x1, y1, z1], [x1, y1, z0], [x1, y0, z0], [x1, y0, z1]]),
+        ("west", Vec3::NEG_X, [[x0, y1, z0], [x0, y1, z1], [x0, y0, z1], [x0, y0, z0]]),
+        ("up", Vec3::Y, [[x0, y1, z0], [x1, y1, z0], [x1, y1, z1], [x0, y1, z1]]),
+        ("down", Vec3::NEG_Y, [[x0, y0, z1], [x1, y0, z1], [x1, y0, z0], [x0, y0, z0]]),
+    ];
+
+    let mut positions = Vec::new();
+    let mut normals = Vec::new();
+    let mut uvs = Vec::new();
+    let mut indices = Vec::new();
+    for (name, normal, corners) in faces {
+        let Some(face) = element.faces.get(name) else { continue };
+        if face.texture.is_none() { continue }
+        let start = positions.len() as u32;
+        let [u0, v0, u1, v1] = face.uv;
+        let (u0, u1, v0, v1) = (u0 / uv_size.x, u1 / uv_size.x, v0 / uv_size.y, v1 / uv_size.y);
+        positions.extend(corners);
+        normals.extend([normal.to_array(); 4]);
+        uvs.extend([[u0, v0], [u1, v0], [u1, v1], [u0, v1]]);
+        indices.extend([start, start + 3, start + 2, start, start + 2, start + 1]);
+    }
+
+    Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::all())
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+        .with_inserted_indices(Indices::U32(indices))
+}
+
+/// Sorts the keyframes of an animator into position, rotation and scale channels.
+fn timeline(keyframes : Vec<BbKeyframe>) -> BoneTimeline {
+    let mut timeline = BoneTimeline::default();
+    for keyframe in keyframes {
+        let Some(value) = keyframe.data_points.first().map(BbDataPoint::value) else { continue };
+        let interpolation = match keyframe.interpolation.as_str() {
+            "catmullrom" => BlockBenchInterpolation::CatmullRom,
+            "step" => BlockBenchInterpolation::Step,
+            _ => BlockBenchInterpolation::Linear,
+        };
+        let keyframe_value = Keyframe { time : keyframe.time, value, interpolation };
+        match keyframe.channel.as_str() {
+            "position" => timeline.position.push(keyframe_value),
+            "rotation" => timeline.rotation.push(keyframe_value),
+            "scale" => timeline.scale.push(keyframe_value),
+            _ => {}
+        }
+    }
+    for channel in [&mut timeline.position, &mut timeline.rotation, &mut timeline.scale] {
+        channel.sort_by(|a, b| a.time.total_cmp(&b.time));
+    }
+    timeline
+}
+
+//=================================================================================
+//    BlockBench Animation
+//=================================================================================
+
+/// This trait will allow you to animate a BlockBench model. Implement `Animation` for the type with
+/// `impl_animation!(MyAnimation, BlockBenchBackend)`.
+pub trait BlockBenchAnimation : Sized {
+
+    /// Animations are defined by name inside of the model. This function will tell bevy what animation to use based on the current
+    /// state of the struct that implements this trait.
+    fn get_animation_name(&self) -> &str;
+}
+
+/// The animation backend for BlockBench models. See `BlockBenchAnimation`.
+pub struct BlockBenchBackend;
+
+impl <A : BlockBenchAnimation + Animation + FromWorld + Send + Sync + 'static> AnimationBackend<A> for BlockBenchBackend {
+    type AsociatedAsset = BlockBenchModel;
+
+    type Query<'w, 's> = &'w mut SkeletonPose;
+
+    fn apply(
+        animator : &Animator<A>,
+        items : &mut <Self::Query<'_, '_> as WorldQuery>::Item<'_>,
+        asset : &Self::AsociatedAsset,
+    ) {
+        let Some(clip) = asset.animations.get(animator.animation.get_animation_name()) else { return };
+        asset.pose(clip, clip.time(animator.progress(), animator.repititions()), &mut items.bones);
+    }
+
+    fn spawn(animation : Option<A>, world : &mut World, path : String, entity : Entity) {
+        let animation_comp = animation.unwrap_or(A::from_world(world));
+        let asset_server = world.resource::<AssetServer>();
+        let model : Handle<BlockBenchModel> = asset_server.load(&path);
+        let scene : Handle<Scene> = asset_server.load(format!("{}#scene", path));
+
+        world.get_or_spawn(entity).unwrap()
+            .insert(Animator::new(animation_comp))
+            .insert(model)
+            .insert(SkeletonPose::default())
+            .insert(SceneBundle { scene, ..Default::default() })
+        ;
+    }
+
+    fn spawn_layer(animation : A, world : &mut World, path : String, entity : Entity) {
+        let model : Handle<BlockBenchModel> = world.resource::<AssetServer>().load(&path);
+
+        world.get_or_spawn(entity).unwrap()
+            .insert(Animator::new(animation))
+            .insert(model)
+            .insert(SkeletonPose::default())
+        ;
+    }
+
+    fn duration(animation : &A, asset : &Self::AsociatedAsset) -> f32 {
+        asset.animations.get(animation.get_animation_name()).map_or(0.0, |clip| clip.length)
+    }
+    
+    fn debug_name(animation : &A) -> Option<&str> {
+        Some(animation.get_animation_name())
+    }
+}
+
+//=================================================================================
+//    Tests
+//=================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    const MODEL : &str = r#"{
+        "resolution" : { "width" : 32, "height" : 32 },
+        "elements" : [
+            { "uuid" : "cube", "from" : [-4, 0, -4], "to" : [4, 8, 4], "origin" : [0, 0, 0], "faces" : { "north" : { "uv" : [0, 0, 8, 8], "texture" : 0 } } }
+        ],
+        "outliner" : [
+            { "name" : "body", "origin" : [0, 8, 0], "children" : [
+                "cube",
+                { "name" : "head", "origin" : [0, 16, 0], "rotation" : [0, 90, 0] }
+            ] }
+        ],
+        "animations" : [
+            { "name" : "wave", "loop" : "loop", "length" : 2.0, "animators" : {
+                "a" : { "name" : "head", "keyframes" : [
+                    { "channel" : "rotation", "time" : 1.0, "data_points" : [{ "x" : "0", "y" : 45, "z" : "math.sin(q.anim_time)" }], "interpolation" : "step" },
+                    { "channel" : "rotation", "time" : 0.0, "data_points" : [{ "x" : 0, "y" : 0, "z" : 0 }] },
+                    { "channel" : "position", "time" : 0.0, "data_points" : [{ "x" : 0, "y" : 16, "z" : 0 }] },
+                    { "channel" : "timeline", "time" : 0.5, "data_points" : [] }
+                ] }
+            } },
+            { "name" : "nod", "length" : 1.0 }
+        ]
+    }"#;
+    
+    fn keyframes(values : &[(f32, f32)], interpolation : BlockBenchInterpolation) -> Vec<Keyframe> {
+        values.iter().map(|(time, x)| Keyframe { time : *time, value : Vec3::new(*x, 0.0, 0.0), interpolation }).collect()
+    }
+    
+    #[test]
+    fn parses_model() {
+        let model : BbModel = serde_json::from_str(MODEL).unwrap();
+        assert_eq!((model.resolution.width, model.resolution.height), (32.0, 32.0));
+        assert_eq!(model.elements.len(), 1);
+        assert_eq!(model.elements[0].faces["north"].uv, [0.0, 0.0, 8.0, 8.0]);
+        
+        let mut world = World::default();
+        let root = world.spawn_empty().id();
+        let elements = model.elements.iter().map(|element| (element.uuid.clone(), element)).collect();
+        let mut bones = HashMap::default();
+        for node in model.outliner.iter() {
+            spawn_node(&mut world, root, Vec3::ZERO, node, &elements, &HashMap::default(), &mut bones);
+        }
+        assert_eq!(bones["body"].transform.translation, Vec3::new(0.0, 0.5, 0.0));
+        assert_eq!(bones["head"].transform.translation, Vec3::new(0.0, 0.5, 0.0));
+        assert!(bones["head"].transform.rotation.abs_diff_eq(Quat::from_rotation_y(PI / 2.0), 1e-6));
+        assert_eq!(bones["head"].angles, Vec3::new(0.0, 90.0, 0.0));
+        
+        let mut animations = model.animations.into_iter();
+        let wave = animations.next().unwrap();
+        assert_eq!((wave.name.as_str(), wave.loop_mode, wave.length), ("wave", BlockBenchLoop::Loop, 2.0));
+        let head = timeline(wave.animators.into_values().next().unwrap().keyframes);
+        assert_eq!(head.position.len(), 1);
+        assert_eq!(head.rotation.iter().map(|keyframe| keyframe.time).collect::<Vec<_>>(), vec![0.0, 1.0]);
+        assert_eq!(head.rotation[1].value, Vec3::new(0.0, 45.0, 0.0));
+        assert_eq!(head.rotation[1].interpolation, BlockBenchInterpolation::Step);
+        
+        let nod = animations.next().unwrap();
+        assert_eq!(nod.loop_mode, BlockBenchLoop::Once);
+        assert!(nod.animators.is_empty());
+    }
+    
+    #[test]
+    fn samples_linear_keyframes() {
+        let keyframes = keyframes(&[(0.0, 0.0), (1.0, 1.0), (2.0, 4.0)], BlockBenchInterpolation::Linear);
+        assert_eq!(sample(&[], 0.5), None);
+        assert_eq!(sample(&keyframes, -1.0), Some(Vec3::ZERO));
+        assert_eq!(sample(&keyframes, 0.25), Some(Vec3::new(0.25, 0.0, 0.0)));
+        assert_eq!(sample(&keyframes, 1.5), Some(Vec3::new(2.5, 0.0, 0.0)));
+        assert_eq!(sample(&keyframes, 3.0), Some(Vec3::new(4.0, 0.0, 0.0)));
+    }
+    
+    #[test]
+    fn samples_catmull_rom_keyframes() {
+        let keyframes = keyframes(&[(0.0, 0.0), (1.0, 1.0), (2.0, 4.0)], BlockBenchInterpolation::CatmullRom);
+        assert_eq!(sample(&keyframes, 0.0), Some(Vec3::ZERO));
+        assert_eq!(sample(&keyframes, 0.5), Some(Vec3::new(0.3125, 0.0, 0.0)));
+        assert_eq!(sample(&keyframes, 1.0), Some(Vec3::new(1.0, 0.0, 0.0)));
+        assert_eq!(sample(&keyframes, 1.5), Some(Vec3::new(2.5625, 0.0, 0.0)));
+    }
+    
+    #[test]
+    fn samples_step_keyframes() {
+        let keyframes = keyframes(&[(0.0, 0.0), (1.0, 1.0), (2.0, 4.0)], BlockBenchInterpolation::Step);
+        assert_eq!(sample(&keyframes, 0.99), Some(Vec3::ZERO));
+        assert_eq!(sample(&keyframes, 1.0), Some(Vec3::new(1.0, 0.0, 0.0)));
+        assert_eq!(sample(&keyframes, 1.99), Some(Vec3::new(1.0, 0.0, 0.0)));
+    }
+    
+    #[test]
+    fn loop_mode_sets_time_after_the_end() {
+        let clip = |loop_mode| BlockBenchClip { length : 2.0, loop_mode, bones : HashMap::default() };
+        assert_eq!(clip(BlockBenchLoop::Once).time(0.5, 0), 1.0);
+        assert_eq!(clip(BlockBenchLoop::Once).time(0.5, 1), 0.0);
+        assert_eq!(clip(BlockBenchLoop::Hold).time(0.5, 1), 2.0);
+        assert_eq!(clip(BlockBenchLoop::Loop).time(0.5, 1), 1.0);
+    }
+    
+    #[test]
+    fn poses_bones_like_blockbench() {
+        let rest = Transform::from_xyz(0.0, 0.5, 0.0).with_rotation(euler(Vec3::new(0.0, 90.0, 0.0)));
+        let model = BlockBenchModel {
+            scene : Handle::default(),
+            bones : [
+                ("head".to_string(), RestBone { transform : rest, angles : Vec3::new(0.0, 90.0, 0.0) }),
+                ("body".to_string(), RestBone { transform : Transform::IDENTITY, angles : Vec3::ZERO }),
+            ].into_iter().collect(),
+            animations : HashMap::default(),
+        };
+        let keyframe = |value| vec![Keyframe { time : 0.0, value, interpolation : BlockBenchInterpolation::Linear }];
+        let timeline = BoneTimeline {
+            position : keyframe(Vec3::new(2.0, 16.0, 0.0)),
+            rotation : keyframe(Vec3::new(10.0, 20.0, 30.0)),
+            scale : Vec::new(),
+        };
+        let clip = BlockBenchClip { length : 1.0, loop_mode : BlockBenchLoop::Loop, bones : [("head".to_string(), timeline)].into_iter().collect() };
+        let mut pose = HashMap::default();
+        model.pose(&clip, 0.0, &mut pose);
+        
+        // BlockBench shows this keyframe with the head at -2, 16, 0 pixels from its rest position, and rotated to the angles
+        // -10, 70, 30, which it applies around Z, then Y, then X.
+        let degrees = |angle : f32| angle.to_radians();
+        let rotation = Quat::from_rotation_z(degrees(30.0)) * Quat::from_rotation_y(degrees(70.0)) * Quat::from_rotation_x(degrees(-10.0));
+        assert!(pose["head"].translation.abs_diff_eq(Vec3::new(-0.125, 1.5, 0.0), 1e-6));
+        assert!(pose["head"].rotation.abs_diff_eq(rotation, 1e-6));
+        assert_eq!(pose["body"], Transform::IDENTITY);
+    }
+}
@@ -0,0 +1,49 @@
+//=================================================================================
+// A frame clip is a list of atlas frames with a duration for each frame. Formats
+// that don't have their own notion of an animation timeline use this to map the
+// progress of an animator onto a frame.
+//=================================================================================
+
+/// A named clip of frames. `frame_ends` holds the normalized time at which each frame ends.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FrameClip {
+    frames : Vec<usize>,
+    frame_ends : Vec<f32>,
+    duration : f32,
+}
+
+impl FrameClip {
+    /// Creates a clip from a list of frames and their durations in milliseconds. If there are fewer durations than frames, the
+    /// last duration is used for the rest of the frames. If there are no durations, each frame lasts 100 milliseconds.
+    pub fn new(frames : Vec<usize>, durations : &[u32]) -> Self {
+        let durations = (0..frames.len())
+            .map(|index| *durations.get(index).or(durations.last()).unwrap_or(&100))
+            .collect::<Vec<_>>();
+        let total = durations.iter().sum::<u32>();
+
+        let mut frame_ends = Vec::with_capacity(durations.len());
+        let mut last = 0;
+        for duration in durations {
+            last += duration;
+            frame_ends.push(last as f32 / total.max(1) as f32);
+        }
+
+        FrameClip { frames, frame_ends, duration : total as f32 / 1000.0 }
+    }
+
+    /// Returns the frame that should be shown at the given progress, which is a value between 0.0 and 1.0.
+    pub fn frame(&self, progress : f32) -> usize {
+        let index = self.frame_ends.partition_point(|end| *end <= progress);
+        self.frames.get(index.min(self.frames.len().saturating_sub(1))).copied().unwrap_or(0)
+    }
+
+    /// The frames of the clip, in the order they are played.
+    pub fn frames(&self) -> &[usize] {
+        &self.frames
+    }
+
+    /// The duration of the clip in seconds.
+    pub fn duration(&self) -> f32 {
+        self.duration
+    }
+}
@@ -0,0 +1,244 @@
+//=================================================================================
+// The debug overlay draws what the animators are doing on top of the game: the
+// bounds of each frame, the anchor it is pivoted on, aseprite slices and a label
+// with the current animation. It is meant for tuning anchors and slices.
+//=================================================================================
+
+use bevy::{prelude::*, sprite::Anchor, transform::TransformSystem, utils::HashMap};
+
+use crate::{animation::AnimatorDebug, layer::AnimationLayer};
+
+//=================================================================================
+//    Animator Debug Plugin
+//=================================================================================
+
+/// Draws a debug overlay over every animated entity. This is not added by the `AnimatorPlugin`, so add it yourself when you want
+/// it. The overlay can be turned on and off at runtime with the `AnimatorDebugOverlay` resource.
+///
+/// While the overlay is on, an `AnimatorDebug` is added to every entity with an `Animator`, which is where the labels get their
+/// animation, frame and progress from.
+pub struct AnimatorDebugPlugin;
+
+impl Plugin for AnimatorDebugPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<AnimatorDebugOverlay>()
+            .register_type::<AnimatorDebugOverlay>()
+            .add_systems(PostUpdate, (
+                draw_frame_bounds,
+                update_debug_labels,
+            ).after(TransformSystem::TransformPropagate))
+        ;
+
+        #[cfg(feature = "aseprite")]
+        app
+            .add_systems(PostUpdate, draw_aseprite_slices.after(TransformSystem::TransformPropagate))
+        ;
+    }
+}
+
+//=================================================================================
+//    Animator Debug Resources
+//=================================================================================
+
+/// Controls the debug overlay drawn by the `AnimatorDebugPlugin`.
+#[derive(Resource, Reflect, Clone, Debug)]
+#[reflect(Resource)]
+pub struct AnimatorDebugOverlay {
+    pub enabled : bool,
+    /// Draws the bounds of the frame that each sprite is showing.
+    pub bounds : bool,
+    /// Draws the anchor that each sprite is pivoted on.
+    pub anchors : bool,
+    /// Draws the slices of aseprite files, and their pivots.
+    pub slices : bool,
+    /// Shows a label with the animation, frame and progress of each animator.
+    pub labels : bool,
+    /// Where labels are placed, relative to the entity.
+    pub label_offset : Vec2,
+}
+
+impl Default for AnimatorDebugOverlay {
+    fn default() -> Self {
+        AnimatorDebugOverlay {
+            enabled : true,
+            bounds : true,
+            anchors : true,
+            slices : true,
+            labels : true,
+            label_offset : Vec2::new(0.0, 24.0),
+        }
+    }
+}
+
+impl AnimatorDebugOverlay {
+    /// Turns the overlay on if it is off, and off if it is on.
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+    }
+}
+
+/// A label of the debug overlay, which follows the entity it describes.
+#[derive(Component)]
+struct AnimatorDebugLabel {
+    target : Entity,
+}
+
+const BOUNDS_COLOR : Color = Color::GREEN;
+
+const ANCHOR_COLOR : Color = Color::RED;
+
+#[cfg(feature = "aseprite")]
+const SLICE_COLOR : Color = Color::CYAN;
+
+//=================================================================================
+//    Animator Debug Systems
+//=================================================================================
+
+/// Adds an `AnimatorDebug` to every entity with an animator of this type while the overlay is on.
+pub(crate) fn insert_animator_debug<A : crate::animation::Animation + Send + Sync + 'static>(
+    mut commands : Commands,
+    overlay : Option<Res<AnimatorDebugOverlay>>,
+    animators : Query<Entity, (With<crate::animation::Animator<A>>, Without<AnimatorDebug>)>,
+) {
+    if !overlay.is_some_and(|overlay| overlay.enabled) { return }
+    for entity in animators.iter() {
+        commands.entity(entity).insert(AnimatorDebug::default());
+    }
+}
+
+/// Maps a point of a sprite, where the corners are at -0.5 and 0.5 and y points up, into the world.
+fn sprite_point(transform : &GlobalTransform, anchor : &Anchor, size : Vec2, point : Vec2) -> Vec2 {
+    transform.transform_point(((point - anchor.as_vec()) * size).extend(0.0)).truncate()
+}
+
+/// Draws a rectangle of a sprite, given by two corners in the same space as `sprite_point`.
+fn sprite_rect(gizmos : &mut Gizmos, transform : &GlobalTransform, anchor : &Anchor, size : Vec2, min : Vec2, max : Vec2, color : Color) {
+    let corners = [min, Vec2::new(max.x, min.y), max, Vec2::new(min.x, max.y), min];
+    gizmos.linestrip_2d(corners.map(|corner| sprite_point(transform, anchor, size, corner)), color);
+}
+
+fn draw_frame_bounds(
+    mut gizmos : Gizmos,
+    overlay : Res<AnimatorDebugOverlay>,
+    layouts : Res<Assets<TextureAtlasLayout>>,
+    sprites : Query<(&Sprite, &TextureAtlas, &GlobalTransform), With<AnimatorDebug>>,
+) {
+    if !overlay.enabled { return }
+    for (sprite, atlas, transform) in sprites.iter() {
+        if overlay.bounds {
+            let frame_size = layouts.get(&atlas.layout).and_then(|layout| layout.textures.get(atlas.index)).map(|rect| rect.size());
+            if let Some(size) = sprite.custom_size.or(frame_size) {
+                sprite_rect(&mut gizmos, transform, &sprite.anchor, size, Vec2::splat(-0.5), Vec2::splat(0.5), BOUNDS_COLOR);
+            }
+        }
+        if overlay.anchors {
+            gizmos.circle_2d(transform.translation().truncate(), 2.0, ANCHOR_COLOR);
+        }
+    }
+}
+
+#[cfg(feature = "aseprite")]
+fn draw_aseprite_slices(
+    mut gizmos : Gizmos,
+    overlay : Res<AnimatorDebugOverlay>,
+    aseprites : Res<Assets<crate::aseprite::Aseprite>>,
+    sprites : Query<(&Sprite, &AnimatorDebug, &Handle<crate::aseprite::Aseprite>, &GlobalTransform)>,
+) {
+    if !overlay.enabled || !overlay.slices { return }
+    for (sprite, debug, handle, transform) in sprites.iter() {
+        let Some(aseprite) = aseprites.get(handle) else { continue };
+        let dimensions = aseprite.dimensions().as_vec2();
+        let size = sprite.custom_size.unwrap_or(dimensions);
+        let to_sprite = |pixel : IVec2| Vec2::new(pixel.x as f32 / dimensions.x - 0.5, 0.5 - pixel.y as f32 / dimensions.y);
+        for slice in aseprite.slices() {
+            let Some(key) = slice.key_at(debug.frame().unwrap_or(0)) else { continue };
+            let min = to_sprite(IVec2::new(key.rect.min.x, key.rect.max.y));
+            let max = to_sprite(IVec2::new(key.rect.max.x, key.rect.min.y));
+            sprite_rect(&mut gizmos, transform, &sprite.anchor, size, min, max, SLICE_COLOR);
+            if let Some(pivot) = key.pivot {
+                let pivot = sprite_point(transform, &sprite.anchor, size, to_sprite(key.rect.min + pivot));
+                gizmos.circle_2d(pivot, 1.5, SLICE_COLOR);
+            }
+        }
+    }
+}
+
+/// Spawns, updates and despawns the labels of the overlay. Layer entities are never shown, so they don't get a label.
+fn update_debug_labels(
+    mut commands : Commands,
+    overlay : Res<AnimatorDebugOverlay>,
+    targets : Query<(Entity, &AnimatorDebug, &GlobalTransform), Without<AnimationLayer>>,
+    mut labels : Query<(Entity, &AnimatorDebugLabel, &mut Text, &mut Transform)>,
+) {
+    let show = overlay.enabled && overlay.labels;
+    let mut labelled = HashMap::new();
+    for (entity, label, mut text, mut transform) in labels.iter_mut() {
+        let Some((_, debug, target)) = targets.get(label.target).ok().filter(|_| show) else {
+            commands.entity(entity).despawn();
+            continue
+        };
+        let value = label_text(debug);
+        if text.sections[0].value != value { text.sections[0].value = value; }
+        transform.translation = target.translation() + overlay.label_offset.extend(1.0);
+        labelled.insert(label.target, entity);
+    }
+    if !show { return }
+
+    for (entity, debug, target) in targets.iter() {
+        if labelled.contains_key(&entity) { continue }
+        commands.spawn((
+            AnimatorDebugLabel { target : entity },
+            Text2dBundle {
+                text : Text::from_section(label_text(debug), TextStyle { font_size : 12.0, color : Color::WHITE, ..Default::default() }),
+                transform : Transform::from_translation(target.translation() + overlay.label_offset.extend(1.0)),
+                ..Default::default()
+            },
+        ));
+    }
+}
+
+fn label_text(debug : &AnimatorDebug) -> String {
+    let animation = debug.animation().unwrap_or("?");
+    match debug.frame() {
+        Some(frame) => format!("{} #{} {:.2}", animation, frame, debug.progress()),
+        None => format!("{} {:.2}", animation, debug.progress()),
+    }
+}
+
+//=================================================================================
+//    Tests
+//=================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn debug(animation : Option<&str>, frame : Option<usize>, progress : f32) -> AnimatorDebug {
+        let mut debug = AnimatorDebug::default();
+        *debug.get_field_mut::<Option<String>>("animation").unwrap() = animation.map(str::to_string);
+        *debug.get_field_mut::<Option<usize>>("frame").unwrap() = frame;
+        *debug.get_field_mut::<f32>("progress").unwrap() = progress;
+        debug
+    }
+    
+    #[test]
+    fn formats_labels() {
+        assert_eq!(label_text(&debug(Some("run"), Some(3), 0.25)), "run #3 0.25");
+        assert_eq!(label_text(&debug(Some("walk"), None, 0.5)), "walk 0.50");
+        assert_eq!(label_text(&debug(None, Some(0), 1.0 / 3.0)), "? #0 0.33");
+    }
+    
+    #[test]
+    fn maps_sprite_points_into_world() {
+        let size = Vec2::new(16.0, 32.0);
+        let at_origin = GlobalTransform::IDENTITY;
+        assert_eq!(sprite_point(&at_origin, &Anchor::Center, size, Vec2::ZERO), Vec2::ZERO);
+        assert_eq!(sprite_point(&at_origin, &Anchor::Center, size, Vec2::splat(0.5)), Vec2::new(8.0, 16.0));
+        assert_eq!(sprite_point(&at_origin, &Anchor::BottomLeft, size, Vec2::splat(-0.5)), Vec2::ZERO);
+        assert_eq!(sprite_point(&at_origin, &Anchor::Custom(Vec2::new(0.0, -0.25)), size, Vec2::ZERO), Vec2::new(0.0, 8.0));
+        
+        let moved = GlobalTransform::from(Transform::from_xyz(10.0, -4.0, 2.0).with_scale(Vec3::splat(2.0)));
+        assert_eq!(sprite_point(&moved, &Anchor::TopRight, size, Vec2::new(-0.5, 0.5)), Vec2::new(-22.0, -4.0));
+    }
+}
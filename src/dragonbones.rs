@@ -0,0 +1,749 @@
+//=================================================================================
+// Here we are defining the DragonBones Armature AssetFile and Animation Backend.
+// A DragonBones JSON export is loaded into a scene of bone entities with sprites
+// for each display of a slot, and the animations in the file tween the bones and
+// switch display frames based on the animator's progress.
+//=================================================================================
+
+use std::{fmt::Display, path::Path};
+use bevy::{asset::{AssetLoader, AsyncReadExt}, ecs::query::WorldQuery, math::URect, prelude::*, sprite::Anchor, utils::HashMap};
+use serde::{Deserialize, Serialize};
+
+use crate::{animation::{Animation, AnimationBackend, Animator}, skeleton::{SkeletonAttachment, SkeletonBone, SkeletonPlugin, SkeletonPose}, util::{frame_image, sheets::sheet_frames}};
+
+/// Each slot is drawn this far in front of the slot before it, so that the draw order of the armature is kept.
+const SLOT_DEPTH : f32 = 0.001;
+
+//=================================================================================
+//    DragonBonesAnimationPlugin
+//=================================================================================
+
+pub(crate) struct DragonBonesAnimationPlugin;
+
+impl Plugin for DragonBonesAnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_asset_loader::<DragonBonesLoader>()
+            .init_asset::<DragonBonesArmature>()
+        ;
+
+        if !app.is_plugin_added::<SkeletonPlugin>() {
+            app.add_plugins(SkeletonPlugin);
+        }
+    }
+}
+
+//=================================================================================
+//    DragonBones Asset
+//=================================================================================
+
+/// The DragonBones Armature Asset. Stores the scene that was built from the armature, the atlas of its displays, the setup pose and
+/// the animations.
+#[derive(Asset, TypePath)]
+pub struct DragonBonesArmature {
+    scene : Handle<Scene>,
+    image : Handle<Image>,
+    layout : Handle<TextureAtlasLayout>,
+    bones : HashMap<String, Transform>,
+    displays : HashMap<String, Option<String>>,
+    animations : HashMap<String, DragonBonesClip>,
+}
+
+/// An animation inside of a DragonBones armature. Times are kept in frames, like they are in the file.
+#[derive(Clone, Debug, Default)]
+pub struct DragonBonesClip {
+    frames : f32,
+    frame_rate : f32,
+    play_times : u32,
+    bones : HashMap<String, DragonBonesBoneTimeline>,
+    displays : HashMap<String, Vec<(f32, Option<String>)>>,
+}
+
+#[derive(Clone, Debug, Default)]
+struct DragonBonesBoneTimeline {
+    translate : Vec<DragonBonesKey>,
+    rotate : Vec<DragonBonesKey>,
+    scale : Vec<DragonBonesKey>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct DragonBonesKey {
+    frame : f32,
+    value : Vec2,
+    tween : bool,
+}
+
+impl DragonBonesArmature {
+    /// The scene that holds the bones and displays of the armature.
+    pub fn scene(&self) -> &Handle<Scene> {
+        &self.scene
+    }
+
+    /// The atlas that holds every display image.
+    pub fn image(&self) -> &Handle<Image> {
+        &self.image
+    }
+
+    /// The layout of the atlas.
+    pub fn layout(&self) -> &Handle<TextureAtlasLayout> {
+        &self.layout
+    }
+
+    /// Returns the animation with the given name.
+    pub fn animation(&self, name : &str) -> Option<&DragonBonesClip> {
+        self.animations.get(name)
+    }
+
+    /// Computes the pose of the armature at the given frame. Bones and slots that the clip doesn't animate are put back into their
+    /// setup pose.
+    fn pose(&self, clip : &DragonBonesClip, frame : f32, pose : &mut SkeletonPose) {
+        pose.bones.clone_from(&self.bones);
+        for (bone, timeline) in clip.bones.iter() {
+            let Some(transform) = pose.bones.get_mut(bone) else { continue };
+            // Translations and rotations are offsets from the setup pose, and scales are multiplied with it.
+            if let Some(translate) = sample(&timeline.translate, frame) {
+                transform.translation += translate.extend(0.0);
+            }
+            if let Some(rotate) = sample(&timeline.rotate, frame) {
+                transform.rotate_z(rotate.x.to_radians());
+            }
+            if let Some(scale) = sample(&timeline.scale, frame) {
+                transform.scale *= scale.extend(1.0);
+            }
+        }
+
+        pose.attachments.clone_from(&self.displays);
+        for (slot, keys) in clip.displays.iter() {
+            let current = keys.partition_point(|(key_frame, _)| *key_frame <= frame);
+            if current == 0 { continue }
+            pose.attachments.insert(slot.clone(), keys[current - 1].1.clone());
+        }
+    }
+}
+
+impl DragonBonesClip {
+    /// The length of the animation in seconds.
+    pub fn duration(&self) -> f32 {
+        self.frames / self.frame_rate
+    }
+
+    /// How many times the animation is meant to play. 0 means that it loops forever.
+    pub fn play_times(&self) -> u32 {
+        self.play_times
+    }
+}
+
+/// Samples a timeline at the given frame. Returns `None` if the timeline has no keys. Easing curves are sampled linearly.
+fn sample(keys : &[DragonBonesKey], frame : f32) -> Option<Vec2> {
+    let next = keys.partition_point(|key| key.frame <= frame);
+    if next == 0 { return keys.first().map(|key| key.value) }
+    if next == keys.len() { return keys.last().map(|key| key.value) }
+
+    let (from, to) = (&keys[next - 1], &keys[next]);
+    if !from.tween { return Some(from.value) }
+    let t = (frame - from.frame) / (to.frame - from.frame).max(f32::EPSILON);
+    Some(from.value.lerp(to.value, t))
+}
+
+/// DragonBones uses a y down coordinate system with clockwise rotations, so both are flipped to match bevy.
+fn local_transform(transform : &DbTransform) -> Transform {
+    Transform::from_xyz(transform.x, -transform.y, 0.0)
+        .with_rotation(Quat::from_rotation_z(-transform.sk_y.to_radians()))
+        .with_scale(Vec3::new(transform.sc_x, transform.sc_y, 1.0))
+}
+
+//=================================================================================
+//    DragonBones File Format
+//=================================================================================
+
+#[derive(Deserialize)]
+struct DbFile {
+    #[serde(default = "default_frame_rate", rename = "frameRate")]
+    frame_rate : f32,
+    #[serde(default)]
+    armature : Vec<DbArmature>,
+}
+
+#[derive(Deserialize)]
+struct DbArmature {
+    name : String,
+    #[serde(rename = "frameRate")]
+    frame_rate : Option<f32>,
+    #[serde(default)]
+    bone : Vec<DbBone>,
+    #[serde(default)]
+    slot : Vec<DbSlot>,
+    #[serde(default)]
+    skin : Vec<DbSkin>,
+    #[serde(default)]
+    animation : Vec<DbAnimation>,
+}
+
+/// Skew is not supported, so the rotation of a transform is read from `skY`.
+#[derive(Deserialize, Clone, Copy)]
+#[serde(default)]
+struct DbTransform {
+    x : f32,
+    y : f32,
+    #[serde(rename = "skY")]
+    sk_y : f32,
+    #[serde(rename = "scX")]
+    sc_x : f32,
+    #[serde(rename = "scY")]
+    sc_y : f32,
+}
+
+impl Default for DbTransform {
+    fn default() -> Self {
+        DbTransform { x : 0.0, y : 0.0, sk_y : 0.0, sc_x : 1.0, sc_y : 1.0 }
+    }
+}
+
+#[derive(Deserialize)]
+struct DbBone {
+    name : String,
+    parent : Option<String>,
+    #[serde(default)]
+    transform : DbTransform,
+}
+
+#[derive(Deserialize)]
+struct DbSlot {
+    name : String,
+    parent : String,
+    #[serde(default, rename = "displayIndex")]
+    display_index : i32,
+}
+
+#[derive(Deserialize)]
+struct DbSkin {
+    #[serde(default)]
+    slot : Vec<DbSkinSlot>,
+}
+
+#[derive(Deserialize)]
+struct DbSkinSlot {
+    name : String,
+    #[serde(default)]
+    display : Vec<DbDisplay>,
+}
+
+#[derive(Deserialize)]
+struct DbDisplay {
+    name : String,
+    #[serde(default = "image", rename = "type")]
+    kind : String,
+    path : Option<String>,
+    #[serde(default)]
+    transform : DbTransform,
+    pivot : Option<DbPoint>,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+struct DbPoint {
+    x : f32,
+    y : f32,
+}
+
+#[derive(Deserialize)]
+struct DbAnimation {
+    name : String,
+    #[serde(default)]
+    duration : f32,
+    #[serde(default = "one_time", rename = "playTimes")]
+    play_times : u32,
+    #[serde(default)]
+    bone : Vec<DbBoneTimeline>,
+    #[serde(default)]
+    slot : Vec<DbSlotTimeline>,
+}
+
+/// DragonBones 5.5 and later split bone timelines by property, and older versions key the whole transform in `frame`.
+#[derive(Deserialize)]
+struct DbBoneTimeline {
+    name : String,
+    #[serde(default, rename = "translateFrame")]
+    translate_frame : Vec<DbFrame>,
+    #[serde(default, rename = "rotateFrame")]
+    rotate_frame : Vec<DbFrame>,
+    #[serde(default, rename = "scaleFrame")]
+    scale_frame : Vec<DbFrame>,
+    #[serde(default)]
+    frame : Vec<DbFrame>,
+}
+
+#[derive(Deserialize)]
+struct DbSlotTimeline {
+    name : String,
+    #[serde(default, rename = "displayFrame")]
+    display_frame : Vec<DbFrame>,
+}
+
+/// Every kind of frame that is read. A frame only tweens into the next one if it has `tweenEasing` or `curve` set.
+#[derive(Deserialize)]
+struct DbFrame {
+    #[serde(default = "one_frame")]
+    duration : f32,
+    #[serde(rename = "tweenEasing")]
+    tween_easing : Option<f32>,
+    curve : Option<serde_json::Value>,
+    x : Option<f32>,
+    y : Option<f32>,
+    rotate : Option<f32>,
+    #[serde(default)]
+    clockwise : i32,
+    transform : Option<DbTransform>,
+    value : Option<i32>,
+    #[serde(rename = "displayIndex")]
+    display_index : Option<i32>,
+}
+
+impl DbFrame {
+    fn tween(&self) -> bool {
+        self.tween_easing.is_some() || self.curve.is_some()
+    }
+}
+
+fn default_frame_rate() -> f32 { 24.0 }
+
+fn one_time() -> u32 { 1 }
+
+fn one_frame() -> f32 { 1.0 }
+
+fn image() -> String { "image".to_string() }
+
+/// The texture atlas that DragonBones exports next to the armature.
+#[derive(Deserialize)]
+struct DbAtlas {
+    #[serde(rename = "imagePath")]
+    image_path : String,
+    #[serde(rename = "SubTexture")]
+    sub_textures : Vec<DbSubTexture>,
+}
+
+#[derive(Deserialize)]
+struct DbSubTexture {
+    name : String,
+    x : u32,
+    y : u32,
+    width : u32,
+    height : u32,
+    #[serde(default, rename = "frameX")]
+    frame_x : i32,
+    #[serde(default, rename = "frameY")]
+    frame_y : i32,
+    #[serde(rename = "frameWidth")]
+    frame_width : Option<u32>,
+    #[serde(rename = "frameHeight")]
+    frame_height : Option<u32>,
+    #[serde(default)]
+    rotated : bool,
+}
+
+impl DbSubTexture {
+    /// The anchor of a display that uses this texture. The pivot is relative to the untrimmed texture, so it is moved onto the
+    /// trimmed pixels that are drawn.
+    fn anchor(&self, pivot : Option<DbPoint>) -> Anchor {
+        let trimmed = Vec2::new(self.width as f32, self.height as f32);
+        let size = Vec2::new(self.frame_width.map_or(trimmed.x, |width| width as f32), self.frame_height.map_or(trimmed.y, |height| height as f32));
+        let offset = Vec2::new(-self.frame_x as f32, -self.frame_y as f32);
+        let pivot = pivot.map_or(Vec2::splat(0.5), |pivot| Vec2::new(pivot.x, pivot.y));
+        let pivot = (pivot * size - offset) / trimmed.max(Vec2::ONE);
+        Anchor::Custom(Vec2::new(pivot.x - 0.5, 0.5 - pivot.y))
+    }
+}
+
+//=================================================================================
+//    DragonBones Asset Loader
+//=================================================================================
+
+/// Asset Loader for DragonBones `.ske.json` files. DragonBones names its exports `Name_ske.json` and `Name_tex.json`, so they have
+/// to be renamed to `name.ske.json` and `name.tex.json`. The texture atlas is read from the `.tex.json` file next to the armature,
+/// unless `DragonBonesLoaderSettings::atlas` is set. The armature is added as the labeled asset `#scene`.
+#[derive(Default)]
+pub struct DragonBonesLoader;
+
+/// The settings for loading a DragonBones armature.
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+pub struct DragonBonesLoaderSettings {
+    /// The path of the texture atlas, relative to the armature.
+    pub atlas : Option<String>,
+    /// The name of the armature to load. This defaults to the first armature in the file.
+    pub armature : Option<String>,
+}
+
+/// The errors that can occur while loading a DragonBones armature.
+#[derive(Debug)]
+pub enum DragonBonesLoaderError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    ReadAsset(bevy::asset::ReadAssetBytesError),
+    Image(image::ImageError),
+    MissingArmature(Option<String>),
+    MissingTexture(String),
+}
+
+impl Display for DragonBonesLoaderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DragonBonesLoaderError::Io(error) => write!(f, "Could not read DragonBones armature: {}", error),
+            DragonBonesLoaderError::Json(error) => write!(f, "Could not parse DragonBones file: {}", error),
+            DragonBonesLoaderError::ReadAsset(error) => write!(f, "Could not read DragonBones texture atlas: {}", error),
+            DragonBonesLoaderError::Image(error) => write!(f, "Could not decode DragonBones texture: {}", error),
+            DragonBonesLoaderError::MissingArmature(Some(name)) => write!(f, "DragonBones file has no armature named '{}'", name),
+            DragonBonesLoaderError::MissingArmature(None) => write!(f, "DragonBones file has no armatures"),
+            DragonBonesLoaderError::MissingTexture(name) => write!(f, "DragonBones texture atlas has no texture named '{}'", name),
+        }
+    }
+}
+
+impl std::error::Error for DragonBonesLoaderError {}
+
+impl From<std::io::Error> for DragonBonesLoaderError {
+    fn from(error: std::io::Error) -> Self {
+        DragonBonesLoaderError::Io(error)
+    }
+}
+
+impl AssetLoader for DragonBonesLoader {
+    type Asset = DragonBonesArmature;
+
+    type Settings = DragonBonesLoaderSettings;
+
+    type Error = DragonBonesLoaderError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut bevy::asset::io::Reader,
+        settings: &'a Self::Settings,
+        load_context: &'a mut bevy::asset::LoadContext,
+    ) -> bevy::utils::BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            let file : DbFile = serde_json::from_slice(&bytes).map_err(DragonBonesLoaderError::Json)?;
+            let armature = match &settings.armature {
+                Some(name) => file.armature.into_iter().find(|armature| &armature.name == name),
+                None => file.armature.into_iter().next(),
+            };
+            let Some(armature) = armature else { return Err(DragonBonesLoaderError::MissingArmature(settings.armature.clone())) };
+            let frame_rate = armature.frame_rate.unwrap_or(file.frame_rate).max(1.0);
+
+            // The layout is built over the image of the texture atlas, and trimmed textures are kept in place by their anchor.
+            let directory = load_context.path().parent().map(Path::to_path_buf).unwrap_or_default();
+            let atlas_path = match &settings.atlas {
+                Some(atlas) => directory.join(atlas),
+                None => {
+                    let file_name = load_context.path().file_name().and_then(|name| name.to_str()).unwrap_or_default();
+                    directory.join(format!("{}.tex.json", file_name.trim_end_matches(".json").trim_end_matches(".ske")))
+                },
+            };
+            let atlas_bytes = load_context.read_asset_bytes(atlas_path.clone()).await.map_err(DragonBonesLoaderError::ReadAsset)?;
+            let atlas : DbAtlas = serde_json::from_slice(&atlas_bytes).map_err(DragonBonesLoaderError::Json)?;
+            let image_path = atlas_path.parent().map(|parent| parent.join(&atlas.image_path)).unwrap_or(atlas.image_path.clone().into());
+            let image_bytes = load_context.read_asset_bytes(image_path).await.map_err(DragonBonesLoaderError::ReadAsset)?;
+            let sheet = image::load_from_memory(&image_bytes).map_err(DragonBonesLoaderError::Image)?.to_rgba8();
+
+            let sheet_rects = atlas.sub_textures.iter()
+                .map(|texture| (URect::new(texture.x, texture.y, texture.x + texture.width, texture.y + texture.height), texture.rotated))
+                .collect::<Vec<_>>();
+            let (sheet, rects) = sheet_frames(sheet, &sheet_rects);
+            let mut layout = TextureAtlasLayout::new_empty(Vec2::new(sheet.width() as f32, sheet.height() as f32));
+            for rect in rects { layout.add_texture(rect.as_rect()); }
+            let image = frame_image(UVec2::new(sheet.width(), sheet.height()), sheet.into_raw());
+            let textures = atlas.sub_textures.iter().enumerate().map(|(index, texture)| (texture.name.clone(), index)).collect::<HashMap<_, _>>();
+            let image = load_context.add_labeled_asset("atlas".to_string(), image);
+            let layout = load_context.add_labeled_asset("layout".to_string(), layout);
+
+            // Bones are listed with parents before their children, so every parent has been spawned by the time a child is.
+            let mut world = World::default();
+            let root = world.spawn(SpatialBundle::INHERITED_IDENTITY).id();
+            let mut bone_entities = HashMap::default();
+            let mut bones = HashMap::default();
+            for bone in armature.bone.iter() {
+                let transform = local_transform(&bone.transform);
+                let entity = world.spawn((
+                    Name::new(bone.name.clone()),
+                    SkeletonBone { name : bone.name.clone() },
+                    SpatialBundle::from_transform(transform),
+                )).id();
+                let parent = bone.parent.as_ref().and_then(|parent| bone_entities.get(parent).copied()).unwrap_or(root);
+                world.entity_mut(parent).add_child(entity);
+                bone_entities.insert(bone.name.clone(), entity);
+                bones.insert(bone.name.clone(), transform);
+            }
+
+            let mut skin_slots = armature.skin.into_iter().next().map(|skin| skin.slot).unwrap_or_default()
+                .into_iter()
+                .map(|slot| (slot.name, slot.display))
+                .collect::<HashMap<_, _>>();
+            let mut slot_displays = HashMap::default();
+            let mut displays = HashMap::default();
+            for (depth, slot) in armature.slot.iter().enumerate() {
+                let display_list = skin_slots.remove(&slot.name).unwrap_or_default();
+                let names = display_list.iter().map(|display| display.name.clone()).collect::<Vec<_>>();
+                let setup = usize::try_from(slot.display_index).ok().and_then(|index| names.get(index)).cloned();
+                displays.insert(slot.name.clone(), setup.clone());
+
+                let Some(&bone_entity) = bone_entities.get(&slot.parent) else { continue };
+                for display in display_list.iter() {
+                    if display.kind != "image" { continue }
+                    let texture_name = display.path.as_ref().unwrap_or(&display.name);
+                    let Some(&index) = textures.get(texture_name) else { return Err(DragonBonesLoaderError::MissingTexture(texture_name.clone())) };
+                    let mut transform = local_transform(&display.transform);
+                    transform.translation.z = depth as f32 * SLOT_DEPTH;
+                    let anchor = atlas.sub_textures[index].anchor(display.pivot);
+                    let visibility = if setup.as_ref() == Some(&display.name) { Visibility::Inherited } else { Visibility::Hidden };
+
+                    let entity = world.spawn((
+                        Name::new(display.name.clone()),
+                        SkeletonAttachment { slot : slot.name.clone(), name : display.name.clone() },
+                        SpriteSheetBundle {
+                            sprite : Sprite { anchor, ..Default::default() },
+                            atlas : TextureAtlas { layout : layout.clone(), index },
+                            texture : image.clone(),
+                            transform,
+                            visibility,
+                            ..Default::default()
+                        },
+                    )).id();
+                    world.entity_mut(bone_entity).add_child(entity);
+                }
+                slot_displays.insert(slot.name.clone(), names);
+            }
+            let scene = load_context.add_labeled_asset("scene".to_string(), Scene::new(world));
+
+            let animations = armature.animation.into_iter()
+                .map(|animation| (animation.name.clone(), clip(animation, frame_rate, &slot_displays)))
+                .collect();
+
+            Ok(DragonBonesArmature { scene, image, layout, bones, displays, animations })
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["ske.json"]
+    }
+}
+
+/// Reads the bone and display timelines of an animation. Timelines that are not supported are skipped.
+fn clip(animation : DbAnimation, frame_rate : f32, slot_displays : &HashMap<String, Vec<String>>) -> DragonBonesClip {
+    let mut clip = DragonBonesClip {
+        frames : animation.duration,
+        frame_rate,
+        play_times : animation.play_times,
+        ..Default::default()
+    };
+
+    for timeline in animation.bone {
+        let mut bone = DragonBonesBoneTimeline::default();
+        let keys = |frames : &[DbFrame], value : &dyn Fn(&DbFrame) -> Vec2| {
+            let mut frame = 0.0;
+            frames.iter()
+                .map(|key| {
+                    let key_value = DragonBonesKey { frame, value : value(key), tween : key.tween() };
+                    frame += key.duration;
+                    key_value
+                })
+                .collect::<Vec<_>>()
+        };
+        bone.translate = keys(&timeline.translate_frame, &|key| Vec2::new(key.x.unwrap_or_default(), -key.y.unwrap_or_default()));
+        bone.scale = keys(&timeline.scale_frame, &|key| Vec2::new(key.x.unwrap_or(1.0), key.y.unwrap_or(1.0)));
+        bone.rotate = keys(&timeline.rotate_frame, &|key| Vec2::new(-key.rotate.unwrap_or_default(), 0.0));
+        if !timeline.frame.is_empty() {
+            let transform = |key : &DbFrame| key.transform.unwrap_or_default();
+            bone.translate = keys(&timeline.frame, &|key| Vec2::new(transform(key).x, -transform(key).y));
+            bone.scale = keys(&timeline.frame, &|key| Vec2::new(transform(key).sc_x, transform(key).sc_y));
+            bone.rotate = keys(&timeline.frame, &|key| Vec2::new(-transform(key).sk_y, 0.0));
+        }
+
+        // Rotations take the shortest way to the next key, plus a full turn for every step of `clockwise`.
+        let frames = if timeline.frame.is_empty() { &timeline.rotate_frame } else { &timeline.frame };
+        for index in 1..bone.rotate.len() {
+            let previous = bone.rotate[index - 1].value.x;
+            let delta = (bone.rotate[index].value.x - previous + 180.0).rem_euclid(360.0) - 180.0;
+            bone.rotate[index].value.x = previous + delta - frames[index - 1].clockwise as f32 * 360.0;
+        }
+        clip.bones.insert(timeline.name, bone);
+    }
+
+    for timeline in animation.slot {
+        let names = slot_displays.get(&timeline.name);
+        let mut frame = 0.0;
+        let keys = timeline.display_frame.iter()
+            .map(|key| {
+                let index = key.value.or(key.display_index).unwrap_or_default();
+                let name = usize::try_from(index).ok().and_then(|index| names?.get(index)).cloned();
+                let key_value = (frame, name);
+                frame += key.duration;
+                key_value
+            })
+            .collect::<Vec<_>>();
+        if keys.is_empty() { continue }
+        clip.displays.insert(timeline.name, keys);
+    }
+
+    clip
+}
+
+//=================================================================================
+//    DragonBones Animation
+//=================================================================================
+
+/// This trait will allow you to animate a DragonBones armature. Implement `Animation` for the type with
+/// `impl_animation!(MyAnimation, DragonBonesBackend)`.
+pub trait DragonBonesAnimation : Sized {
+
+    /// Animations are defined by name inside of the armature. This function will tell bevy what animation to use based on the
+    /// current state of the struct that implements this trait.
+    fn get_animation_name(&self) -> &str;
+}
+
+/// The animation backend for DragonBones armatures. See `DragonBonesAnimation`.
+pub struct DragonBonesBackend;
+
+impl <A : DragonBonesAnimation + Animation + FromWorld + Send + Sync + 'static> AnimationBackend<A> for DragonBonesBackend {
+    type AsociatedAsset = DragonBonesArmature;
+
+    type Query<'w, 's> = &'w mut SkeletonPose;
+
+    fn apply(
+        animator : &Animator<A>,
+        items : &mut <Self::Query<'_, '_> as WorldQuery>::Item<'_>,
+        asset : &Self::AsociatedAsset,
+    ) {
+        let Some(clip) = asset.animations.get(animator.animation.get_animation_name()) else { return };
+        // Animations that play a limited number of times hold their last frame once they are done.
+        let frame = if clip.play_times > 0 && animator.repititions() >= clip.play_times {
+            clip.frames
+        } else {
+            animator.progress() * clip.frames
+        };
+        asset.pose(clip, frame, items);
+    }
+
+    fn spawn(animation : Option<A>, world : &mut World, path : String, entity : Entity) {
+        let animation_comp = animation.unwrap_or(A::from_world(world));
+        let asset_server = world.resource::<AssetServer>();
+        let armature : Handle<DragonBonesArmature> = asset_server.load(&path);
+        let scene : Handle<Scene> = asset_server.load(format!("{}#scene", path));
+
+        world.get_or_spawn(entity).unwrap()
+            .insert(Animator::new(animation_comp))
+            .insert(armature)
+            .insert(SkeletonPose::default())
+            .insert(SceneBundle { scene, ..Default::default() })
+        ;
+    }
+
+    fn spawn_layer(animation : A, world : &mut World, path : String, entity : Entity) {
+        let armature : Handle<DragonBonesArmature> = world.resource::<AssetServer>().load(&path);
+
+        world.get_or_spawn(entity).unwrap()
+            .insert(Animator::new(animation))
+            .insert(armature)
+            .insert(SkeletonPose::default())
+        ;
+    }
+
+    fn duration(animation : &A, asset : &Self::AsociatedAsset) -> f32 {
+        asset.animations.get(animation.get_animation_name()).map_or(0.0, |clip| clip.duration())
+    }
+    
+    fn debug_name(animation : &A) -> Option<&str> {
+        Some(animation.get_animation_name())
+    }
+}
+
+//=================================================================================
+//    Tests
+//=================================================================================
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+    use super::*;
+    
+    const ARMATURE : &str = r#"{
+        "frameRate" : 30,
+        "armature" : [ {
+            "name" : "hero",
+            "frameRate" : 24,
+            "bone" : [
+                { "name" : "root" },
+                { "name" : "arm", "parent" : "root", "transform" : { "x" : 10, "y" : 4, "skY" : 90 } }
+            ],
+            "slot" : [ { "name" : "hand", "parent" : "arm", "displayIndex" : 1 } ],
+            "skin" : [ { "slot" : [ { "name" : "hand", "display" : [
+                { "name" : "open" },
+                { "name" : "fist", "path" : "hand/fist", "pivot" : { "x" : 0.5, "y" : 1.0 } }
+            ] } ] } ],
+            "animation" : [ {
+                "name" : "punch",
+                "duration" : 12,
+                "playTimes" : 0,
+                "bone" : [ { "name" : "arm",
+                    "translateFrame" : [ { "duration" : 6, "tweenEasing" : 0, "x" : 0 }, { "duration" : 6, "x" : 12, "y" : 2 }, { "duration" : 0, "x" : 0 } ],
+                    "rotateFrame" : [ { "duration" : 6, "tweenEasing" : 0, "rotate" : 170 }, { "duration" : 0, "rotate" : -170 } ]
+                } ],
+                "slot" : [ { "name" : "hand", "displayFrame" : [ { "duration" : 4 }, { "duration" : 8, "value" : 1 } ] } ]
+            } ]
+        } ]
+    }"#;
+    
+    const ATLAS : &str = r#"{
+        "imagePath" : "hero_tex.png",
+        "SubTexture" : [
+            { "name" : "open", "x" : 0, "y" : 0, "width" : 8, "height" : 8 },
+            { "name" : "hand/fist", "x" : 8, "y" : 0, "width" : 4, "height" : 6, "frameX" : -2, "frameY" : 0, "frameWidth" : 8, "frameHeight" : 6, "rotated" : true }
+        ]
+    }"#;
+    
+    #[test]
+    fn parses_armature() {
+        let file : DbFile = serde_json::from_str(ARMATURE).unwrap();
+        let armature = &file.armature[0];
+        assert_eq!((file.frame_rate, armature.frame_rate), (30.0, Some(24.0)));
+        assert_eq!(armature.bone[1].parent.as_deref(), Some("root"));
+        
+        let arm = local_transform(&armature.bone[1].transform);
+        assert_eq!(arm.translation, Vec3::new(10.0, -4.0, 0.0));
+        assert!(arm.rotation.abs_diff_eq(Quat::from_rotation_z(-PI / 2.0), 1e-6));
+        
+        assert_eq!(armature.slot[0].display_index, 1);
+        let displays = &armature.skin[0].slot[0].display;
+        assert_eq!(displays[0].kind, "image");
+        assert_eq!(displays[1].path.as_deref(), Some("hand/fist"));
+    }
+    
+    #[test]
+    fn anchors_trimmed_textures() {
+        let atlas : DbAtlas = serde_json::from_str(ATLAS).unwrap();
+        assert_eq!(atlas.image_path, "hero_tex.png");
+        assert_eq!(atlas.sub_textures[0].anchor(None), Anchor::Custom(Vec2::ZERO));
+        // The fist is trimmed two pixels from the left and right, so the bottom center pivot is still at its bottom center.
+        let fist = &atlas.sub_textures[1];
+        assert!(fist.rotated);
+        assert_eq!(fist.anchor(Some(DbPoint { x : 0.5, y : 1.0 })), Anchor::Custom(Vec2::new(0.0, -0.5)));
+        assert_eq!(fist.anchor(Some(DbPoint { x : 0.0, y : 0.0 })), Anchor::Custom(Vec2::new(-1.0, 0.5)));
+    }
+    
+    #[test]
+    fn samples_animation() {
+        let file : DbFile = serde_json::from_str(ARMATURE).unwrap();
+        let mut armature = file.armature.into_iter().next().unwrap();
+        let slot_displays = HashMap::from_iter([("hand".to_string(), vec!["open".to_string(), "fist".to_string()])]);
+        let punch = clip(armature.animation.remove(0), 24.0, &slot_displays);
+        assert_eq!(punch.duration(), 0.5);
+        assert_eq!(punch.play_times(), 0);
+        
+        let arm = &punch.bones["arm"];
+        assert_eq!(sample(&arm.translate, 3.0), Some(Vec2::new(6.0, -1.0)));
+        assert_eq!(sample(&arm.translate, 9.0), Some(Vec2::new(12.0, -2.0)));
+        assert_eq!(sample(&arm.translate, 12.0), Some(Vec2::ZERO));
+        // 170 to -170 degrees is a 20 degree turn, and rotations are flipped to match bevy.
+        assert_eq!(sample(&arm.rotate, 3.0), Some(Vec2::new(-180.0, 0.0)));
+        
+        assert_eq!(punch.displays["hand"], vec![(0.0, Some("open".to_string())), (4.0, Some("fist".to_string()))]);
+    }
+}
@@ -0,0 +1,278 @@
+//=================================================================================
+// Easing curves remap a normalized time between 0 and 1 onto a new value, so
+// that motion can speed up and slow down instead of moving at a constant rate.
+// They are used by property keyframes, and by animators to remap their progress.
+//=================================================================================
+
+use std::{f32::consts::PI, sync::Arc};
+use bevy::prelude::*;
+
+//=================================================================================
+//    Easing
+//=================================================================================
+
+/// An easing curve. Every curve starts at 0 and ends at 1.
+#[derive(Reflect, Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum Easing {
+    #[default]
+    Linear,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    CubicIn,
+    CubicOut,
+    CubicInOut,
+    ElasticIn,
+    ElasticOut,
+    ElasticInOut,
+    BounceIn,
+    BounceOut,
+    BounceInOut,
+    /// Holds 0 until the end, then jumps to 1.
+    Step,
+    /// Jumps between the given number of evenly spaced steps, starting at 0.
+    Steps(u32),
+    /// A CSS style cubic bezier, given by the x and y of its two control points. The x of each control point is clamped between
+    /// 0 and 1.
+    CubicBezier(f32, f32, f32, f32),
+}
+
+impl Easing {
+    /// Remaps `t`, which is clamped between 0 and 1.
+    pub fn ease(&self, t : f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::QuadIn => t * t,
+            Easing::QuadOut => 1.0 - (1.0 - t).powi(2),
+            Easing::QuadInOut => if t < 0.5 { 2.0 * t * t } else { 1.0 - (-2.0 * t + 2.0).powi(2) / 2.0 },
+            Easing::CubicIn => t * t * t,
+            Easing::CubicOut => 1.0 - (1.0 - t).powi(3),
+            Easing::CubicInOut => if t < 0.5 { 4.0 * t * t * t } else { 1.0 - (-2.0 * t + 2.0).powi(3) / 2.0 },
+            Easing::ElasticIn => if t == 0.0 || t == 1.0 { t } else {
+                -(2.0f32).powf(10.0 * t - 10.0) * ((10.0 * t - 10.75) * ELASTIC).sin()
+            },
+            Easing::ElasticOut => if t == 0.0 || t == 1.0 { t } else {
+                (2.0f32).powf(-10.0 * t) * ((10.0 * t - 0.75) * ELASTIC).sin() + 1.0
+            },
+            Easing::ElasticInOut => if t == 0.0 || t == 1.0 { t } else if t < 0.5 {
+                -((2.0f32).powf(20.0 * t - 10.0) * ((20.0 * t - 11.125) * ELASTIC_IN_OUT).sin()) / 2.0
+            } else {
+                (2.0f32).powf(-20.0 * t + 10.0) * ((20.0 * t - 11.125) * ELASTIC_IN_OUT).sin() / 2.0 + 1.0
+            },
+            Easing::BounceIn => 1.0 - bounce_out(1.0 - t),
+            Easing::BounceOut => bounce_out(t),
+            Easing::BounceInOut => if t < 0.5 { (1.0 - bounce_out(1.0 - 2.0 * t)) / 2.0 } else { (1.0 + bounce_out(2.0 * t - 1.0)) / 2.0 },
+            Easing::Step => if t < 1.0 { 0.0 } else { 1.0 },
+            Easing::Steps(0) => t,
+            Easing::Steps(steps) => (t * *steps as f32).floor() / *steps as f32,
+            Easing::CubicBezier(x1, y1, x2, y2) => cubic_bezier(x1.clamp(0.0, 1.0), *y1, x2.clamp(0.0, 1.0), *y2, t),
+        }
+    }
+}
+
+const ELASTIC : f32 = 2.0 * PI / 3.0;
+
+const ELASTIC_IN_OUT : f32 = 2.0 * PI / 4.5;
+
+fn bounce_out(t : f32) -> f32 {
+    const N : f32 = 7.5625;
+    const D : f32 = 2.75;
+    if t < 1.0 / D {
+        N * t * t
+    } else if t < 2.0 / D {
+        let t = t - 1.5 / D;
+        N * t * t + 0.75
+    } else if t < 2.5 / D {
+        let t = t - 2.25 / D;
+        N * t * t + 0.9375
+    } else {
+        let t = t - 2.625 / D;
+        N * t * t + 0.984375
+    }
+}
+
+/// Finds the point on the curve with the given x using Newton's method, falling back to bisection, and returns its y.
+fn cubic_bezier(x1 : f32, y1 : f32, x2 : f32, y2 : f32, x : f32) -> f32 {
+    let bezier = |a : f32, b : f32, s : f32| 3.0 * a * s * (1.0 - s).powi(2) + 3.0 * b * s * s * (1.0 - s) + s * s * s;
+    let slope = |a : f32, b : f32, s : f32| 3.0 * a * (1.0 - s).powi(2) + 6.0 * (b - a) * s * (1.0 - s) + 3.0 * (1.0 - b) * s * s;
+
+    let mut s = x;
+    for _ in 0..8 {
+        let error = bezier(x1, x2, s) - x;
+        if error.abs() < 1e-5 { return bezier(y1, y2, s) }
+        let derivative = slope(x1, x2, s);
+        if derivative.abs() < 1e-6 { break }
+        s -= error / derivative;
+    }
+
+    let (mut low, mut high) = (0.0, 1.0);
+    s = x;
+    for _ in 0..32 {
+        let value = bezier(x1, x2, s);
+        if (value - x).abs() < 1e-5 { break }
+        if value < x { low = s } else { high = s }
+        s = (low + high) / 2.0;
+    }
+    bezier(y1, y2, s)
+}
+
+//=================================================================================
+//    Easing Curve Asset
+//=================================================================================
+
+/// A sampled easing curve. The samples are spread evenly from 0 to 1, and the curve is linear between them. This is useful for
+/// curves that are too expensive to compute every tick. There is no loader for curves, so they are added with `Assets::add`, and
+/// with the `serialize` feature they can be read from any serde format first.
+#[derive(Asset, TypePath, Clone, Debug, Default)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct EasingCurve {
+    samples : Vec<f32>,
+}
+
+impl EasingCurve {
+    /// Creates a curve from its samples.
+    pub fn new(samples : Vec<f32>) -> Self {
+        EasingCurve { samples }
+    }
+
+    /// Creates a curve by sampling a function the given number of times.
+    pub fn from_fn(count : usize, function : impl Fn(f32) -> f32) -> Self {
+        let last = count.saturating_sub(1).max(1) as f32;
+        EasingCurve { samples : (0..count).map(|index| function(index as f32 / last)).collect() }
+    }
+
+    /// Samples the curve at `t`, which is clamped between 0 and 1. A curve without samples is linear.
+    pub fn sample(&self, t : f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self.samples.len() {
+            0 => t,
+            1 => self.samples[0],
+            count => {
+                let position = t * (count - 1) as f32;
+                let index = (position.floor() as usize).min(count - 2);
+                let a = self.samples[index];
+                let b = self.samples[index + 1];
+                a + (b - a) * (position - index as f32)
+            },
+        }
+    }
+}
+
+//=================================================================================
+//    Animator Easing
+//=================================================================================
+
+/// How an `Animator` remaps its progress before the animation is applied.
+#[derive(Clone)]
+pub enum AnimatorEasing {
+    /// One of the built in easing curves.
+    Curve(Easing),
+    /// A custom function. It is given the linear progress between 0 and 1.
+    Custom(Arc<dyn Fn(f32) -> f32 + Send + Sync>),
+    /// A sampled curve asset. The progress stays linear until the asset has loaded.
+    Sampled(Handle<EasingCurve>),
+}
+
+impl Default for AnimatorEasing {
+    fn default() -> Self {
+        AnimatorEasing::Curve(Easing::Linear)
+    }
+}
+
+impl From<Easing> for AnimatorEasing {
+    fn from(easing : Easing) -> Self {
+        AnimatorEasing::Curve(easing)
+    }
+}
+
+impl From<Handle<EasingCurve>> for AnimatorEasing {
+    fn from(curve : Handle<EasingCurve>) -> Self {
+        AnimatorEasing::Sampled(curve)
+    }
+}
+
+impl std::fmt::Debug for AnimatorEasing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AnimatorEasing::Curve(easing) => f.debug_tuple("Curve").field(easing).finish(),
+            AnimatorEasing::Custom(_) => f.debug_tuple("Custom").finish(),
+            AnimatorEasing::Sampled(curve) => f.debug_tuple("Sampled").field(curve).finish(),
+        }
+    }
+}
+
+impl AnimatorEasing {
+    /// Creates an easing from a custom function.
+    pub fn custom(function : impl Fn(f32) -> f32 + Send + Sync + 'static) -> Self {
+        AnimatorEasing::Custom(Arc::new(function))
+    }
+
+    /// Remaps `t`. Sampled curves are looked up in `curves`.
+    pub fn ease(&self, t : f32, curves : Option<&Assets<EasingCurve>>) -> f32 {
+        match self {
+            AnimatorEasing::Curve(easing) => easing.ease(t),
+            AnimatorEasing::Custom(function) => function(t),
+            AnimatorEasing::Sampled(curve) => curves.and_then(|curves| curves.get(curve)).map_or(t, |curve| curve.sample(t)),
+        }
+    }
+}
+
+//=================================================================================
+//    Tests
+//=================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    const EASINGS : [Easing; 16] = [
+        Easing::Linear, Easing::QuadIn, Easing::QuadOut, Easing::QuadInOut, Easing::CubicIn, Easing::CubicOut, Easing::CubicInOut,
+        Easing::ElasticIn, Easing::ElasticOut, Easing::ElasticInOut, Easing::BounceIn, Easing::BounceOut, Easing::BounceInOut,
+        Easing::Step, Easing::Steps(4), Easing::CubicBezier(0.25, 0.1, 0.25, 1.0),
+    ];
+    
+    #[test]
+    fn easings_start_at_0_and_end_at_1() {
+        for easing in EASINGS {
+            assert!(easing.ease(0.0).abs() < 1e-5, "{:?} starts at {}", easing, easing.ease(0.0));
+            assert!((easing.ease(1.0) - 1.0).abs() < 1e-5, "{:?} ends at {}", easing, easing.ease(1.0));
+            assert_eq!(easing.ease(-1.0), easing.ease(0.0));
+            assert_eq!(easing.ease(2.0), easing.ease(1.0));
+        }
+    }
+    
+    #[test]
+    fn steps_jump_between_even_steps() {
+        assert_eq!(Easing::Steps(4).ease(0.2), 0.0);
+        assert_eq!(Easing::Steps(4).ease(0.25), 0.25);
+        assert_eq!(Easing::Steps(4).ease(0.7), 0.5);
+        assert_eq!(Easing::Steps(4).ease(0.99), 0.75);
+        assert_eq!(Easing::Steps(0).ease(0.3), 0.3);
+        assert_eq!(Easing::Step.ease(0.99), 0.0);
+    }
+    
+    #[test]
+    fn cubic_bezier_matches_css() {
+        // The CSS `ease`, `ease-in-out` and `linear` curves.
+        let ease = Easing::CubicBezier(0.25, 0.1, 0.25, 1.0);
+        assert!((ease.ease(0.25) - 0.4094).abs() < 1e-3);
+        assert!((ease.ease(0.5) - 0.8024).abs() < 1e-3);
+        let ease_in_out = Easing::CubicBezier(0.42, 0.0, 0.58, 1.0);
+        assert!((ease_in_out.ease(0.5) - 0.5).abs() < 1e-4);
+        assert!((ease_in_out.ease(0.25) - 0.1291).abs() < 1e-3);
+        let linear = Easing::CubicBezier(0.0, 0.0, 1.0, 1.0);
+        assert!((linear.ease(0.3) - 0.3).abs() < 1e-4);
+    }
+    
+    #[test]
+    fn sampled_curves_are_linear_between_samples() {
+        let curve = EasingCurve::from_fn(5, |t| t * t);
+        assert_eq!(curve.sample(0.25), 0.0625);
+        assert_eq!(curve.sample(0.375), (0.0625 + 0.25) / 2.0);
+        assert_eq!(curve.sample(1.5), 1.0);
+        assert_eq!(EasingCurve::default().sample(0.4), 0.4);
+        assert_eq!(EasingCurve::new(vec![0.7]).sample(0.1), 0.7);
+    }
+}
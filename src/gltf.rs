@@ -0,0 +1,322 @@
+//=================================================================================
+// Here we are defining the glTF Animation Backend. glTF files are loaded with
+// bevy's own loader, and the animator drives the AnimationPlayer in the spawned
+// scene, so 3D models use the same state machine as sprites.
+//=================================================================================
+
+use bevy::{ecs::{event::ManualEventReader, query::WorldQuery}, gltf::Gltf, prelude::*, transform::TransformSystem, utils::HashMap};
+
+use crate::animation::{Animation, AnimationBackend, AnimationSet, Animator};
+
+//=================================================================================
+//    GltfAnimationPlugin
+//=================================================================================
+
+pub(crate) struct GltfAnimationPlugin;
+
+impl Plugin for GltfAnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_asset::<GltfAnimations>()
+            .init_resource::<GltfAnimationRequests>()
+            .add_systems(PostUpdate, (
+                (forget_removed_gltf_animations, build_gltf_animations).chain().before(AnimationSet::Animate),
+                drive_gltf_players
+                    .after(AnimationSet::Animate)
+                    .before(bevy::animation::animation_player)
+                    .before(TransformSystem::TransformPropagate),
+            ))
+        ;
+    }
+}
+
+//=================================================================================
+//    glTF Animations Asset
+//=================================================================================
+
+/// The clips of a glTF file along with their durations. Bevy's `Gltf` asset only holds handles to its clips, so this asset is built
+/// once the file and all of its clips have loaded.
+#[derive(Asset, TypePath)]
+pub struct GltfAnimations {
+    gltf : Handle<Gltf>,
+    clips : HashMap<String, GltfClip>,
+}
+
+#[derive(Clone, Debug)]
+struct GltfClip {
+    handle : Handle<AnimationClip>,
+    duration : f32,
+}
+
+impl GltfAnimations {
+    /// The glTF file that these animations were read from.
+    pub fn gltf(&self) -> &Handle<Gltf> {
+        &self.gltf
+    }
+
+    /// Returns the handle of the clip with the given name.
+    pub fn clip(&self, name : &str) -> Option<&Handle<AnimationClip>> {
+        self.clips.get(name).map(|clip| &clip.handle)
+    }
+}
+
+/// The glTF files that are waiting to be turned into `GltfAnimations`, and the animations that have already been handed out by
+/// path. Only the ids are kept by path, so the animations are dropped once no entity uses them.
+#[derive(Resource, Default)]
+struct GltfAnimationRequests {
+    pending : Vec<(Handle<Gltf>, Handle<GltfAnimations>)>,
+    by_path : HashMap<String, AssetId<GltfAnimations>>,
+}
+
+/// Returns a handle to the animations that were handed out for `path`, taken from the pending requests or from an entity that
+/// still uses them.
+fn find_animations(world : &mut World, path : &str) -> Option<Handle<GltfAnimations>> {
+    let requests = world.resource::<GltfAnimationRequests>();
+    let id = *requests.by_path.get(path)?;
+    if let Some((_, handle)) = requests.pending.iter().find(|(_, handle)| handle.id() == id) {
+        return Some(handle.clone())
+    }
+    world.query::<&Handle<GltfAnimations>>().iter(world).find(|handle| handle.id() == id).cloned()
+}
+
+//=================================================================================
+//    glTF Components
+//=================================================================================
+
+/// The clip and time that the animation wants to show this tick. It is copied onto the `AnimationPlayer` in the spawned scene.
+#[derive(Component, Default, Clone, Debug)]
+pub struct GltfAnimationTarget {
+    clip : Option<Handle<AnimationClip>>,
+    time : f32,
+}
+
+//=================================================================================
+//    glTF Systems
+//=================================================================================
+
+/// Builds the `GltfAnimations` asset for every glTF file that has finished loading, and builds it again when the file is reloaded.
+/// Without bevy's `GltfPlugin` there are no glTF files to build, so the system does nothing.
+fn build_gltf_animations(
+    mut requests : ResMut<GltfAnimationRequests>,
+    gltfs : Option<Res<Assets<Gltf>>>,
+    clips : Option<Res<Assets<AnimationClip>>>,
+    gltf_events : Option<Res<Events<AssetEvent<Gltf>>>>,
+    mut gltf_reader : Local<ManualEventReader<AssetEvent<Gltf>>>,
+    mut animations : ResMut<Assets<GltfAnimations>>,
+    handles : Query<&Handle<GltfAnimations>>,
+) {
+    let (Some(gltfs), Some(clips), Some(gltf_events)) = (gltfs, clips, gltf_events) else { return };
+
+    for event in gltf_reader.read(&gltf_events) {
+        let AssetEvent::Modified { id } = event else { continue };
+        for (animations_id, file_animations) in animations.iter().filter(|(_, file_animations)| file_animations.gltf.id() == *id) {
+            if requests.pending.iter().any(|(_, handle)| handle.id() == animations_id) { continue }
+            let Some(handle) = handles.iter().find(|handle| handle.id() == animations_id) else { continue };
+            requests.pending.push((file_animations.gltf.clone(), handle.clone()));
+        }
+    }
+
+    requests.pending.retain(|(gltf_handle, handle)| {
+        let Some(gltf) = gltfs.get(gltf_handle) else { return true };
+        let mut named_clips = HashMap::default();
+        for (name, clip_handle) in gltf.named_animations.iter() {
+            let Some(clip) = clips.get(clip_handle) else { return true };
+            named_clips.insert(name.clone(), GltfClip { handle : clip_handle.clone(), duration : clip.duration() });
+        }
+        animations.insert(handle.id(), GltfAnimations { gltf : gltf_handle.clone(), clips : named_clips });
+        false
+    });
+}
+
+/// Forgets the paths of animations that were dropped, so the next entity that uses the file requests them again.
+fn forget_removed_gltf_animations(
+    mut requests : ResMut<GltfAnimationRequests>,
+    mut events : EventReader<AssetEvent<GltfAnimations>>,
+) {
+    for event in events.read() {
+        if let AssetEvent::Removed { id } = event { requests.by_path.retain(|_, path_id| path_id != id); }
+    }
+}
+
+/// Copies the target of every animated glTF onto the `AnimationPlayer` in its scene. The player is kept paused, since the animator
+/// is the one that keeps time.
+fn drive_gltf_players(
+    targets : Query<(Entity, &GltfAnimationTarget), Changed<GltfAnimationTarget>>,
+    children : Query<&Children>,
+    mut players : Query<&mut AnimationPlayer>,
+) {
+    for (root, target) in targets.iter() {
+        let Some(clip) = &target.clip else { continue };
+        let Some(player_entity) = children.iter_descendants(root).find(|entity| players.contains(*entity)) else { continue };
+        let Ok(mut player) = players.get_mut(player_entity) else { continue };
+        if !player.is_playing_clip(clip) {
+            player.start(clip.clone());
+        }
+        player.seek_to(target.time);
+        player.pause();
+    }
+}
+
+//=================================================================================
+//    glTF Animation
+//=================================================================================
+
+/// This trait will allow you to animate a glTF model. Implement `Animation` for the type with
+/// `impl_animation!(MyAnimation, GltfBackend)`.
+pub trait GltfAnimation : Sized {
+
+    /// Animations are defined by the named clips inside of the glTF file. This function will tell bevy what clip to use based on the
+    /// current state of the struct that implements this trait.
+    fn get_clip_name(&self) -> &str;
+
+    /// The label of the scene to spawn. This defaults to the first scene in the file.
+    fn get_scene_label() -> &'static str { "Scene0" }
+}
+
+/// The animation backend for glTF files. See `GltfAnimation`.
+pub struct GltfBackend;
+
+impl <A : GltfAnimation + Animation + FromWorld + Send + Sync + 'static> AnimationBackend<A> for GltfBackend {
+    type AsociatedAsset = GltfAnimations;
+
+    type Query<'w, 's> = &'w mut GltfAnimationTarget;
+
+    fn apply(
+        animator : &Animator<A>,
+        items : &mut <Self::Query<'_, '_> as WorldQuery>::Item<'_>,
+        asset : &Self::AsociatedAsset,
+    ) {
+        let Some(clip) = asset.clips.get(animator.animation.get_clip_name()) else { return };
+        items.clip = Some(clip.handle.clone());
+        items.time = animator.progress() * clip.duration;
+    }
+
+    fn spawn(animation : Option<A>, world : &mut World, path : String, entity : Entity) {
+        let animation_comp = animation.unwrap_or(A::from_world(world));
+        let asset_server = world.resource::<AssetServer>();
+        let scene : Handle<Scene> = asset_server.load(format!("{}#{}", path, A::get_scene_label()));
+        let gltf : Handle<Gltf> = asset_server.load(&path);
+
+        let animations = find_animations(world, &path).unwrap_or_else(|| {
+            let handle = world.resource::<Assets<GltfAnimations>>().reserve_handle();
+            let mut requests = world.resource_mut::<GltfAnimationRequests>();
+            requests.pending.push((gltf, handle.clone()));
+            requests.by_path.insert(path.clone(), handle.id());
+            handle
+        });
+
+        world.get_or_spawn(entity).unwrap()
+            .insert(Animator::new(animation_comp))
+            .insert(animations)
+            .insert(GltfAnimationTarget::default())
+            .insert(SceneBundle { scene, ..Default::default() })
+        ;
+    }
+
+    fn duration(animation : &A, asset : &Self::AsociatedAsset) -> f32 {
+        asset.clips.get(animation.get_clip_name()).map_or(0.0, |clip| clip.duration)
+    }
+    
+    fn debug_name(animation : &A) -> Option<&str> {
+        Some(animation.get_clip_name())
+    }
+}
+
+//=================================================================================
+//    Tests
+//=================================================================================
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use bevy::animation::{EntityPath, Interpolation, Keyframes, VariableCurve};
+    use super::*;
+    use crate::{impl_animation, testing::AnimationTestApp};
+    
+    #[derive(Default)]
+    struct Walker;
+    
+    impl GltfAnimation for Walker {
+        fn get_clip_name(&self) -> &str { "walk" }
+    }
+    
+    impl_animation!(Walker, GltfBackend);
+    
+    /// A clip that moves one bone over two seconds.
+    fn clip() -> AnimationClip {
+        let mut clip = AnimationClip::default();
+        clip.add_curve_to_path(EntityPath { parts : vec![Name::new("bone")] }, VariableCurve {
+            keyframe_timestamps : vec![0.0, 2.0],
+            keyframes : Keyframes::Translation(vec![Vec3::ZERO, Vec3::X]),
+            interpolation : Interpolation::Linear,
+        });
+        clip
+    }
+    
+    /// A glTF file with a single named clip, as bevy's loader would build it.
+    fn gltf(clip : Handle<AnimationClip>) -> Gltf {
+        Gltf {
+            scenes : Vec::new(),
+            named_scenes : HashMap::default(),
+            meshes : Vec::new(),
+            named_meshes : HashMap::default(),
+            materials : Vec::new(),
+            named_materials : HashMap::default(),
+            nodes : Vec::new(),
+            named_nodes : HashMap::default(),
+            default_scene : None,
+            animations : vec![clip.clone()],
+            named_animations : [("walk".to_string(), clip)].into_iter().collect(),
+            source : None,
+        }
+    }
+    
+    fn test_app() -> AnimationTestApp {
+        let mut test = AnimationTestApp::new();
+        test.app.init_asset::<Gltf>().init_asset::<AnimationClip>();
+        test.add_animation::<Walker>();
+        test
+    }
+    
+    /// Requests the animations of a glTF file like `GltfBackend::spawn` does.
+    fn request(test : &mut AnimationTestApp, gltf : Handle<Gltf>) -> Handle<GltfAnimations> {
+        let handle = test.app.world.resource::<Assets<GltfAnimations>>().reserve_handle();
+        test.app.world.resource_mut::<GltfAnimationRequests>().pending.push((gltf, handle.clone()));
+        handle
+    }
+    
+    #[test]
+    fn builds_animations_once_clips_load() {
+        let mut test = test_app();
+        let clip_handle = test.app.world.resource::<Assets<AnimationClip>>().reserve_handle();
+        let gltf_handle = test.app.world.resource_mut::<Assets<Gltf>>().add(gltf(clip_handle.clone()));
+        let animations = request(&mut test, gltf_handle.clone());
+        
+        test.advance(Duration::ZERO);
+        assert!(test.app.world.resource::<Assets<GltfAnimations>>().get(&animations).is_none());
+        assert_eq!(test.app.world.resource::<GltfAnimationRequests>().pending.len(), 1);
+        
+        test.app.world.resource_mut::<Assets<AnimationClip>>().insert(clip_handle.id(), clip());
+        test.advance(Duration::ZERO);
+        let built = test.app.world.resource::<Assets<GltfAnimations>>().get(&animations).unwrap();
+        assert_eq!(built.gltf(), &gltf_handle);
+        assert_eq!(built.clip("walk"), Some(&clip_handle));
+        assert_eq!(built.clips["walk"].duration, 2.0);
+        assert!(test.app.world.resource::<GltfAnimationRequests>().pending.is_empty());
+    }
+    
+    #[test]
+    fn applies_clip_and_time() {
+        let mut test = test_app();
+        let clip_handle = test.app.world.resource_mut::<Assets<AnimationClip>>().add(clip());
+        let gltf_handle = test.app.world.resource_mut::<Assets<Gltf>>().add(gltf(clip_handle.clone()));
+        let animations = request(&mut test, gltf_handle);
+        let entity = test.app.world.spawn((Animator::new(Walker), animations, GltfAnimationTarget::default())).id();
+        
+        test.advance(Duration::ZERO);
+        test.advance(Duration::from_millis(500));
+        let target = test.app.world.get::<GltfAnimationTarget>(entity).unwrap();
+        assert_eq!(target.clip, Some(clip_handle));
+        assert!((target.time - 0.5).abs() < 1e-5);
+    }
+}
@@ -0,0 +1,338 @@
+//=================================================================================
+// Animation layers let more than one animation play on the same entity. Each
+// layer is a hidden child entity with its own animator, and once every animator
+// has been applied the layers are mixed into the entity they target, based on
+// their weights and masks.
+//=================================================================================
+
+use bevy::{
+    ecs::{entity::{EntityMapper, MapEntities}, reflect::ReflectMapEntities, system::EntityCommands},
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+
+use crate::animation::{Animation, AnimationSet};
+
+//=================================================================================
+//    AnimationLayerPlugin
+//=================================================================================
+
+pub(crate) struct AnimationLayerPlugin;
+
+impl Plugin for AnimationLayerPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .register_type::<AnimationLayer>()
+            .register_type::<LayerMask>()
+            .configure_sets(PostUpdate, AnimationSet::Layer.after(AnimationSet::Animate))
+            .add_systems(PostUpdate, apply_sprite_layers.in_set(AnimationSet::Layer))
+        ;
+
+        #[cfg(any(feature = "blockbench", feature = "spine", feature = "dragonbones"))]
+        app
+            .add_systems(PostUpdate, blend_skeleton_layers.in_set(AnimationSet::Layer))
+        ;
+
+        #[cfg(feature = "property")]
+        app
+            .add_systems(PostUpdate, blend_property_layers.in_set(AnimationSet::Layer))
+        ;
+    }
+}
+
+//=================================================================================
+//    Animation Layer Components
+//=================================================================================
+
+/// Marks an entity as a layer of another entity. The animation on the layer entity is applied to the layer entity as usual, and
+/// is then mixed into the target:
+///
+/// - Skeletons blend each bone in the mask by the weight of the layer. Slots in the mask switch attachments when the weight is at
+///   least 0.5.
+/// - Property clips blend each track in the mask by the weight of the layer. A mask can name a whole component, like `Transform`,
+///   or a single track, like `Transform.translation.y`.
+/// - Sprites can't be blended, so the frame of the layer replaces the frame of each sprite part in the mask when the weight is at
+///   least 0.5. The parts of a target are the target itself and every descendant with a `TextureAtlas`, named by their `Name`.
+///
+/// Layers are mixed in order of their priority, so layers with a higher priority win.
+#[derive(Component, Reflect, Clone, Debug)]
+#[reflect(Component, MapEntities)]
+pub struct AnimationLayer {
+    pub target : Entity,
+    pub weight : f32,
+    pub mask : LayerMask,
+    pub priority : i32,
+}
+
+impl AnimationLayer {
+    /// Creates a layer for the given target, with a weight of 1 and a mask that lets everything through.
+    pub fn new(target : Entity) -> Self {
+        AnimationLayer { target, weight : 1.0, mask : LayerMask::All, priority : 0 }
+    }
+
+    /// Sets the weight of the layer. This is clamped between 0 and 1 when the layer is mixed.
+    pub fn with_weight(mut self, weight : f32) -> Self {
+        self.weight = weight;
+        self
+    }
+
+    /// Sets what bones, slots, tracks or sprite parts the layer affects.
+    pub fn with_mask(mut self, mask : LayerMask) -> Self {
+        self.mask = mask;
+        self
+    }
+
+    /// Sets the priority of the layer. Layers with a higher priority are mixed in later, so they win over lower ones.
+    pub fn with_priority(mut self, priority : i32) -> Self {
+        self.priority = priority;
+        self
+    }
+}
+
+impl MapEntities for AnimationLayer {
+    fn map_entities<M : EntityMapper>(&mut self, entity_mapper : &mut M) {
+        self.target = entity_mapper.map_entity(self.target);
+    }
+}
+
+/// Which bones, slots, tracks or sprite parts a layer affects, by name.
+#[derive(Reflect, Clone, Debug, Default, PartialEq)]
+pub enum LayerMask {
+    #[default]
+    All,
+    Only(HashSet<String>),
+    Except(HashSet<String>),
+}
+
+impl LayerMask {
+    /// A mask that only lets the given names through.
+    pub fn only<S : Into<String>>(names : impl IntoIterator<Item = S>) -> Self {
+        LayerMask::Only(names.into_iter().map(Into::into).collect())
+    }
+
+    /// A mask that lets everything but the given names through.
+    pub fn except<S : Into<String>>(names : impl IntoIterator<Item = S>) -> Self {
+        LayerMask::Except(names.into_iter().map(Into::into).collect())
+    }
+
+    /// Returns true if the mask lets the given name through.
+    pub fn contains(&self, name : &str) -> bool {
+        match self {
+            LayerMask::All => true,
+            LayerMask::Only(names) => names.contains(name),
+            LayerMask::Except(names) => !names.contains(name),
+        }
+    }
+}
+
+/// Groups the layers in the query by their target, sorted by priority.
+fn layers_by_target<'a, T>(layers : impl Iterator<Item = (&'a AnimationLayer, T)>) -> HashMap<Entity, Vec<(&'a AnimationLayer, T)>> {
+    let mut by_target : HashMap<Entity, Vec<(&AnimationLayer, T)>> = HashMap::default();
+    for (layer, item) in layers {
+        by_target.entry(layer.target).or_default().push((layer, item));
+    }
+    for layers in by_target.values_mut() {
+        layers.sort_by_key(|(layer, _)| layer.priority);
+    }
+    by_target
+}
+
+//=================================================================================
+//    Animation Layer Systems
+//=================================================================================
+
+/// A sprite part of a layer target, with its name.
+type SpritePart = (Option<&'static Name>, &'static mut TextureAtlas, &'static mut Handle<Image>);
+
+/// Copies the frame of the winning layer onto each sprite part of a target.
+fn apply_sprite_layers(
+    layers : Query<(&AnimationLayer, &TextureAtlas, &Handle<Image>)>,
+    children : Query<&Children>,
+    mut parts : Query<SpritePart, Without<AnimationLayer>>,
+) {
+    for (target, layers) in layers_by_target(layers.iter().map(|(layer, atlas, image)| (layer, (atlas, image)))) {
+        for part in std::iter::once(target).chain(children.iter_descendants(target)) {
+            let Ok((name, mut atlas, mut image)) = parts.get_mut(part) else { continue };
+            let name = name.map_or("", Name::as_str);
+            let winner = layers.iter().rev().find(|(layer, _)| layer.weight >= 0.5 && layer.mask.contains(name));
+            let Some((_, (layer_atlas, layer_image))) = winner else { continue };
+            if atlas.index != layer_atlas.index { atlas.index = layer_atlas.index; }
+            if atlas.layout != layer_atlas.layout { atlas.layout = layer_atlas.layout.clone(); }
+            if *image != **layer_image { *image = (*layer_image).clone(); }
+        }
+    }
+}
+
+/// Blends the pose of each skeleton layer into the pose of its target.
+#[cfg(any(feature = "blockbench", feature = "spine", feature = "dragonbones"))]
+fn blend_skeleton_layers(
+    layers : Query<(&AnimationLayer, &crate::skeleton::SkeletonPose)>,
+    mut targets : Query<&mut crate::skeleton::SkeletonPose, Without<AnimationLayer>>,
+) {
+    for (target, layers) in layers_by_target(layers.iter()) {
+        let Ok(mut target) = targets.get_mut(target) else { continue };
+        for (layer, pose) in layers {
+            let weight = layer.weight.clamp(0.0, 1.0);
+            if weight <= 0.0 { continue }
+            for (bone, transform) in pose.bones.iter() {
+                if !layer.mask.contains(bone) { continue }
+                let blended = match target.bones.get(bone) {
+                    Some(base) => Transform {
+                        translation : base.translation.lerp(transform.translation, weight),
+                        rotation : base.rotation.slerp(transform.rotation, weight),
+                        scale : base.scale.lerp(transform.scale, weight),
+                    },
+                    None => *transform,
+                };
+                target.bones.insert(bone.clone(), blended);
+            }
+            if weight < 0.5 { continue }
+            for (slot, attachment) in pose.attachments.iter() {
+                if !layer.mask.contains(slot) { continue }
+                target.attachments.insert(slot.clone(), attachment.clone());
+            }
+        }
+    }
+}
+
+/// Blends the tracks of each property layer into the pose of its target.
+#[cfg(feature = "property")]
+fn blend_property_layers(
+    layers : Query<(&AnimationLayer, &crate::property::PropertyPose)>,
+    mut targets : Query<&mut crate::property::PropertyPose, Without<AnimationLayer>>,
+) {
+    for (target, layers) in layers_by_target(layers.iter()) {
+        let Ok(mut target) = targets.get_mut(target) else { continue };
+        for (layer, pose) in layers {
+            let weight = layer.weight.clamp(0.0, 1.0);
+            if weight <= 0.0 { continue }
+            for (track, value) in pose.values.iter() {
+                let component = track.split_once('.').map_or(track.as_str(), |(component, _)| component);
+                if !layer.mask.contains(track) && !layer.mask.contains(component) { continue }
+                match target.values.iter_mut().find(|(base_track, _)| base_track == track) {
+                    Some((_, base)) => *base = base.lerp(value, weight),
+                    None => target.values.push((track.clone(), *value)),
+                }
+            }
+        }
+    }
+}
+
+//=================================================================================
+//    Animation Layer Commands
+//=================================================================================
+
+pub trait AddAnimationLayerCommand {
+    /// Spawns a layer entity as a hidden child of the layer's target, and spawns the animation on it with `Animation::spawn_layer`.
+    fn add_animation_layer<A : Animation + Send + Sync + 'static>(&mut self, animation : A, path : &str, layer : AnimationLayer) -> EntityCommands<'_>;
+}
+
+impl <'w, 's> AddAnimationLayerCommand for Commands<'w, 's> {
+    fn add_animation_layer<A : Animation + Send + Sync + 'static>(&mut self, animation : A, path : &str, layer : AnimationLayer) -> EntityCommands<'_> {
+        let entity = self.spawn_empty().id();
+        let path = path.to_string();
+        self.add(move |world : &mut World| {
+            let target = layer.target;
+            A::spawn_layer(animation, world, path, entity);
+            world.entity_mut(entity).insert((layer, VisibilityBundle { visibility : Visibility::Hidden, ..Default::default() }));
+            if let Some(mut target) = world.get_entity_mut(target) {
+                target.add_child(entity);
+            }
+        });
+        self.entity(entity)
+    }
+}
+
+//=================================================================================
+//    Tests
+//=================================================================================
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::{entity::{EntityHashMap, SceneEntityMapper}, system::RunSystemOnce};
+    use super::*;
+    
+    #[test]
+    fn masks_names() {
+        assert!(LayerMask::All.contains("arm"));
+        assert!(LayerMask::only(["arm"]).contains("arm"));
+        assert!(!LayerMask::only(["arm"]).contains("leg"));
+        assert!(!LayerMask::except(["arm"]).contains("arm"));
+        assert!(LayerMask::except(["arm"]).contains("leg"));
+    }
+    
+    #[test]
+    fn maps_target_entities() {
+        let mut world = World::new();
+        let target = world.spawn_empty().id();
+        let mapped = world.spawn_empty().id();
+        let mut layer = AnimationLayer::new(target);
+        let mut entities = EntityHashMap::default();
+        entities.insert(target, mapped);
+        SceneEntityMapper::world_scope(&mut entities, &mut world, |_, mapper| layer.map_entities(mapper));
+        assert_eq!(layer.target, mapped);
+    }
+    
+    #[test]
+    fn applies_winning_sprite_layer() {
+        let mut world = World::new();
+        let sprite = |index| (TextureAtlas { layout : Handle::default(), index }, Handle::<Image>::default());
+        let target = world.spawn(sprite(0)).id();
+        let arm = world.spawn((sprite(0), Name::new("arm"))).id();
+        let leg = world.spawn((sprite(0), Name::new("leg"))).id();
+        world.entity_mut(target).push_children(&[arm, leg]);
+        world.spawn((AnimationLayer::new(target).with_priority(1).with_mask(LayerMask::only(["arm"])), sprite(2)));
+        world.spawn((AnimationLayer::new(target), sprite(1)));
+        world.spawn((AnimationLayer::new(target).with_priority(2).with_weight(0.4), sprite(3)));
+        world.run_system_once(apply_sprite_layers);
+        
+        assert_eq!(world.get::<TextureAtlas>(target).unwrap().index, 1);
+        assert_eq!(world.get::<TextureAtlas>(arm).unwrap().index, 2);
+        assert_eq!(world.get::<TextureAtlas>(leg).unwrap().index, 1);
+    }
+    
+    #[cfg(any(feature = "blockbench", feature = "spine", feature = "dragonbones"))]
+    #[test]
+    fn blends_skeleton_layers() {
+        use crate::skeleton::SkeletonPose;
+        
+        let mut world = World::new();
+        let mut base = SkeletonPose::default();
+        base.bones.insert("arm".to_string(), Transform::from_xyz(0.0, 0.0, 0.0));
+        base.bones.insert("leg".to_string(), Transform::from_xyz(0.0, 0.0, 0.0));
+        base.attachments.insert("hand".to_string(), Some("open".to_string()));
+        let target = world.spawn(base).id();
+        
+        let mut pose = SkeletonPose::default();
+        pose.bones.insert("arm".to_string(), Transform::from_xyz(4.0, 0.0, 0.0));
+        pose.bones.insert("leg".to_string(), Transform::from_xyz(4.0, 0.0, 0.0));
+        pose.attachments.insert("hand".to_string(), Some("fist".to_string()));
+        world.spawn((AnimationLayer::new(target).with_weight(0.25).with_mask(LayerMask::except(["leg"])), pose));
+        world.run_system_once(blend_skeleton_layers);
+        
+        let blended = world.get::<SkeletonPose>(target).unwrap();
+        assert_eq!(blended.bones["arm"].translation, Vec3::new(1.0, 0.0, 0.0));
+        assert_eq!(blended.bones["leg"].translation, Vec3::ZERO);
+        assert_eq!(blended.attachments["hand"], Some("open".to_string()));
+    }
+    
+    #[cfg(feature = "property")]
+    #[test]
+    fn blends_property_layers() {
+        use crate::property::{PropertyPose, PropertyValue};
+        
+        let pose = |x, y| PropertyPose { values : vec![
+            ("Transform.translation.x".to_string(), PropertyValue::Float(x)),
+            ("Sprite.custom_size.x".to_string(), PropertyValue::Float(y)),
+        ]};
+        let mut world = World::new();
+        let target = world.spawn(pose(0.0, 0.0)).id();
+        world.spawn((AnimationLayer::new(target).with_priority(1).with_weight(0.5).with_mask(LayerMask::only(["Transform"])), pose(8.0, 8.0)));
+        world.spawn((AnimationLayer::new(target).with_mask(LayerMask::only(["Transform.translation.x"])), pose(4.0, 4.0)));
+        world.run_system_once(blend_property_layers);
+        
+        let blended = world.get::<PropertyPose>(target).unwrap();
+        assert_eq!(blended.values[0].1, PropertyValue::Float(6.0));
+        assert_eq!(blended.values[1].1, PropertyValue::Float(0.0));
+    }
+}
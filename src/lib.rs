@@ -1,21 +1,93 @@
 pub mod animation;
+pub mod clip;
+pub mod debug;
+pub mod easing;
+pub mod layer;
+pub mod scene;
 pub mod state;
+pub mod sync;
+pub mod time;
 pub mod util;
 
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+
 #[cfg(feature = "aseprite")]
 pub mod aseprite;
 
+#[cfg(feature = "sprite_sheet")]
+pub mod sprite_sheet;
+
+#[cfg(feature = "texture_packer")]
+pub mod texture_packer;
+
+#[cfg(feature = "animated_image")]
+pub mod animated_image;
+
+#[cfg(feature = "blockbench")]
+pub mod blockbench;
+
+#[cfg(feature = "gltf")]
+pub mod gltf;
+
+#[cfg(feature = "spine")]
+pub mod spine;
+
+#[cfg(feature = "dragonbones")]
+pub mod dragonbones;
+
+#[cfg(feature = "property")]
+pub mod property;
+
+#[cfg(any(feature = "blockbench", feature = "spine", feature = "dragonbones"))]
+pub mod skeleton;
+
 use bevy::{ecs::system::EntityCommands, prelude::*};
 use animation::Animation;
 
 pub mod prelude {
     pub use crate::AnimatorPlugin;
-    pub use crate::animation::{Animation, Animator, AnimationPlugin};
+    pub use crate::animation::{Animation, AnimationBackend, AnimationQueueEvent, AnimationSet, Animator, AnimatorDebug, AnimatorTicks, AnimationPlugin};
+    pub use crate::debug::{AnimatorDebugOverlay, AnimatorDebugPlugin};
+    pub use crate::easing::{AnimatorEasing, Easing, EasingCurve};
+    pub use crate::scene::AnimationSource;
     pub use crate::state::{AnimationState, AnimationStatePlugin};
+    pub use crate::sync::{AnimationSyncGroup, AnimationSyncGroups};
+    pub use crate::time::{AnimationHitStop, AnimationTime, AnimationTimeSource};
     pub use crate::{InitAnimationCommand, InsertAnimationCommand};
+    pub use crate::layer::{AddAnimationLayerCommand, AnimationLayer, LayerMask};
     
     #[cfg(feature = "aseprite")]
-    pub use crate::aseprite::{Aseprite, AsepriteAnimation};
+    pub use crate::aseprite::{Aseprite, AsepriteAnimation, AsepriteAtlasGroups, AsepriteTileGrid};
+    
+    #[cfg(feature = "sprite_sheet")]
+    pub use crate::sprite_sheet::{SpriteSheet, SpriteSheetAnimation, SpriteSheetBackend};
+    
+    #[cfg(feature = "texture_packer")]
+    pub use crate::texture_packer::{TexturePackerAtlas, TexturePackerAnimation, TexturePackerBackend};
+    
+    #[cfg(feature = "animated_image")]
+    pub use crate::animated_image::{AnimatedImage, AnimatedImageAnimation, AnimatedImageBackend};
+    
+    #[cfg(feature = "blockbench")]
+    pub use crate::blockbench::{BlockBenchAnimation, BlockBenchBackend, BlockBenchModel};
+    
+    #[cfg(feature = "gltf")]
+    pub use crate::gltf::{GltfAnimation, GltfAnimations, GltfBackend};
+    
+    #[cfg(feature = "spine")]
+    pub use crate::spine::{SpineAnimation, SpineBackend, SpineSkeleton};
+    
+    #[cfg(feature = "dragonbones")]
+    pub use crate::dragonbones::{DragonBonesAnimation, DragonBonesArmature, DragonBonesBackend};
+    
+    #[cfg(feature = "property")]
+    pub use crate::property::{PropertyAnimation, PropertyBackend, PropertyClip, PropertyClips, PropertyTrack, PropertyValue};
+    
+    #[cfg(any(feature = "blockbench", feature = "spine", feature = "dragonbones"))]
+    pub use crate::skeleton::{SkeletonAttachment, SkeletonBone, SkeletonPose};
+    
+    pub use crate::impl_animation;
 }
 
 //=================================================================================
@@ -27,14 +99,127 @@ pub struct AnimatorPlugin;
 
 impl Plugin for AnimatorPlugin {
     fn build(&self, app: &mut App) {
-        if cfg!(feature = "aseprite") {
-            app
-                .add_plugins(aseprite::AsepriteAnimationPlugin)
-            ;
-        }
+        app
+            .init_asset::<easing::EasingCurve>()
+            .init_resource::<sync::AnimationSyncGroups>()
+            .register_type::<sync::AnimationSyncGroup>()
+            .register_type::<sync::SyncStart>()
+            .register_type::<animation::AnimatorDebug>()
+            .register_type::<animation::AnimatorTicks>()
+            .register_type::<Option<animation::AnimatorTicks>>()
+            .register_type::<Option<u32>>()
+            .register_type::<Option<usize>>()
+            .register_type::<Option<String>>()
+            .init_resource::<time::AnimationTime>()
+            .register_type::<time::AnimationTime>()
+            .register_type::<time::AnimationTimeSource>()
+            .register_type::<time::AnimationHitStop>()
+            .add_plugins(layer::AnimationLayerPlugin)
+        ;
+        
+        #[cfg(feature = "aseprite")]
+        app
+            .add_plugins(aseprite::AsepriteAnimationPlugin)
+        ;
+        
+        #[cfg(feature = "sprite_sheet")]
+        app
+            .add_plugins(sprite_sheet::SpriteSheetAnimationPlugin)
+        ;
+        
+        #[cfg(feature = "texture_packer")]
+        app
+            .add_plugins(texture_packer::TexturePackerAnimationPlugin)
+        ;
+        
+        #[cfg(feature = "animated_image")]
+        app
+            .add_plugins(animated_image::AnimatedImageAnimationPlugin)
+        ;
+        
+        #[cfg(feature = "blockbench")]
+        app
+            .add_plugins(blockbench::BlockBenchAnimationPlugin)
+        ;
+        
+        #[cfg(feature = "gltf")]
+        app
+            .add_plugins(gltf::GltfAnimationPlugin)
+        ;
+        
+        #[cfg(feature = "spine")]
+        app
+            .add_plugins(spine::SpineAnimationPlugin)
+        ;
+        
+        #[cfg(feature = "dragonbones")]
+        app
+            .add_plugins(dragonbones::DragonBonesAnimationPlugin)
+        ;
+        
+        #[cfg(feature = "property")]
+        app
+            .add_plugins(property::PropertyAnimationPlugin)
+        ;
     }
 }
 
+//=================================================================================
+//    Animation Backend Macro
+//=================================================================================
+
+/// Implements `Animation` for a type by forwarding to an `AnimationBackend`. 
+/// 
+/// ```ignore
+/// impl SpriteSheetAnimation for Fire {
+///     fn get_clip_name(&self) -> &str { "burn" }
+/// }
+/// 
+/// impl_animation!(Fire, SpriteSheetBackend);
+/// ```
+#[macro_export]
+macro_rules! impl_animation {
+    ($animation:ty, $backend:ty) => {
+        impl $crate::animation::Animation for $animation {
+            type AsociatedAsset = <$backend as $crate::animation::AnimationBackend<$animation>>::AsociatedAsset;
+            
+            type Query<'w, 's> = <$backend as $crate::animation::AnimationBackend<$animation>>::Query<'w, 's>;
+            
+            fn apply(
+                animator : &$crate::animation::Animator<Self>,
+                items : &mut <Self::Query<'_, '_> as ::bevy::ecs::query::WorldQuery>::Item<'_>,
+                asset : &Self::AsociatedAsset,
+            ) {
+                <$backend as $crate::animation::AnimationBackend<$animation>>::apply(animator, items, asset)
+            }
+            
+            fn spawn(animation : Option<Self>, world : &mut ::bevy::prelude::World, path : String, entity : ::bevy::prelude::Entity) {
+                <$backend as $crate::animation::AnimationBackend<$animation>>::spawn(animation, world, path, entity)
+            }
+            
+            fn spawn_layer(animation : Self, world : &mut ::bevy::prelude::World, path : String, entity : ::bevy::prelude::Entity) {
+                <$backend as $crate::animation::AnimationBackend<$animation>>::spawn_layer(animation, world, path, entity)
+            }
+            
+            fn duration(&self, asset : &Self::AsociatedAsset) -> f32 {
+                <$backend as $crate::animation::AnimationBackend<$animation>>::duration(self, asset)
+            }
+            
+            fn debug_name(&self) -> Option<&str> {
+                <$backend as $crate::animation::AnimationBackend<$animation>>::debug_name(self)
+            }
+            
+            fn debug_frame(&self, progress : f32, asset : &Self::AsociatedAsset) -> Option<usize> {
+                <$backend as $crate::animation::AnimationBackend<$animation>>::debug_frame(self, progress, asset)
+            }
+            
+            fn build(app : &mut ::bevy::prelude::App) {
+                <$backend as $crate::animation::AnimationBackend<$animation>>::build(app)
+            }
+        }
+    };
+}
+
 //=================================================================================
 //    Animation Spawn Commands
 //=================================================================================
@@ -0,0 +1,437 @@
+//=================================================================================
+// Here we are defining the Property Clip AssetFile and Animation Backend. A
+// property clip has tracks that target component fields by their reflect path,
+// like `Transform.translation.y` or `Sprite.color`, so any reflected component
+// can be animated by an `Animator` and driven by an `AnimationState`.
+//=================================================================================
+
+use std::fmt::Display;
+use bevy::{asset::{AssetLoader, AsyncReadExt}, ecs::query::WorldQuery, prelude::*, reflect::GetPath, transform::TransformSystem, utils::{HashMap, HashSet}};
+use serde::{Deserialize, Serialize};
+
+use crate::{animation::{Animation, AnimationBackend, AnimationSet, Animator}, easing::Easing, layer::AnimationLayer};
+
+//=================================================================================
+//    PropertyAnimationPlugin
+//=================================================================================
+
+pub(crate) struct PropertyAnimationPlugin;
+
+impl Plugin for PropertyAnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_asset_loader::<PropertyClipsLoader>()
+            .init_asset::<PropertyClips>()
+            .add_systems(PostUpdate, apply_property_poses.after(AnimationSet::Layer).before(TransformSystem::TransformPropagate))
+        ;
+    }
+}
+
+//=================================================================================
+//    Property Clips Asset
+//=================================================================================
+
+/// The Property Clips Asset. Stores named clips, each with a set of tracks that animate component fields.
+#[derive(Asset, TypePath, Serialize, Deserialize, Clone, Debug, Default)]
+pub struct PropertyClips {
+    pub clips : HashMap<String, PropertyClip>,
+}
+
+/// A clip of property tracks. The clip lasts until the last keyframe of its longest track.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct PropertyClip {
+    pub tracks : Vec<PropertyTrack>,
+}
+
+/// The keyframes of a single component field. `target` is the name of the component followed by the reflect path of the field,
+/// for example `Transform.translation.y`. The component can be named by its short or full type path.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct PropertyTrack {
+    pub target : String,
+    pub keyframes : Vec<PropertyKeyframe>,
+}
+
+/// A value at a point in time. The easing shapes the transition from this keyframe to the next one.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PropertyKeyframe {
+    pub time : f32,
+    pub value : PropertyValue,
+    #[serde(default)]
+    pub easing : Easing,
+}
+
+/// The types of values that a property track can animate.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum PropertyValue {
+    Float(f32),
+    Vec2(Vec2),
+    Vec3(Vec3),
+    Vec4(Vec4),
+    Quat(Quat),
+    Color(Color),
+}
+
+impl PropertyClips {
+    /// Returns the clip with the given name.
+    pub fn clip(&self, name : &str) -> Option<&PropertyClip> {
+        self.clips.get(name)
+    }
+
+    /// Parses clips written in RON. Keyframes can be written in any order, and are sorted by time.
+    fn from_ron(bytes : &[u8]) -> Result<Self, PropertyClipsLoaderError> {
+        let mut clips : PropertyClips = ron::de::from_bytes(bytes).map_err(PropertyClipsLoaderError::Ron)?;
+        for track in clips.clips.values_mut().flat_map(|clip| clip.tracks.iter_mut()) {
+            track.keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+        }
+        Ok(clips)
+    }
+}
+
+impl PropertyClip {
+    /// Creates an empty clip.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a track to the clip.
+    pub fn with_track(mut self, track : PropertyTrack) -> Self {
+        self.tracks.push(track);
+        self
+    }
+
+    /// The length of the clip in seconds.
+    pub fn duration(&self) -> f32 {
+        self.tracks.iter()
+            .filter_map(|track| track.keyframes.iter().map(|keyframe| keyframe.time).reduce(f32::max))
+            .fold(0.0, f32::max)
+    }
+}
+
+impl PropertyTrack {
+    /// Creates a track without any keyframes.
+    pub fn new(target : impl Into<String>) -> Self {
+        PropertyTrack { target : target.into(), keyframes : Vec::new() }
+    }
+
+    /// Adds a keyframe to the track. Keyframes are kept sorted by time.
+    pub fn with_keyframe(mut self, time : f32, value : impl Into<PropertyValue>, easing : Easing) -> Self {
+        let index = self.keyframes.partition_point(|keyframe| keyframe.time <= time);
+        self.keyframes.insert(index, PropertyKeyframe { time, value : value.into(), easing });
+        self
+    }
+
+    /// Samples the track at the given time in seconds. Returns `None` if the track has no keyframes.
+    pub fn sample(&self, time : f32) -> Option<PropertyValue> {
+        let next = self.keyframes.partition_point(|keyframe| keyframe.time <= time);
+        if next == 0 { return self.keyframes.first().map(|keyframe| keyframe.value) }
+        if next == self.keyframes.len() { return self.keyframes.last().map(|keyframe| keyframe.value) }
+
+        let (from, to) = (&self.keyframes[next - 1], &self.keyframes[next]);
+        let t = (time - from.time) / (to.time - from.time).max(f32::EPSILON);
+        Some(from.value.lerp(&to.value, from.easing.ease(t)))
+    }
+}
+
+impl PropertyValue {
+    /// Interpolates between two values of the same type. If the types don't match, this value is kept.
+    pub fn lerp(&self, other : &PropertyValue, t : f32) -> PropertyValue {
+        match (self, other) {
+            (PropertyValue::Float(a), PropertyValue::Float(b)) => PropertyValue::Float(a + (b - a) * t),
+            (PropertyValue::Vec2(a), PropertyValue::Vec2(b)) => PropertyValue::Vec2(a.lerp(*b, t)),
+            (PropertyValue::Vec3(a), PropertyValue::Vec3(b)) => PropertyValue::Vec3(a.lerp(*b, t)),
+            (PropertyValue::Vec4(a), PropertyValue::Vec4(b)) => PropertyValue::Vec4(a.lerp(*b, t)),
+            (PropertyValue::Quat(a), PropertyValue::Quat(b)) => PropertyValue::Quat(a.slerp(*b, t)),
+            (PropertyValue::Color(a), PropertyValue::Color(b)) => {
+                let color = Vec4::from(a.as_rgba_f32()).lerp(Vec4::from(b.as_rgba_f32()), t);
+                PropertyValue::Color(Color::rgba(color.x, color.y, color.z, color.w))
+            },
+            _ => *self,
+        }
+    }
+
+    fn into_reflect(self) -> Box<dyn Reflect> {
+        match self {
+            PropertyValue::Float(value) => Box::new(value),
+            PropertyValue::Vec2(value) => Box::new(value),
+            PropertyValue::Vec3(value) => Box::new(value),
+            PropertyValue::Vec4(value) => Box::new(value),
+            PropertyValue::Quat(value) => Box::new(value),
+            PropertyValue::Color(value) => Box::new(value),
+        }
+    }
+}
+
+impl From<f32> for PropertyValue {
+    fn from(value : f32) -> Self { PropertyValue::Float(value) }
+}
+
+impl From<Vec2> for PropertyValue {
+    fn from(value : Vec2) -> Self { PropertyValue::Vec2(value) }
+}
+
+impl From<Vec3> for PropertyValue {
+    fn from(value : Vec3) -> Self { PropertyValue::Vec3(value) }
+}
+
+impl From<Vec4> for PropertyValue {
+    fn from(value : Vec4) -> Self { PropertyValue::Vec4(value) }
+}
+
+impl From<Quat> for PropertyValue {
+    fn from(value : Quat) -> Self { PropertyValue::Quat(value) }
+}
+
+impl From<Color> for PropertyValue {
+    fn from(value : Color) -> Self { PropertyValue::Color(value) }
+}
+
+//=================================================================================
+//    Property Clips Asset Loader
+//=================================================================================
+
+/// Asset Loader for `.props.ron` files, which are a `PropertyClips` written in RON.
+#[derive(Default)]
+pub struct PropertyClipsLoader;
+
+/// The errors that can occur while loading property clips.
+#[derive(Debug)]
+pub enum PropertyClipsLoaderError {
+    Io(std::io::Error),
+    Ron(ron::error::SpannedError),
+}
+
+impl Display for PropertyClipsLoaderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PropertyClipsLoaderError::Io(error) => write!(f, "Could not read property clips: {}", error),
+            PropertyClipsLoaderError::Ron(error) => write!(f, "Could not parse property clips: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for PropertyClipsLoaderError {}
+
+impl From<std::io::Error> for PropertyClipsLoaderError {
+    fn from(error: std::io::Error) -> Self {
+        PropertyClipsLoaderError::Io(error)
+    }
+}
+
+impl AssetLoader for PropertyClipsLoader {
+    type Asset = PropertyClips;
+
+    type Settings = ();
+
+    type Error = PropertyClipsLoaderError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut bevy::asset::io::Reader,
+        _: &'a Self::Settings,
+        _: &'a mut bevy::asset::LoadContext,
+    ) -> bevy::utils::BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            PropertyClips::from_ron(&bytes)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["props.ron"]
+    }
+}
+
+//=================================================================================
+//    Property Components
+//=================================================================================
+
+/// The value of every track this tick, by target. An animation can only write to the components in its query, so it writes the
+/// values here, and `apply_property_poses` writes them into the targeted components through reflection.
+#[derive(Component, Default, Clone, Debug)]
+pub struct PropertyPose {
+    pub values : Vec<(String, PropertyValue)>,
+}
+
+//=================================================================================
+//    Property Systems
+//=================================================================================
+
+/// Writes every changed `PropertyPose` into the components that its tracks target. Layers are skipped, since they are mixed into
+/// the pose of their target instead. The components have to be registered with
+/// `#[reflect(Component)]`. Targets that can't be found or that don't match the type of their value are skipped with a warning.
+fn apply_property_poses(world : &mut World, mut warned : Local<HashSet<String>>) {
+    let poses = world.query_filtered::<(Entity, &PropertyPose), (Changed<PropertyPose>, Without<AnimationLayer>)>()
+        .iter(world)
+        .map(|(entity, pose)| (entity, pose.values.clone()))
+        .collect::<Vec<_>>();
+    if poses.is_empty() { return }
+
+    let registry = world.resource::<AppTypeRegistry>().clone();
+    let registry = registry.read();
+    for (entity, values) in poses {
+        for (target, value) in values {
+            let (component, path) = target.split_once('.').unwrap_or((&target, ""));
+            let reflect_component = registry.get_with_short_type_path(component)
+                .or_else(|| registry.get_with_type_path(component))
+                .and_then(|registration| registration.data::<ReflectComponent>());
+            let Some(reflect_component) = reflect_component else {
+                if warned.insert(target.clone()) { warn!("Property track '{}' targets a component that isn't registered with #[reflect(Component)]", target); }
+                continue;
+            };
+            let mut entity_mut = world.entity_mut(entity);
+            let Some(mut reflected) = reflect_component.reflect_mut(&mut entity_mut) else { continue };
+            let field = if path.is_empty() { Ok(reflected.as_reflect_mut()) } else { reflected.reflect_path_mut(path) };
+            let Ok(field) = field else {
+                if warned.insert(target.clone()) { warn!("Property track '{}' targets a field that doesn't exist", target); }
+                continue;
+            };
+            if field.set(value.into_reflect()).is_err() && warned.insert(target.clone()) {
+                warn!("Property track '{}' has a value of the wrong type for its field", target);
+            }
+        }
+    }
+}
+
+//=================================================================================
+//    Property Animation
+//=================================================================================
+
+/// This trait will allow you to animate the fields of any reflected component. Implement `Animation` for the type with
+/// `impl_animation!(MyAnimation, PropertyBackend)`.
+pub trait PropertyAnimation : Sized {
+
+    /// Animations are defined by name inside of the `.props.ron` file. This function will tell bevy what clip to use based on the
+    /// current state of the struct that implements this trait.
+    fn get_clip_name(&self) -> &str;
+}
+
+/// The animation backend for property clips. See `PropertyAnimation`.
+pub struct PropertyBackend;
+
+impl <A : PropertyAnimation + Animation + FromWorld + Send + Sync + 'static> AnimationBackend<A> for PropertyBackend {
+    type AsociatedAsset = PropertyClips;
+
+    type Query<'w, 's> = &'w mut PropertyPose;
+
+    fn apply(
+        animator : &Animator<A>,
+        items : &mut <Self::Query<'_, '_> as WorldQuery>::Item<'_>,
+        asset : &Self::AsociatedAsset,
+    ) {
+        let Some(clip) = asset.clips.get(animator.animation.get_clip_name()) else { return };
+        let time = animator.progress() * clip.duration();
+        items.values.clear();
+        for track in clip.tracks.iter() {
+            let Some(value) = track.sample(time) else { continue };
+            items.values.push((track.target.clone(), value));
+        }
+    }
+
+    fn spawn(animation : Option<A>, world : &mut World, path : String, entity : Entity) {
+        let animation_comp = animation.unwrap_or(A::from_world(world));
+        let clips : Handle<PropertyClips> = world.resource::<AssetServer>().load(&path);
+
+        world.get_or_spawn(entity).unwrap()
+            .insert(Animator::new(animation_comp))
+            .insert(clips)
+            .insert(PropertyPose::default())
+        ;
+    }
+
+    fn spawn_layer(animation : A, world : &mut World, path : String, entity : Entity) {
+        let clips : Handle<PropertyClips> = world.resource::<AssetServer>().load(&path);
+
+        world.get_or_spawn(entity).unwrap()
+            .insert(Animator::new(animation))
+            .insert(clips)
+            .insert(PropertyPose::default())
+        ;
+    }
+
+    fn duration(animation : &A, asset : &Self::AsociatedAsset) -> f32 {
+        asset.clips.get(animation.get_clip_name()).map_or(0.0, |clip| clip.duration())
+    }
+    
+    fn debug_name(animation : &A) -> Option<&str> {
+        Some(animation.get_clip_name())
+    }
+}
+
+//=================================================================================
+//    Tests
+//=================================================================================
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+    use super::*;
+    
+    const CLIPS : &str = r#"(
+        clips : {
+            "bob" : (tracks : [
+                (target : "Transform.translation.y", keyframes : [
+                    (time : 1.0, value : Float(4.0)),
+                    (time : 0.0, value : Float(0.0), easing : QuadIn),
+                ]),
+                (target : "Sprite.color", keyframes : [
+                    (time : 0.0, value : Color(Rgba(red : 1.0, green : 0.0, blue : 0.0, alpha : 1.0))),
+                    (time : 2.0, value : Color(Rgba(red : 0.0, green : 0.0, blue : 1.0, alpha : 1.0))),
+                ]),
+            ]),
+        },
+    )"#;
+    
+    #[test]
+    fn parses_clips() {
+        let clips = PropertyClips::from_ron(CLIPS.as_bytes()).unwrap();
+        let bob = clips.clip("bob").unwrap();
+        assert_eq!(bob.tracks.len(), 2);
+        assert_eq!(bob.duration(), 2.0);
+        
+        let bounce = &bob.tracks[0];
+        assert_eq!(bounce.target, "Transform.translation.y");
+        assert_eq!(bounce.keyframes.iter().map(|keyframe| keyframe.time).collect::<Vec<_>>(), vec![0.0, 1.0]);
+        assert_eq!(bounce.keyframes[0].easing, Easing::QuadIn);
+        assert_eq!(bounce.keyframes[1].easing, Easing::Linear);
+        
+        assert!(matches!(PropertyClips::from_ron(b"(clips : 4)"), Err(PropertyClipsLoaderError::Ron(_))));
+    }
+    
+    #[test]
+    fn samples_tracks() {
+        let clips = PropertyClips::from_ron(CLIPS.as_bytes()).unwrap();
+        let bob = clips.clip("bob").unwrap();
+        assert_eq!(bob.tracks[0].sample(-1.0), Some(PropertyValue::Float(0.0)));
+        assert_eq!(bob.tracks[0].sample(0.5), Some(PropertyValue::Float(1.0)));
+        assert_eq!(bob.tracks[0].sample(3.0), Some(PropertyValue::Float(4.0)));
+        assert_eq!(bob.tracks[1].sample(1.0), Some(PropertyValue::Color(Color::rgba(0.5, 0.0, 0.5, 1.0))));
+        assert_eq!(PropertyTrack::new("Transform.scale").sample(0.0), None);
+        
+        let track = PropertyTrack::new("Transform.translation")
+            .with_keyframe(2.0, Vec3::X, Easing::Linear)
+            .with_keyframe(0.0, Vec3::ZERO, Easing::Step);
+        assert_eq!(track.sample(1.0), Some(PropertyValue::Vec3(Vec3::ZERO)));
+        assert_eq!(track.sample(2.0), Some(PropertyValue::Vec3(Vec3::X)));
+        assert_eq!(PropertyValue::Float(1.0).lerp(&PropertyValue::Vec2(Vec2::ONE), 0.5), PropertyValue::Float(1.0));
+    }
+    
+    #[test]
+    fn applies_poses_through_reflection() {
+        let mut world = World::default();
+        world.init_resource::<AppTypeRegistry>();
+        world.resource::<AppTypeRegistry>().write().register::<Transform>();
+        let entity = world.spawn((Transform::default(), PropertyPose { values : vec![
+            ("Transform.translation.y".to_string(), PropertyValue::Float(3.0)),
+            ("bevy_transform::components::transform::Transform.scale".to_string(), PropertyValue::Vec3(Vec3::splat(2.0))),
+            ("Transform.rotation".to_string(), PropertyValue::Float(1.0)),
+            ("Sprite.color".to_string(), PropertyValue::Color(Color::RED)),
+        ] })).id();
+        world.run_system_once(apply_property_poses);
+        
+        let transform = world.get::<Transform>(entity).unwrap();
+        assert_eq!(transform.translation, Vec3::new(0.0, 3.0, 0.0));
+        assert_eq!(transform.scale, Vec3::splat(2.0));
+        assert_eq!(transform.rotation, Quat::IDENTITY);
+    }
+}
@@ -0,0 +1,170 @@
+//=================================================================================
+// Animation sources describe an animated entity with data alone, so that it can
+// be saved in a scene and set up again when the scene is loaded. The setup that
+// `Animation::spawn` does is run when the source is added to an entity.
+//=================================================================================
+
+use bevy::prelude::*;
+
+use crate::animation::{Animation, Animator};
+
+//=================================================================================
+//    Animation Source Component
+//=================================================================================
+
+/// Describes the animation on an entity by the path of its asset and the animation to play. When this component is added, the
+/// entity is set up with `Animation::spawn`, just like `insert_animation` does, so an animated entity can be spawned from data or
+/// loaded from a `DynamicScene`. An `Animator` or `Transform` that is already on the entity, for example one that was saved in
+/// the scene, is kept.
+///
+/// The source is registered for reflection, along with the system that sets it up, by `AnimationPlugin::reflect`.
+#[derive(Component, Reflect, Clone, Debug)]
+#[reflect(Component)]
+pub struct AnimationSource<A : Animation> {
+    pub path : String,
+    pub animation : A,
+}
+
+impl <A : Animation> AnimationSource<A> {
+    /// Creates a source for the asset at the given path, playing the given animation.
+    pub fn new(path : impl Into<String>, animation : A) -> Self {
+        AnimationSource { path : path.into(), animation }
+    }
+}
+
+//=================================================================================
+//    Animation Source Systems
+//=================================================================================
+
+/// An entity with a source, along with the asset it has already been given, if any.
+type SourcedEntity<A> = (Entity, &'static AnimationSource<A>, Option<&'static Handle<<A as Animation>::AsociatedAsset>>);
+
+/// Sets up the entities that were given an `AnimationSource`, unless the entity already has the asset at that path.
+pub(crate) fn spawn_animation_sources<A : Animation + Clone + Send + Sync + 'static>(
+    mut commands : Commands,
+    sources : Query<SourcedEntity<A>, Added<AnimationSource<A>>>,
+) {
+    for (entity, source, handle) in sources.iter() {
+        let spawned = handle.and_then(|handle| handle.path()).is_some_and(|path| path.to_string() == source.path);
+        if spawned { continue }
+        let source = source.clone();
+        commands.add(move |world : &mut World| {
+            let Some(mut entity_mut) = world.get_entity_mut(entity) else { return };
+            let animator = entity_mut.take::<Animator<A>>();
+            let transform = entity_mut.take::<Transform>();
+            A::spawn(Some(source.animation), world, source.path, entity);
+            let Some(mut entity_mut) = world.get_entity_mut(entity) else { return };
+            if let Some(animator) = animator { entity_mut.insert(animator); }
+            if let Some(transform) = transform { entity_mut.insert(transform); }
+        });
+    }
+}
+
+//=================================================================================
+//    Tests
+//=================================================================================
+
+#[cfg(all(test, feature = "aseprite"))]
+mod tests {
+    use std::time::Duration;
+    use bevy::{ecs::entity::EntityHashMap, scene::{DynamicScene, DynamicSceneBuilder}, sprite::Anchor};
+    use super::*;
+    use crate::{animation::AnimationPlugin, aseprite::{Aseprite, AsepriteAnimation}, testing::AnimationTestApp};
+    
+    #[derive(Reflect, Clone, Copy, Debug, Default, PartialEq)]
+    struct Knight;
+    
+    impl AsepriteAnimation for Knight {
+        fn get_tag_name(&self) -> &str { "run" }
+        fn get_anchor_pixel() -> Vec2 { Vec2::new(24.0, 36.0) }
+        fn get_dimensions() -> UVec2 { UVec2::new(48, 48) }
+    }
+    
+    fn source_world() -> World {
+        let mut world = World::new();
+        world.init_resource::<AppTypeRegistry>();
+        let registry = world.resource::<AppTypeRegistry>();
+        registry.write().register::<Knight>();
+        registry.write().register::<AnimationSource<Knight>>();
+        world
+    }
+    
+    #[test]
+    fn round_trips_through_dynamic_scene() {
+        let mut world = source_world();
+        world.spawn(AnimationSource::new("knight.aseprite", Knight));
+        let scene = DynamicScene::from_world(&world);
+        
+        let mut loaded = source_world();
+        scene.write_to_world(&mut loaded, &mut EntityHashMap::default()).unwrap();
+        let sources : Vec<_> = loaded.query::<&AnimationSource<Knight>>().iter(&loaded).collect();
+        assert_eq!(sources.len(), 1);
+        assert_eq!(sources[0].path, "knight.aseprite");
+        assert_eq!(sources[0].animation, Knight);
+    }
+    
+    #[cfg(feature = "serialize")]
+    #[test]
+    fn round_trips_through_ron() {
+        use bevy::scene::{ron, serde::SceneDeserializer};
+        use serde::de::DeserializeSeed;
+        
+        let mut world = source_world();
+        world.spawn(AnimationSource::new("knight.aseprite", Knight));
+        let registry = world.resource::<AppTypeRegistry>().clone();
+        let text = DynamicScene::from_world(&world).serialize_ron(&registry.0).unwrap();
+        
+        let mut deserializer = ron::de::Deserializer::from_str(&text).unwrap();
+        let scene = SceneDeserializer { type_registry : &registry.read() }.deserialize(&mut deserializer).unwrap();
+        let mut loaded = source_world();
+        scene.write_to_world(&mut loaded, &mut EntityHashMap::default()).unwrap();
+        let source = loaded.query::<&AnimationSource<Knight>>().single(&loaded);
+        assert_eq!(source.path, "knight.aseprite");
+    }
+    
+    #[test]
+    fn sets_up_entities_loaded_from_scene() {
+        // Play a knight for a quarter of a second and save it with its source.
+        let mut saved = AnimationTestApp::new();
+        saved.app.add_plugins(AnimationPlugin::<Knight>::reflect());
+        let knight = saved.load_aseprite(include_bytes!("../assets/knight.aseprite"));
+        let entity = saved.spawn_aseprite(Knight, knight);
+        saved.advance(Duration::from_millis(250));
+        saved.app.world.entity_mut(entity).insert((
+            AnimationSource::new("knight.aseprite", Knight),
+            Transform::from_xyz(1.0, 2.0, 3.0),
+        ));
+        let scene = DynamicSceneBuilder::from_world(&saved.app.world)
+            .deny_all()
+            .allow::<Animator<Knight>>()
+            .allow::<AnimationSource<Knight>>()
+            .allow::<Transform>()
+            .extract_entity(entity)
+            .build();
+        
+        let mut test = AnimationTestApp::new();
+        test.app.add_plugins(AnimationPlugin::<Knight>::reflect());
+        let mut entities = EntityHashMap::default();
+        scene.write_to_world(&mut test.app.world, &mut entities).unwrap();
+        let loaded = entities[&entity];
+        // Already has the asset at its path, so it is skipped.
+        let handle : Handle<Aseprite> = test.app.world.resource::<AssetServer>().load("knight.aseprite");
+        let skipped = test.app.world.spawn((AnimationSource::new("knight.aseprite", Knight), handle)).id();
+        test.advance(Duration::ZERO);
+        
+        let world = &test.app.world;
+        assert_eq!(world.get::<Handle<Aseprite>>(loaded).and_then(|handle| handle.path()).map(|path| path.to_string()), Some("knight.aseprite".into()));
+        assert!(world.get::<TextureAtlas>(loaded).is_some());
+        assert!(matches!(world.get::<Sprite>(loaded).map(|sprite| sprite.anchor), Some(Anchor::Custom(anchor)) if anchor == Vec2::new(0.0, -0.25)));
+        assert_eq!(test.animator::<Knight>(loaded).total_progress(), 0.25);
+        assert_eq!(world.get::<Transform>(loaded), Some(&Transform::from_xyz(1.0, 2.0, 3.0)));
+        assert!(world.get::<Sprite>(skipped).is_none());
+        assert!(world.get::<Animator<Knight>>(skipped).is_none());
+        
+        // Only added sources are set up, so changing one doesn't set the entity up again.
+        test.app.world.entity_mut(loaded).remove::<Sprite>();
+        test.app.world.get_mut::<AnimationSource<Knight>>(loaded).unwrap().animation = Knight;
+        test.advance(Duration::ZERO);
+        assert!(test.app.world.get::<Sprite>(loaded).is_none());
+    }
+}
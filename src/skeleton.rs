@@ -0,0 +1,120 @@
+//=================================================================================
+// Skeletal formats spawn a hierarchy of bone entities under the animated entity.
+// An animation can only write to the entity its animator is on, so it writes a
+// pose there, and the systems in this module copy that pose onto the bones.
+//=================================================================================
+
+use bevy::{prelude::*, transform::TransformSystem, utils::HashMap};
+
+use crate::{animation::AnimationSet, layer::AnimationLayer};
+
+//=================================================================================
+//    SkeletonPlugin
+//=================================================================================
+
+/// Registers the skeleton components and the systems that bind and pose rigs. This is added by each skeletal format's plugin.
+pub(crate) struct SkeletonPlugin;
+
+impl Plugin for SkeletonPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .register_type::<SkeletonBone>()
+            .register_type::<SkeletonAttachment>()
+            .add_systems(PostUpdate, (
+                bind_skeleton_rigs.before(AnimationSet::Animate),
+                apply_skeleton_poses.after(AnimationSet::Layer).before(TransformSystem::TransformPropagate),
+            ))
+        ;
+    }
+}
+
+//=================================================================================
+//    Skeleton Components
+//=================================================================================
+
+/// Marks an entity as a bone of a skeleton. Bones are found by name, so names have to be unique within a skeleton.
+#[derive(Component, Reflect, Default, Clone, Debug)]
+#[reflect(Component)]
+pub struct SkeletonBone {
+    pub name : String,
+}
+
+/// Marks an entity as an attachment of a slot. Only the attachment that the pose selects for a slot is visible.
+#[derive(Component, Reflect, Default, Clone, Debug)]
+#[reflect(Component)]
+pub struct SkeletonAttachment {
+    pub slot : String,
+    pub name : String,
+}
+
+/// The pose computed by the animation this tick. Bones and slots that are not in the pose keep their current state.
+#[derive(Component, Default, Clone, Debug)]
+pub struct SkeletonPose {
+    /// The local transform of each bone, by bone name.
+    pub bones : HashMap<String, Transform>,
+    /// The visible attachment of each slot, by slot name. `None` hides every attachment of the slot.
+    pub attachments : HashMap<String, Option<String>>,
+}
+
+/// The bone and attachment entities of a spawned skeleton. This is added to the animated entity once its skeleton has been spawned.
+#[derive(Component, Default, Clone, Debug)]
+pub struct SkeletonRig {
+    bones : HashMap<String, Entity>,
+    attachments : Vec<(Entity, SkeletonAttachment)>,
+}
+
+impl SkeletonRig {
+    /// Returns the entity of the bone with the given name.
+    pub fn bone(&self, name : &str) -> Option<Entity> {
+        self.bones.get(name).copied()
+    }
+}
+
+//=================================================================================
+//    Skeleton Systems
+//=================================================================================
+
+/// The animated entities whose skeleton has not been bound yet. Layers pose the rig of their target, so they never get one.
+type UnboundRoot = (With<SkeletonPose>, Without<SkeletonRig>, Without<AnimationLayer>);
+
+/// Once the skeleton of an animated entity has been spawned, this system finds its bones and attachments and adds a `SkeletonRig`.
+pub(crate) fn bind_skeleton_rigs(
+    mut commands : Commands,
+    roots : Query<Entity, UnboundRoot>,
+    children : Query<&Children>,
+    bones : Query<&SkeletonBone>,
+    attachments : Query<&SkeletonAttachment>,
+) {
+    for root in roots.iter() {
+        let rig = SkeletonRig {
+            bones : children.iter_descendants(root)
+                .filter_map(|entity| bones.get(entity).ok().map(|bone| (bone.name.clone(), entity)))
+                .collect(),
+            attachments : children.iter_descendants(root)
+                .filter_map(|entity| attachments.get(entity).ok().map(|attachment| (entity, attachment.clone())))
+                .collect(),
+        };
+        if rig.bones.is_empty() { continue }
+        commands.entity(root).insert(rig);
+    }
+}
+
+/// Copies the pose of every rig onto its bones and attachments.
+pub(crate) fn apply_skeleton_poses(
+    rigs : Query<(&SkeletonRig, &SkeletonPose), Changed<SkeletonPose>>,
+    mut transforms : Query<&mut Transform, With<SkeletonBone>>,
+    mut visibilities : Query<&mut Visibility, With<SkeletonAttachment>>,
+) {
+    for (rig, pose) in rigs.iter() {
+        for (name, transform) in pose.bones.iter() {
+            let Some(mut bone) = rig.bone(name).and_then(|entity| transforms.get_mut(entity).ok()) else { continue };
+            if *bone != *transform { *bone = *transform; }
+        }
+        for (entity, attachment) in rig.attachments.iter() {
+            let Some(visible) = pose.attachments.get(&attachment.slot) else { continue };
+            let Ok(mut visibility) = visibilities.get_mut(*entity) else { continue };
+            let target = if visible.as_deref() == Some(attachment.name.as_str()) { Visibility::Inherited } else { Visibility::Hidden };
+            if *visibility != target { *visibility = target; }
+        }
+    }
+}
@@ -0,0 +1,884 @@
+//=================================================================================
+// Here we are defining the Spine Skeleton AssetFile and Animation Backend. A
+// Spine JSON export is loaded into a scene of bone entities with sprites and
+// meshes for its attachments, and the animations in the file pose the bones and
+// switch attachments based on the animator's progress.
+//
+// Weighted mesh vertices are placed where the setup pose puts them and move
+// rigidly with the bone of their slot, so meshes that are bound to several bones
+// do not deform as the bones move. Deform timelines are not read either.
+//=================================================================================
+
+use std::{f32::consts::PI, fmt::Display, path::Path};
+use bevy::{asset::{AssetLoader, AsyncReadExt}, ecs::query::WorldQuery, prelude::*, render::{mesh::{Indices, PrimitiveTopology}, render_asset::RenderAssetUsages}, sprite::{Anchor, MaterialMesh2dBundle, Mesh2dHandle}, utils::HashMap};
+use serde::{Deserialize, Serialize};
+
+use crate::{animation::{Animation, AnimationBackend, Animator}, easing::Easing, skeleton::{SkeletonAttachment, SkeletonBone, SkeletonPlugin, SkeletonPose}};
+
+/// Each slot is drawn this far in front of the slot before it, so that the draw order of the skeleton is kept.
+const SLOT_DEPTH : f32 = 0.001;
+
+//=================================================================================
+//    SpineAnimationPlugin
+//=================================================================================
+
+pub(crate) struct SpineAnimationPlugin;
+
+impl Plugin for SpineAnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_asset_loader::<SpineLoader>()
+            .init_asset::<SpineSkeleton>()
+        ;
+
+        if !app.is_plugin_added::<SkeletonPlugin>() {
+            app.add_plugins(SkeletonPlugin);
+        }
+    }
+}
+
+//=================================================================================
+//    Spine Asset
+//=================================================================================
+
+/// The Spine Skeleton Asset. Stores the scene that was built from the skeleton, the setup pose and the animations.
+#[derive(Asset, TypePath)]
+pub struct SpineSkeleton {
+    scene : Handle<Scene>,
+    bones : HashMap<String, Transform>,
+    attachments : HashMap<String, Option<String>>,
+    animations : HashMap<String, SpineClip>,
+}
+
+/// An animation inside of a Spine skeleton.
+#[derive(Clone, Debug, Default)]
+pub struct SpineClip {
+    duration : f32,
+    bones : HashMap<String, SpineBoneTimeline>,
+    attachments : HashMap<String, Vec<(f32, Option<String>)>>,
+}
+
+#[derive(Clone, Debug, Default)]
+struct SpineBoneTimeline {
+    rotate : Vec<SpineKey>,
+    translate : Vec<SpineKey>,
+    scale : Vec<SpineKey>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct SpineKey {
+    time : f32,
+    value : Vec2,
+    stepped : bool,
+    /// The curve to the next key for the x and y of the value.
+    easing : [Easing; 2],
+}
+
+impl SpineSkeleton {
+    /// The scene that holds the bones and attachments of the skeleton.
+    pub fn scene(&self) -> &Handle<Scene> {
+        &self.scene
+    }
+
+    /// Returns the animation with the given name.
+    pub fn animation(&self, name : &str) -> Option<&SpineClip> {
+        self.animations.get(name)
+    }
+
+    /// Computes the pose of the skeleton at the given time in seconds. Bones and slots that the clip doesn't animate are put back
+    /// into their setup pose.
+    fn pose(&self, clip : &SpineClip, time : f32, pose : &mut SkeletonPose) {
+        pose.bones.clone_from(&self.bones);
+        for (bone, timeline) in clip.bones.iter() {
+            let Some(transform) = pose.bones.get_mut(bone) else { continue };
+            // Rotations and translations are offsets from the setup pose, and scales are multiplied with it.
+            if let Some(rotate) = sample(&timeline.rotate, time) {
+                transform.rotate_z(rotate.x.to_radians());
+            }
+            if let Some(translate) = sample(&timeline.translate, time) {
+                transform.translation += translate.extend(0.0);
+            }
+            if let Some(scale) = sample(&timeline.scale, time) {
+                transform.scale *= scale.extend(1.0);
+            }
+        }
+
+        pose.attachments.clone_from(&self.attachments);
+        for (slot, keys) in clip.attachments.iter() {
+            let current = keys.partition_point(|(key_time, _)| *key_time <= time);
+            if current == 0 { continue }
+            pose.attachments.insert(slot.clone(), keys[current - 1].1.clone());
+        }
+    }
+}
+
+impl SpineClip {
+    /// The length of the animation in seconds. This is the time of the last key in the animation.
+    pub fn duration(&self) -> f32 {
+        self.duration
+    }
+}
+
+/// Samples a timeline at the given time. Returns `None` if the timeline has no keys.
+fn sample(keys : &[SpineKey], time : f32) -> Option<Vec2> {
+    let next = keys.partition_point(|key| key.time <= time);
+    if next == 0 { return keys.first().map(|key| key.value) }
+    if next == keys.len() { return keys.last().map(|key| key.value) }
+
+    let (from, to) = (&keys[next - 1], &keys[next]);
+    if from.stepped { return Some(from.value) }
+    let t = (time - from.time) / (to.time - from.time).max(f32::EPSILON);
+    Some(from.value + (to.value - from.value) * Vec2::new(from.easing[0].ease(t), from.easing[1].ease(t)))
+}
+
+/// Builds the local transform of a bone or attachment from its Spine properties.
+fn local_transform(x : f32, y : f32, rotation : f32, scale_x : f32, scale_y : f32) -> Transform {
+    Transform::from_xyz(x, y, 0.0)
+        .with_rotation(Quat::from_rotation_z(rotation.to_radians()))
+        .with_scale(Vec3::new(scale_x, scale_y, 1.0))
+}
+
+//=================================================================================
+//    Spine File Format
+//=================================================================================
+
+#[derive(Deserialize)]
+struct SpFile {
+    #[serde(default)]
+    skeleton : SpSkeletonInfo,
+    #[serde(default)]
+    bones : Vec<SpBone>,
+    #[serde(default)]
+    slots : Vec<SpSlot>,
+    #[serde(default)]
+    skins : SpSkins,
+    #[serde(default)]
+    animations : HashMap<String, SpAnimation>,
+}
+
+#[derive(Deserialize, Default)]
+struct SpSkeletonInfo {
+    spine : Option<String>,
+}
+
+impl SpSkeletonInfo {
+    /// Spine 4 stores bezier curves in the time and value of the timeline. Files without a version are read as Spine 4.
+    fn absolute_curves(&self) -> bool {
+        let major = self.spine.as_deref().and_then(|version| version.split('.').next()?.parse::<u32>().ok());
+        !matches!(major, Some(major) if major < 4)
+    }
+}
+
+#[derive(Deserialize)]
+struct SpBone {
+    name : String,
+    parent : Option<String>,
+    #[serde(default)]
+    x : f32,
+    #[serde(default)]
+    y : f32,
+    #[serde(default)]
+    rotation : f32,
+    #[serde(default = "one", rename = "scaleX")]
+    scale_x : f32,
+    #[serde(default = "one", rename = "scaleY")]
+    scale_y : f32,
+}
+
+#[derive(Deserialize)]
+struct SpSlot {
+    name : String,
+    bone : String,
+    attachment : Option<String>,
+    color : Option<String>,
+}
+
+/// Spine 3.8 and later store skins as a list, and older versions store them as a map from skin name to attachments.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SpSkins {
+    List(Vec<SpSkin>),
+    Map(HashMap<String, HashMap<String, HashMap<String, SpAttachment>>>),
+}
+
+impl Default for SpSkins {
+    fn default() -> Self {
+        SpSkins::List(Vec::new())
+    }
+}
+
+impl SpSkins {
+    /// Only the default skin is used.
+    fn default_skin(self) -> HashMap<String, HashMap<String, SpAttachment>> {
+        match self {
+            SpSkins::List(skins) => skins.into_iter().find(|skin| skin.name == "default").map(|skin| skin.attachments).unwrap_or_default(),
+            SpSkins::Map(mut skins) => skins.remove("default").unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct SpSkin {
+    name : String,
+    #[serde(default)]
+    attachments : HashMap<String, HashMap<String, SpAttachment>>,
+}
+
+#[derive(Deserialize)]
+struct SpAttachment {
+    #[serde(default = "region", rename = "type")]
+    kind : String,
+    path : Option<String>,
+    #[serde(default)]
+    x : f32,
+    #[serde(default)]
+    y : f32,
+    #[serde(default)]
+    rotation : f32,
+    #[serde(default = "one", rename = "scaleX")]
+    scale_x : f32,
+    #[serde(default = "one", rename = "scaleY")]
+    scale_y : f32,
+    #[serde(default)]
+    width : f32,
+    #[serde(default)]
+    height : f32,
+    color : Option<String>,
+    #[serde(default)]
+    uvs : Vec<f32>,
+    #[serde(default)]
+    triangles : Vec<u32>,
+    #[serde(default)]
+    vertices : Vec<f32>,
+}
+
+#[derive(Deserialize)]
+struct SpAnimation {
+    #[serde(default)]
+    bones : HashMap<String, HashMap<String, Vec<SpKey>>>,
+    #[serde(default)]
+    slots : HashMap<String, HashMap<String, Vec<SpKey>>>,
+}
+
+/// Every kind of key that is read. Spine 4 names the rotation `value`, and older versions name it `angle`.
+#[derive(Deserialize)]
+struct SpKey {
+    #[serde(default)]
+    time : f32,
+    value : Option<f32>,
+    angle : Option<f32>,
+    x : Option<f32>,
+    y : Option<f32>,
+    name : Option<String>,
+    #[serde(default)]
+    curve : serde_json::Value,
+    c2 : Option<f32>,
+    c3 : Option<f32>,
+    c4 : Option<f32>,
+}
+
+fn one() -> f32 { 1.0 }
+
+fn region() -> String { "region".to_string() }
+
+/// Parses a Spine `RRGGBBAA` hex color.
+fn color(hex : Option<&str>) -> Color {
+    let Some(hex) = hex else { return Color::WHITE };
+    let channel = |index : usize| hex.get(index * 2..index * 2 + 2).and_then(|channel| u8::from_str_radix(channel, 16).ok()).unwrap_or(255);
+    Color::rgba_u8(channel(0), channel(1), channel(2), channel(3))
+}
+
+//=================================================================================
+//    Spine Atlas Format
+//=================================================================================
+
+/// A page of a libGDX atlas, which is the atlas format that Spine exports.
+struct AtlasPage {
+    image : Handle<Image>,
+    size : Vec2,
+}
+
+/// A region of a page. `rect` is the area that the region takes up on the page, which has its width and height swapped when the
+/// region is rotated. Regions that were trimmed when they were packed keep the size of the original image, and the offset of
+/// the packed pixels from its bottom left corner.
+struct AtlasRegion {
+    page : usize,
+    rect : Rect,
+    rotated : bool,
+    offset : Vec2,
+    original_size : Vec2,
+}
+
+impl AtlasRegion {
+    /// The size and anchor of a sprite that draws the region for a region attachment of the given size. A trimmed region is
+    /// smaller than the attachment, so the anchor moves it back to where it was in the original image.
+    fn sprite(&self, attachment_size : Vec2) -> (Vec2, Anchor) {
+        let packed = if self.rotated { Vec2::new(self.rect.height(), self.rect.width()) } else { self.rect.size() };
+        let scale = attachment_size / self.original_size.max(Vec2::ONE);
+        let mut size = packed * scale;
+        let mut center = (self.offset + packed / 2.0 - self.original_size / 2.0) * scale;
+        // Rotated regions are drawn as they are on the page and turned back by the transform, so the center is turned the other way.
+        if self.rotated {
+            size = Vec2::new(size.y, size.x);
+            center = Vec2::new(-center.y, center.x);
+        }
+        (size, Anchor::Custom(-center / size.max(Vec2::splat(f32::EPSILON))))
+    }
+}
+
+/// Parses the libGDX atlas format. Both the Spine 3 (`xy`, `size`, `orig` and `offset`) and the Spine 4 (`bounds` and `offsets`)
+/// region properties are supported.
+fn parse_atlas(source : &str, mut load_page : impl FnMut(&str) -> Handle<Image>) -> Result<(Vec<AtlasPage>, HashMap<String, AtlasRegion>), SpineLoaderError> {
+    let mut pages : Vec<AtlasPage> = Vec::new();
+    let mut regions = HashMap::default();
+    let mut region : Option<(String, AtlasRegion, Vec2, Vec2)> = None;
+    let mut expect_page = true;
+
+    fn finish_region(region : &mut Option<(String, AtlasRegion, Vec2, Vec2)>, regions : &mut HashMap<String, AtlasRegion>) {
+        let Some((name, mut atlas_region, position, size)) = region.take() else { return };
+        if atlas_region.original_size == Vec2::ZERO { atlas_region.original_size = size; }
+        let size = if atlas_region.rotated { Vec2::new(size.y, size.x) } else { size };
+        atlas_region.rect = Rect::from_corners(position, position + size);
+        regions.insert(name, atlas_region);
+    }
+
+    for line in source.lines() {
+        let line = line.trim();
+        if line.is_empty() {
+            finish_region(&mut region, &mut regions);
+            expect_page = true;
+            continue;
+        }
+
+        let Some((key, value)) = line.split_once(':') else {
+            finish_region(&mut region, &mut regions);
+            if expect_page {
+                pages.push(AtlasPage { image : load_page(line), size : Vec2::ZERO });
+                expect_page = false;
+            } else {
+                let Some(page) = pages.len().checked_sub(1) else { return Err(SpineLoaderError::Atlas(format!("Region '{}' has no page", line))) };
+                region = Some((line.to_string(), AtlasRegion { page, rect : Rect::default(), rotated : false, offset : Vec2::ZERO, original_size : Vec2::ZERO }, Vec2::ZERO, Vec2::ZERO));
+            }
+            continue;
+        };
+
+        let numbers = value.split(',').filter_map(|number| number.trim().parse::<f32>().ok()).collect::<Vec<_>>();
+        let pair = |numbers : &[f32]| -> Result<Vec2, SpineLoaderError> {
+            match numbers {
+                [x, y, ..] => Ok(Vec2::new(*x, *y)),
+                _ => Err(SpineLoaderError::Atlas(format!("Expected two numbers in '{}'", line))),
+            }
+        };
+        match (&mut region, key.trim()) {
+            (None, "size") => if let Some(page) = pages.last_mut() { page.size = pair(&numbers)? },
+            (Some((_, _, position, _)), "xy") => *position = pair(&numbers)?,
+            (Some((_, _, _, size)), "size") => *size = pair(&numbers)?,
+            (Some((_, _, position, size)), "bounds") => {
+                *position = pair(&numbers)?;
+                *size = pair(numbers.get(2..).unwrap_or_default())?;
+            },
+            (Some((_, atlas_region, _, _)), "rotate") => atlas_region.rotated = matches!(value.trim(), "true" | "90"),
+            (Some((_, atlas_region, _, _)), "orig") => atlas_region.original_size = pair(&numbers)?,
+            (Some((_, atlas_region, _, _)), "offset") => atlas_region.offset = pair(&numbers)?,
+            (Some((_, atlas_region, _, _)), "offsets") => {
+                atlas_region.offset = pair(&numbers)?;
+                atlas_region.original_size = pair(numbers.get(2..).unwrap_or_default())?;
+            },
+            _ => {},
+        }
+    }
+    finish_region(&mut region, &mut regions);
+
+    if let Some(page) = pages.iter().position(|page| page.size == Vec2::ZERO) {
+        return Err(SpineLoaderError::Atlas(format!("Page {} has no size", page)));
+    }
+    Ok((pages, regions))
+}
+
+//=================================================================================
+//    Spine Asset Loader
+//=================================================================================
+
+/// Asset Loader for Spine `.spine.json` files. The atlas is read from the file next to the skeleton with the same name and the
+/// `.atlas` extension, unless `SpineLoaderSettings::atlas` is set. The skeleton is added as the labeled asset `#scene`.
+#[derive(Default)]
+pub struct SpineLoader;
+
+/// The settings for loading a Spine skeleton.
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+pub struct SpineLoaderSettings {
+    /// The path of the atlas, relative to the skeleton.
+    pub atlas : Option<String>,
+}
+
+/// The errors that can occur while loading a Spine skeleton.
+#[derive(Debug)]
+pub enum SpineLoaderError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    ReadAtlas(bevy::asset::ReadAssetBytesError),
+    Atlas(String),
+    MissingRegion(String),
+}
+
+impl Display for SpineLoaderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SpineLoaderError::Io(error) => write!(f, "Could not read Spine skeleton: {}", error),
+            SpineLoaderError::Json(error) => write!(f, "Could not parse Spine skeleton: {}", error),
+            SpineLoaderError::ReadAtlas(error) => write!(f, "Could not read Spine atlas: {}", error),
+            SpineLoaderError::Atlas(error) => write!(f, "Could not parse Spine atlas: {}", error),
+            SpineLoaderError::MissingRegion(region) => write!(f, "Spine atlas has no region named '{}'", region),
+        }
+    }
+}
+
+impl std::error::Error for SpineLoaderError {}
+
+impl From<std::io::Error> for SpineLoaderError {
+    fn from(error: std::io::Error) -> Self {
+        SpineLoaderError::Io(error)
+    }
+}
+
+impl AssetLoader for SpineLoader {
+    type Asset = SpineSkeleton;
+
+    type Settings = SpineLoaderSettings;
+
+    type Error = SpineLoaderError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut bevy::asset::io::Reader,
+        settings: &'a Self::Settings,
+        load_context: &'a mut bevy::asset::LoadContext,
+    ) -> bevy::utils::BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            let file : SpFile = serde_json::from_slice(&bytes).map_err(SpineLoaderError::Json)?;
+
+            let directory = load_context.path().parent().map(Path::to_path_buf).unwrap_or_default();
+            let atlas_path = match &settings.atlas {
+                Some(atlas) => directory.join(atlas),
+                None => {
+                    let file_name = load_context.path().file_name().and_then(|name| name.to_str()).unwrap_or_default();
+                    directory.join(format!("{}.atlas", file_name.trim_end_matches(".json").trim_end_matches(".spine")))
+                },
+            };
+            let atlas_bytes = load_context.read_asset_bytes(atlas_path.clone()).await.map_err(SpineLoaderError::ReadAtlas)?;
+            let atlas_directory = atlas_path.parent().map(Path::to_path_buf).unwrap_or_default();
+            let (pages, regions) = parse_atlas(&String::from_utf8_lossy(&atlas_bytes), |page| load_context.load(atlas_directory.join(page)))?;
+
+            // Bones are listed with parents before their children, so every parent has been spawned by the time a child is.
+            let mut world = World::default();
+            let root = world.spawn(SpatialBundle::INHERITED_IDENTITY).id();
+            let mut bone_entities = HashMap::default();
+            let mut bones = HashMap::default();
+            let mut bone_matrices = Vec::with_capacity(file.bones.len());
+            let mut bone_indices = HashMap::default();
+            for (index, bone) in file.bones.iter().enumerate() {
+                let transform = local_transform(bone.x, bone.y, bone.rotation, bone.scale_x, bone.scale_y);
+                let parent = bone.parent.as_ref().and_then(|parent| bone_indices.get(parent).copied());
+                let parent_matrix = parent.map_or(Mat4::IDENTITY, |parent : usize| bone_matrices[parent]);
+                bone_matrices.push(parent_matrix * transform.compute_matrix());
+                bone_indices.insert(bone.name.clone(), index);
+
+                let entity = world.spawn((
+                    Name::new(bone.name.clone()),
+                    SkeletonBone { name : bone.name.clone() },
+                    SpatialBundle::from_transform(transform),
+                )).id();
+                let parent_entity = bone.parent.as_ref().and_then(|parent| bone_entities.get(parent).copied()).unwrap_or(root);
+                world.entity_mut(parent_entity).add_child(entity);
+                bone_entities.insert(bone.name.clone(), entity);
+                bones.insert(bone.name.clone(), transform);
+            }
+
+            let mut skin = file.skins.default_skin();
+            for (depth, slot) in file.slots.iter().enumerate() {
+                let (Some(&bone_entity), Some(&bone_index)) = (bone_entities.get(&slot.bone), bone_indices.get(&slot.bone)) else { continue };
+                let Some(attachments) = skin.remove(&slot.name) else { continue };
+                let slot_color = color(slot.color.as_deref());
+                for (name, attachment) in attachments {
+                    if !matches!(attachment.kind.as_str(), "region" | "mesh") { continue }
+                    let region_name = attachment.path.clone().unwrap_or_else(|| name.clone());
+                    let Some(region) = regions.get(&region_name) else { return Err(SpineLoaderError::MissingRegion(region_name)) };
+                    let page = &pages[region.page];
+                    let tint = multiply(slot_color, color(attachment.color.as_deref()));
+                    let visibility = if slot.attachment.as_ref() == Some(&name) { Visibility::Inherited } else { Visibility::Hidden };
+                    let marker = (Name::new(name.clone()), SkeletonAttachment { slot : slot.name.clone(), name : name.clone() });
+
+                    let entity = if attachment.kind == "region" {
+                        let mut transform = local_transform(attachment.x, attachment.y, attachment.rotation, attachment.scale_x, attachment.scale_y);
+                        transform.translation.z = depth as f32 * SLOT_DEPTH;
+                        // Rotated regions are stored a quarter turn counter clockwise on the page, so they are turned back here.
+                        if region.rotated { transform.rotate_local_z(-PI / 2.0); }
+                        let (size, anchor) = region.sprite(Vec2::new(attachment.width, attachment.height));
+                        world.spawn((marker, SpriteBundle {
+                            sprite : Sprite { color : tint, rect : Some(region.rect), custom_size : Some(size), anchor, ..Default::default() },
+                            texture : page.image.clone(),
+                            transform,
+                            visibility,
+                            ..Default::default()
+                        })).id()
+                    } else {
+                        let mesh = spine_mesh(&attachment, region, page, &bone_matrices, bone_matrices[bone_index]);
+                        let mesh = load_context.add_labeled_asset(format!("mesh-{}-{}", slot.name, name), mesh);
+                        let material = load_context.add_labeled_asset(format!("material-{}-{}", slot.name, name), ColorMaterial {
+                            color : tint,
+                            texture : Some(page.image.clone()),
+                        });
+                        world.spawn((marker, MaterialMesh2dBundle {
+                            mesh : Mesh2dHandle(mesh),
+                            material,
+                            transform : Transform::from_xyz(0.0, 0.0, depth as f32 * SLOT_DEPTH),
+                            visibility,
+                            ..Default::default()
+                        })).id()
+                    };
+                    world.entity_mut(bone_entity).add_child(entity);
+                }
+            }
+            let scene = load_context.add_labeled_asset("scene".to_string(), Scene::new(world));
+
+            let attachments = file.slots.iter().map(|slot| (slot.name.clone(), slot.attachment.clone())).collect();
+            let absolute_curves = file.skeleton.absolute_curves();
+            let animations = file.animations.into_iter().map(|(name, animation)| (name, clip(animation, absolute_curves))).collect();
+
+            Ok(SpineSkeleton { scene, bones, attachments, animations })
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["spine.json"]
+    }
+}
+
+fn multiply(a : Color, b : Color) -> Color {
+    Color::rgba(a.r() * b.r(), a.g() * b.g(), a.b() * b.b(), a.a() * b.a())
+}
+
+/// Builds the mesh of a mesh attachment, relative to the bone of its slot. Weighted meshes are placed where the setup pose puts
+/// them, and are not deformed by the animation.
+fn spine_mesh(attachment : &SpAttachment, region : &AtlasRegion, page : &AtlasPage, bone_matrices : &[Mat4], slot_bone : Mat4) -> Mesh {
+    let vertex_count = attachment.uvs.len() / 2;
+    let positions = if attachment.vertices.len() == attachment.uvs.len() {
+        attachment.vertices.chunks_exact(2).map(|vertex| [vertex[0], vertex[1], 0.0]).collect::<Vec<_>>()
+    } else {
+        // Weighted vertices are a bone count followed by a bone index, position and weight for each bone.
+        let to_slot = slot_bone.inverse();
+        let mut positions = Vec::with_capacity(vertex_count);
+        let mut values = attachment.vertices.iter().copied();
+        while positions.len() < vertex_count {
+            let Some(bone_count) = values.next() else { break };
+            let mut position = Vec3::ZERO;
+            for _ in 0..bone_count as usize {
+                let [bone, x, y, weight] = [(); 4].map(|_| values.next().unwrap_or_default());
+                let matrix = bone_matrices.get(bone as usize).copied().unwrap_or(Mat4::IDENTITY);
+                position += matrix.transform_point3(Vec3::new(x, y, 0.0)) * weight;
+            }
+            positions.push(to_slot.transform_point3(position).to_array());
+        }
+        positions
+    };
+
+    let min = region.rect.min / page.size;
+    let size = region.rect.size() / page.size;
+    let uvs = attachment.uvs.chunks_exact(2)
+        .map(|uv| if region.rotated {
+            [min.x + uv[1] * size.x, min.y + size.y - uv[0] * size.y]
+        } else {
+            [min.x + uv[0] * size.x, min.y + uv[1] * size.y]
+        })
+        .collect::<Vec<_>>();
+
+    Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::all())
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[0.0, 0.0, 1.0]; vertex_count])
+        .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+        .with_inserted_indices(Indices::U32(attachment.triangles.clone()))
+}
+
+/// Reads the bone and slot timelines of an animation. Timelines that are not supported are skipped. `absolute_curves` is true for
+/// files that store bezier curves in the time and value of the timeline.
+fn clip(animation : SpAnimation, absolute_curves : bool) -> SpineClip {
+    let mut duration = 0.0f32;
+    let mut clip = SpineClip::default();
+
+    for (bone, timelines) in animation.bones {
+        let mut timeline = SpineBoneTimeline::default();
+        for (kind, keys) in timelines {
+            let (channel, default) = match kind.as_str() {
+                "rotate" => (&mut timeline.rotate, 0.0),
+                "translate" => (&mut timeline.translate, 0.0),
+                "scale" => (&mut timeline.scale, 1.0),
+                _ => continue,
+            };
+            let mut keys = keys;
+            keys.sort_by(|a, b| a.time.total_cmp(&b.time));
+            let values = keys.iter()
+                .map(|key| match kind.as_str() {
+                    "rotate" => Vec2::new(key.value.or(key.angle).unwrap_or_default(), 0.0),
+                    _ => Vec2::new(key.x.unwrap_or(default), key.y.unwrap_or(default)),
+                })
+                .collect::<Vec<_>>();
+            for (index, key) in keys.iter().enumerate() {
+                duration = duration.max(key.time);
+                let next = keys.get(index + 1).map(|next| (next.time, values[index + 1]));
+                channel.push(SpineKey {
+                    time : key.time,
+                    value : values[index],
+                    stepped : key.curve.as_str() == Some("stepped"),
+                    easing : curve(key, (key.time, values[index]), next, absolute_curves),
+                });
+            }
+        }
+        clip.bones.insert(bone, timeline);
+    }
+
+    for (slot, mut timelines) in animation.slots {
+        let Some(keys) = timelines.remove("attachment") else { continue };
+        let mut keys = keys.into_iter().map(|key| (key.time, key.name)).collect::<Vec<_>>();
+        keys.sort_by(|a, b| a.0.total_cmp(&b.0));
+        duration = keys.last().map_or(duration, |(time, _)| duration.max(*time));
+        clip.attachments.insert(slot, keys);
+    }
+
+    clip.duration = duration;
+    clip
+}
+
+/// Reads the bezier curve from a key to the next one, for the x and y of the value. Spine 4 stores four numbers for each value,
+/// the time and value of both control points. Older versions store one curve for the whole key, scaled to 0..1 between the keys,
+/// as a list or as `curve`, `c2`, `c3` and `c4`.
+fn curve(key : &SpKey, from : (f32, Vec2), to : Option<(f32, Vec2)>, absolute_curves : bool) -> [Easing; 2] {
+    let (numbers, absolute) = match &key.curve {
+        serde_json::Value::Number(curve) => {
+            let c1 = curve.as_f64().unwrap_or_default() as f32;
+            (vec![c1, key.c2.unwrap_or(0.0), key.c3.unwrap_or(1.0), key.c4.unwrap_or(1.0)], false)
+        },
+        serde_json::Value::Array(curve) => (curve.iter().filter_map(|number| number.as_f64()).map(|number| number as f32).collect(), absolute_curves),
+        _ => return [Easing::Linear; 2],
+    };
+    let Some((to_time, to_value)) = to else { return [Easing::Linear; 2] };
+    if numbers.len() < 4 { return [Easing::Linear; 2] }
+
+    [0, 1].map(|index| {
+        let points = numbers.get(index * 4..index * 4 + 4).unwrap_or(&numbers[..4]);
+        if !absolute { return Easing::CubicBezier(points[0], points[1], points[2], points[3]) }
+        let time = |time : f32| (time - from.0) / (to_time - from.0).max(f32::EPSILON);
+        let change = to_value[index] - from.1[index];
+        let value = |value : f32| if change.abs() > f32::EPSILON { (value - from.1[index]) / change } else { 0.0 };
+        Easing::CubicBezier(time(points[0]), value(points[1]), time(points[2]), value(points[3]))
+    })
+}
+
+//=================================================================================
+//    Spine Animation
+//=================================================================================
+
+/// This trait will allow you to animate a Spine skeleton. Implement `Animation` for the type with
+/// `impl_animation!(MyAnimation, SpineBackend)`.
+pub trait SpineAnimation : Sized {
+
+    /// Animations are defined by name inside of the skeleton. This function will tell bevy what animation to use based on the current
+    /// state of the struct that implements this trait.
+    fn get_animation_name(&self) -> &str;
+}
+
+/// The animation backend for Spine skeletons. See `SpineAnimation`.
+pub struct SpineBackend;
+
+impl <A : SpineAnimation + Animation + FromWorld + Send + Sync + 'static> AnimationBackend<A> for SpineBackend {
+    type AsociatedAsset = SpineSkeleton;
+
+    type Query<'w, 's> = &'w mut SkeletonPose;
+
+    fn apply(
+        animator : &Animator<A>,
+        items : &mut <Self::Query<'_, '_> as WorldQuery>::Item<'_>,
+        asset : &Self::AsociatedAsset,
+    ) {
+        let Some(clip) = asset.animations.get(animator.animation.get_animation_name()) else { return };
+        asset.pose(clip, animator.progress() * clip.duration, items);
+    }
+
+    fn spawn(animation : Option<A>, world : &mut World, path : String, entity : Entity) {
+        let animation_comp = animation.unwrap_or(A::from_world(world));
+        let asset_server = world.resource::<AssetServer>();
+        let skeleton : Handle<SpineSkeleton> = asset_server.load(&path);
+        let scene : Handle<Scene> = asset_server.load(format!("{}#scene", path));
+
+        world.get_or_spawn(entity).unwrap()
+            .insert(Animator::new(animation_comp))
+            .insert(skeleton)
+            .insert(SkeletonPose::default())
+            .insert(SceneBundle { scene, ..Default::default() })
+        ;
+    }
+
+    fn spawn_layer(animation : A, world : &mut World, path : String, entity : Entity) {
+        let skeleton : Handle<SpineSkeleton> = world.resource::<AssetServer>().load(&path);
+
+        world.get_or_spawn(entity).unwrap()
+            .insert(Animator::new(animation))
+            .insert(skeleton)
+            .insert(SkeletonPose::default())
+        ;
+    }
+
+    fn duration(animation : &A, asset : &Self::AsociatedAsset) -> f32 {
+        asset.animations.get(animation.get_animation_name()).map_or(0.0, |clip| clip.duration)
+    }
+    
+    fn debug_name(animation : &A) -> Option<&str> {
+        Some(animation.get_animation_name())
+    }
+}
+
+//=================================================================================
+//    Tests
+//=================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    const SPINE_3_ATLAS : &str = "
+hero.png
+size: 64, 32
+format: RGBA8888
+filter: Linear, Linear
+repeat: none
+head
+  rotate: false
+  xy: 2, 2
+  size: 20, 10
+  orig: 24, 16
+  offset: 1, 2
+  index: -1
+arm
+  rotate: true
+  xy: 24, 2
+  size: 8, 4
+  orig: 10, 4
+  offset: 2, 0
+  index: -1
+";
+    
+    const SPINE_4_ATLAS : &str = "
+hero.png
+size:64,32
+filter:Linear,Linear
+head
+bounds:2,2,20,10
+offsets:1,2,24,16
+arm
+bounds:24,2,8,4
+offsets:2,0,10,4
+rotate:90
+";
+    
+    const SKELETON : &str = r#"{
+        "skeleton" : { "spine" : "4.1.00" },
+        "bones" : [
+            { "name" : "root" },
+            { "name" : "head", "parent" : "root", "y" : 20, "rotation" : 90 }
+        ],
+        "slots" : [ { "name" : "head", "bone" : "head", "attachment" : "head", "color" : "ff000080" } ],
+        "skins" : [ { "name" : "default", "attachments" : { "head" : {
+            "head" : { "width" : 48, "height" : 32 },
+            "blink" : { "type" : "mesh", "path" : "head", "uvs" : [0, 0, 1, 0, 1, 1], "triangles" : [0, 1, 2], "vertices" : [0, 0, 10, 0, 10, 10] }
+        } } } ],
+        "animations" : { "nod" : {
+            "bones" : { "head" : { "rotate" : [ { "value" : 10 }, { "time" : 0.5, "value" : -10, "curve" : "stepped" }, { "time" : 1.0, "value" : 0 } ] } },
+            "slots" : { "head" : { "attachment" : [ { "time" : 0.25, "name" : "blink" }, { "time" : 0.75, "name" : "head" } ] } }
+        } }
+    }"#;
+    
+    #[test]
+    fn parses_atlas() {
+        for source in [SPINE_3_ATLAS, SPINE_4_ATLAS] {
+            let (pages, regions) = parse_atlas(source, |_| Handle::default()).unwrap();
+            assert_eq!(pages.len(), 1);
+            assert_eq!(pages[0].size, Vec2::new(64.0, 32.0));
+            
+            let head = &regions["head"];
+            assert_eq!(head.rect, Rect::new(2.0, 2.0, 22.0, 12.0));
+            assert_eq!((head.offset, head.original_size), (Vec2::new(1.0, 2.0), Vec2::new(24.0, 16.0)));
+            
+            let arm = &regions["arm"];
+            assert!(arm.rotated);
+            assert_eq!(arm.rect, Rect::new(24.0, 2.0, 28.0, 10.0));
+        }
+        assert!(matches!(parse_atlas("hero.png\nhead\nxy: 0, 0", |_| Handle::default()), Err(SpineLoaderError::Atlas(_))));
+    }
+    
+    #[test]
+    fn places_trimmed_regions() {
+        let (_, regions) = parse_atlas(SPINE_3_ATLAS, |_| Handle::default()).unwrap();
+        // The head is drawn at twice the size of its image, and its packed pixels sit one pixel left and below the center.
+        assert_eq!(regions["head"].sprite(Vec2::new(48.0, 32.0)), (Vec2::new(40.0, 20.0), Anchor::Custom(Vec2::new(0.05, 0.1))));
+        // The arm is drawn as it is on the page, and the anchor is turned with it.
+        assert_eq!(regions["arm"].sprite(Vec2::new(10.0, 4.0)), (Vec2::new(4.0, 8.0), Anchor::Custom(Vec2::new(0.0, -0.125))));
+        
+        let untrimmed = AtlasRegion { page : 0, rect : Rect::new(0.0, 0.0, 8.0, 8.0), rotated : false, offset : Vec2::ZERO, original_size : Vec2::splat(8.0) };
+        assert_eq!(untrimmed.sprite(Vec2::splat(16.0)), (Vec2::splat(16.0), Anchor::Custom(Vec2::ZERO)));
+    }
+    
+    #[test]
+    fn parses_skeleton() {
+        let file : SpFile = serde_json::from_str(SKELETON).unwrap();
+        assert_eq!(file.bones.len(), 2);
+        assert_eq!(file.bones[1].parent.as_deref(), Some("root"));
+        assert_eq!((file.bones[1].y, file.bones[1].rotation, file.bones[1].scale_x), (20.0, 90.0, 1.0));
+        assert_eq!(color(file.slots[0].color.as_deref()), Color::rgba_u8(255, 0, 0, 128));
+        
+        let mut skin = file.skins.default_skin();
+        let attachments = skin.remove("head").unwrap();
+        assert_eq!(attachments["head"].kind, "region");
+        assert_eq!(attachments["blink"].kind, "mesh");
+        
+        let (pages, regions) = parse_atlas(SPINE_4_ATLAS, |_| Handle::default()).unwrap();
+        let mesh = spine_mesh(&attachments["blink"], &regions["head"], &pages[0], &[], Mat4::IDENTITY);
+        assert_eq!(mesh.count_vertices(), 3);
+        
+        assert!(file.skeleton.absolute_curves());
+        let nod = clip(file.animations.into_iter().next().unwrap().1, true);
+        assert_eq!(nod.duration(), 1.0);
+        let rotate = &nod.bones["head"].rotate;
+        assert_eq!(sample(rotate, 0.25), Some(Vec2::new(0.0, 0.0)));
+        assert_eq!(sample(rotate, 0.75), Some(Vec2::new(-10.0, 0.0)));
+        assert_eq!(nod.attachments["head"], vec![(0.25, Some("blink".to_string())), (0.75, Some("head".to_string()))]);
+    }
+    
+    #[test]
+    fn samples_bezier_curves() {
+        // Spine 4 stores the control points in the time and value of the timeline, with four numbers for each of x and y.
+        let keys : Vec<SpKey> = serde_json::from_str(r#"[
+            { "x" : 0, "y" : 10, "curve" : [0.25, 0, 0.75, 10, 0.5, 10, 0.5, 10] },
+            { "time" : 1.0, "x" : 10, "y" : 10 }
+        ]"#).unwrap();
+        let mut animation = SpAnimation { bones : HashMap::default(), slots : HashMap::default() };
+        animation.bones.insert("head".to_string(), [("translate".to_string(), keys)].into_iter().collect());
+        let translate = &clip(animation, true).bones["head"].translate;
+        assert_eq!(translate[0].easing[0], Easing::CubicBezier(0.25, 0.0, 0.75, 1.0));
+        let expected = Easing::CubicBezier(0.25, 0.0, 0.75, 1.0).ease(0.25) * 10.0;
+        assert!((sample(translate, 0.25).unwrap().x - expected).abs() < 1e-4);
+        assert!(sample(translate, 0.25).unwrap().x < 2.5);
+        assert_eq!(sample(translate, 0.5).unwrap(), Vec2::new(5.0, 10.0));
+        
+        // Older versions scale one curve for the whole key between the keys.
+        let keys : Vec<SpKey> = serde_json::from_str(r#"[
+            { "angle" : 0, "curve" : 0.25, "c2" : 0, "c3" : 0.75 },
+            { "time" : 2.0, "angle" : 90 }
+        ]"#).unwrap();
+        let mut animation = SpAnimation { bones : HashMap::default(), slots : HashMap::default() };
+        animation.bones.insert("head".to_string(), [("rotate".to_string(), keys)].into_iter().collect());
+        let rotate = &clip(animation, false).bones["head"].rotate;
+        assert_eq!(rotate[0].easing[0], Easing::CubicBezier(0.25, 0.0, 0.75, 1.0));
+        assert!((sample(rotate, 0.5).unwrap().x - expected / 10.0 * 90.0).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,341 @@
+//=================================================================================
+// Here we are defining the Sprite Sheet AssetFile and Animation Backend. A sprite
+// sheet is a plain image laid out in a grid, along with a RON or JSON file that
+// describes the grid and the named clips inside of it.
+//=================================================================================
+
+use std::fmt::Display;
+use bevy::{asset::{AssetLoader, AsyncReadExt}, ecs::query::WorldQuery, prelude::*, sprite::Anchor, utils::HashMap};
+use serde::Deserialize;
+
+use crate::{animation::{Animation, AnimationBackend, Animator}, clip::FrameClip};
+
+//=================================================================================
+//    SpriteSheetAnimationPlugin
+//=================================================================================
+
+pub(crate) struct SpriteSheetAnimationPlugin;
+
+impl Plugin for SpriteSheetAnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_asset_loader::<SpriteSheetLoader>()
+            .init_asset::<SpriteSheet>()
+        ;
+    }
+}
+
+//=================================================================================
+//    Sprite Sheet Asset
+//=================================================================================
+
+/// The Sprite Sheet Asset. Stores the sheet image, the grid layout and the named clips.
+#[derive(Asset, TypePath)]
+pub struct SpriteSheet {
+    image : Handle<Image>,
+    layout : Handle<TextureAtlasLayout>,
+    clips : HashMap<String, FrameClip>,
+    tile_size : UVec2,
+    anchor : Vec2,
+}
+
+impl SpriteSheet {
+    /// The image that the sprite sheet was cut from.
+    pub fn image(&self) -> &Handle<Image> {
+        &self.image
+    }
+
+    /// The grid layout of the sprite sheet.
+    pub fn layout(&self) -> &Handle<TextureAtlasLayout> {
+        &self.layout
+    }
+
+    /// The size of a single cell of the grid.
+    pub fn tile_size(&self) -> UVec2 {
+        self.tile_size
+    }
+
+    /// Returns true if the sprite sheet has a clip with the given name.
+    pub fn has_clip(&self, name : &str) -> bool {
+        self.clips.contains_key(name)
+    }
+}
+
+//=================================================================================
+//    Sprite Sheet Metadata
+//=================================================================================
+
+/// The sidecar file that describes a sprite sheet. The image path is relative to the sidecar file.
+///
+/// ```ron
+/// (
+///     image : "fire.png",
+///     tile_size : (16, 16),
+///     columns : 8,
+///     rows : 2,
+///     clips : {
+///         "burn" : (frames : [0, 1, 2, 3], durations : [100, 100, 150, 100]),
+///         "smoke" : (frames : [8, 9, 10], durations : [80]),
+///     },
+/// )
+/// ```
+#[derive(Deserialize)]
+struct SpriteSheetMeta {
+    image : String,
+    tile_size : (u32, u32),
+    columns : usize,
+    rows : usize,
+    #[serde(default)]
+    padding : (u32, u32),
+    #[serde(default)]
+    offset : (u32, u32),
+    /// The pixel inside of a cell that the sprite is anchored to. Defaults to the center of the cell.
+    #[serde(default)]
+    anchor : Option<(f32, f32)>,
+    clips : HashMap<String, ClipMeta>,
+}
+
+/// A clip in the sidecar file. Durations are in milliseconds. If there are fewer durations than frames, the last duration is
+/// used for the rest of the frames.
+#[derive(Deserialize)]
+struct ClipMeta {
+    frames : Vec<usize>,
+    #[serde(default = "default_durations")]
+    durations : Vec<u32>,
+}
+
+fn default_durations() -> Vec<u32> {
+    vec![100]
+}
+
+impl SpriteSheetMeta {
+    /// Parses a sidecar file and checks that every clip only uses frames inside of the grid.
+    fn parse(bytes : &[u8], is_json : bool) -> Result<Self, SpriteSheetLoaderError> {
+        let meta : SpriteSheetMeta = if is_json {
+            serde_json::from_slice(bytes).map_err(SpriteSheetLoaderError::Json)?
+        } else {
+            ron::de::from_bytes(bytes).map_err(SpriteSheetLoaderError::Ron)?
+        };
+
+        let frame_count = meta.columns * meta.rows;
+        for (name, clip) in meta.clips.iter() {
+            if let Some(frame) = clip.frames.iter().find(|frame| **frame >= frame_count) {
+                return Err(SpriteSheetLoaderError::InvalidFrame { clip : name.clone(), frame : *frame });
+            }
+        }
+        Ok(meta)
+    }
+}
+
+//=================================================================================
+//    Sprite Sheet Asset Loader
+//=================================================================================
+
+/// Asset Loader for Sprite Sheet sidecar files. Loads files ending in `.sheet.ron` or `.sheet.json`.
+#[derive(Default)]
+pub struct SpriteSheetLoader;
+
+/// The errors that can occur while loading a sprite sheet.
+#[derive(Debug)]
+pub enum SpriteSheetLoaderError {
+    Io(std::io::Error),
+    Ron(ron::error::SpannedError),
+    Json(serde_json::Error),
+    InvalidFrame { clip : String, frame : usize },
+}
+
+impl Display for SpriteSheetLoaderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SpriteSheetLoaderError::Io(error) => write!(f, "Could not read sprite sheet: {}", error),
+            SpriteSheetLoaderError::Ron(error) => write!(f, "Could not parse sprite sheet RON: {}", error),
+            SpriteSheetLoaderError::Json(error) => write!(f, "Could not parse sprite sheet JSON: {}", error),
+            SpriteSheetLoaderError::InvalidFrame { clip, frame } => write!(f, "Clip '{}' uses frame {} which is outside of the grid", clip, frame),
+        }
+    }
+}
+
+impl std::error::Error for SpriteSheetLoaderError {}
+
+impl From<std::io::Error> for SpriteSheetLoaderError {
+    fn from(error: std::io::Error) -> Self {
+        SpriteSheetLoaderError::Io(error)
+    }
+}
+
+impl AssetLoader for SpriteSheetLoader {
+    type Asset = SpriteSheet;
+
+    type Settings = ();
+
+    type Error = SpriteSheetLoaderError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut bevy::asset::io::Reader,
+        _: &'a Self::Settings,
+        load_context: &'a mut bevy::asset::LoadContext,
+    ) -> bevy::utils::BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            let is_json = load_context.path().extension().is_some_and(|extension| extension == "json");
+            let meta = SpriteSheetMeta::parse(&bytes, is_json)?;
+
+            let tile_size = UVec2::new(meta.tile_size.0, meta.tile_size.1);
+            let layout = TextureAtlasLayout::from_grid(
+                tile_size.as_vec2(),
+                meta.columns,
+                meta.rows,
+                Some(Vec2::new(meta.padding.0 as f32, meta.padding.1 as f32)),
+                Some(Vec2::new(meta.offset.0 as f32, meta.offset.1 as f32)),
+            );
+            let layout = load_context.add_labeled_asset("layout".to_string(), layout);
+
+            let image_path = load_context.path().parent().map(|parent| parent.join(&meta.image)).unwrap_or(meta.image.into());
+            let image = load_context.load(image_path);
+
+            let anchor = meta.anchor.map_or(tile_size.as_vec2() / 2.0, |(x, y)| Vec2::new(x, y));
+            let clips = meta.clips.into_iter().map(|(name, clip)| (name, FrameClip::new(clip.frames, &clip.durations))).collect();
+
+            Ok(SpriteSheet { image, layout, clips, tile_size, anchor })
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["sheet.ron", "sheet.json"]
+    }
+}
+
+//=================================================================================
+//    Sprite Sheet Animation
+//=================================================================================
+
+/// This trait will allow you to animate a sprite with a sprite sheet. Implement `Animation` for the type with
+/// `impl_animation!(MyAnimation, SpriteSheetBackend)`.
+pub trait SpriteSheetAnimation : Sized {
+
+    /// Animations are defined by the clips inside of the sprite sheet's sidecar file. This function will tell bevy what clip to use
+    /// based on the current state of the struct that implements this trait.
+    fn get_clip_name(&self) -> &str;
+}
+
+/// The animation backend for sprite sheets. See `SpriteSheetAnimation`.
+pub struct SpriteSheetBackend;
+
+impl <A : SpriteSheetAnimation + Animation + FromWorld + Send + Sync + 'static> AnimationBackend<A> for SpriteSheetBackend {
+    type AsociatedAsset = SpriteSheet;
+
+    type Query<'w, 's> = (&'w mut TextureAtlas, &'w mut Handle<Image>, &'w mut Sprite);
+
+    fn apply(
+        animator : &Animator<A>,
+        items : &mut <Self::Query<'_, '_> as WorldQuery>::Item<'_>,
+        asset : &Self::AsociatedAsset,
+    ) {
+        let (atlas, texture, sprite) = items;
+        if atlas.layout != asset.layout { atlas.layout = asset.layout.clone(); }
+        if **texture != asset.image { **texture = asset.image.clone(); }
+
+        let size = asset.tile_size.as_vec2();
+        let anchor = Anchor::Custom(Vec2::new(asset.anchor.x / size.x - 0.5, 0.5 - asset.anchor.y / size.y));
+        if sprite.anchor != anchor { sprite.anchor = anchor; }
+
+        if let Some(clip) = asset.clips.get(animator.animation.get_clip_name()) {
+            atlas.index = clip.frame(animator.progress());
+        }
+    }
+
+    fn spawn(animation : Option<A>, world : &mut World, path : String, entity : Entity) {
+        let animation_comp = animation.unwrap_or(A::from_world(world));
+        let asset_server = world.resource::<AssetServer>();
+        let sheet : Handle<SpriteSheet> = asset_server.load(&path);
+        let layout : Handle<TextureAtlasLayout> = asset_server.load(format!("{}#layout", path));
+
+        world.get_or_spawn(entity).unwrap()
+            .insert(Animator::new(animation_comp))
+            .insert(sheet)
+            .insert(SpriteSheetBundle {
+                atlas : TextureAtlas { layout, index : 0 },
+                ..Default::default()
+            })
+        ;
+    }
+
+    fn duration(animation : &A, asset : &Self::AsociatedAsset) -> f32 {
+        asset.clips.get(animation.get_clip_name()).map_or(0.0, |clip| clip.duration())
+    }
+    
+    fn debug_name(animation : &A) -> Option<&str> {
+        Some(animation.get_clip_name())
+    }
+    
+    fn debug_frame(animation : &A, progress : f32, asset : &Self::AsociatedAsset) -> Option<usize> {
+        asset.clips.get(animation.get_clip_name()).map(|clip| clip.frame(progress))
+    }
+}
+
+//=================================================================================
+//    Tests
+//=================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn parses_ron_sidecar() {
+        let meta = SpriteSheetMeta::parse(br#"(
+            image : "fire.png",
+            tile_size : (16, 16),
+            columns : 8,
+            rows : 2,
+            clips : {
+                "burn" : (frames : [0, 1, 2, 3], durations : [100, 100, 150, 100]),
+                "smoke" : (frames : [8, 9, 10], durations : [80]),
+            },
+        )"#, false).unwrap();
+        assert_eq!(meta.image, "fire.png");
+        assert_eq!(meta.tile_size, (16, 16));
+        assert_eq!((meta.columns, meta.rows), (8, 2));
+        assert_eq!((meta.padding, meta.offset, meta.anchor), ((0, 0), (0, 0), None));
+        
+        let burn = &meta.clips["burn"];
+        let clip = FrameClip::new(burn.frames.clone(), &burn.durations);
+        assert_eq!(clip.duration(), 0.45);
+        assert_eq!(clip.frame(0.5), 2);
+        
+        let smoke = &meta.clips["smoke"];
+        assert_eq!(FrameClip::new(smoke.frames.clone(), &smoke.durations).duration(), 0.24);
+    }
+    
+    #[test]
+    fn parses_json_sidecar() {
+        let meta = SpriteSheetMeta::parse(br#"{
+            "image" : "coin.png",
+            "tile_size" : [8, 8],
+            "columns" : 4,
+            "rows" : 1,
+            "padding" : [1, 1],
+            "anchor" : [4.0, 7.0],
+            "clips" : { "spin" : { "frames" : [0, 1, 2, 3] } }
+        }"#, true).unwrap();
+        assert_eq!(meta.padding, (1, 1));
+        assert_eq!(meta.anchor, Some((4.0, 7.0)));
+        assert_eq!(meta.clips["spin"].durations, vec![100]);
+    }
+    
+    #[test]
+    fn rejects_frames_outside_of_grid() {
+        let result = SpriteSheetMeta::parse(br#"(
+            image : "fire.png",
+            tile_size : (16, 16),
+            columns : 2,
+            rows : 2,
+            clips : { "burn" : (frames : [0, 4]) },
+        )"#, false);
+        assert!(matches!(result, Err(SpriteSheetLoaderError::InvalidFrame { frame : 4, .. })));
+        assert!(matches!(SpriteSheetMeta::parse(b"{}", true), Err(SpriteSheetLoaderError::Json(_))));
+        assert!(matches!(SpriteSheetMeta::parse(b"(", false), Err(SpriteSheetLoaderError::Ron(_))));
+    }
+}
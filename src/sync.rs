@@ -0,0 +1,162 @@
+//=================================================================================
+// Sync groups let animators on different entities share a single clock, so that
+// they stay in phase no matter when they were spawned. Each member reads its
+// progress from the clock of its group, plus an optional offset.
+//=================================================================================
+
+use std::time::Duration;
+use bevy::{prelude::*, utils::HashMap};
+
+//=================================================================================
+//    Animation Sync Components
+//=================================================================================
+
+/// Adds the animator on this entity to a sync group. Instead of counting up its own progress, the animator takes its progress
+/// from the clock of the group, so every member of the group plays in phase. The clock is counted in seconds, so members with
+/// the same duration and speed land on the same frame.
+#[derive(Component, Reflect, Clone, Copy, Debug, Default, PartialEq)]
+#[reflect(Component)]
+pub struct AnimationSyncGroup {
+    pub group : u32,
+    /// Shifts the member ahead of the group by this many repititions. Only the fractional part is used, so 0.5 plays the
+    /// member half a repitition ahead.
+    pub offset : f32,
+}
+
+impl AnimationSyncGroup {
+    /// Adds the entity to the given group, with no offset.
+    pub fn new(group : u32) -> Self {
+        AnimationSyncGroup { group, offset : 0.0 }
+    }
+
+    /// Sets the offset of the member. See `AnimationSyncGroup::offset`.
+    pub fn with_offset(mut self, offset : f32) -> Self {
+        self.offset = offset;
+        self
+    }
+}
+
+//=================================================================================
+//    Animation Sync Resources
+//=================================================================================
+
+/// Holds the clock of every sync group. A group's clock is created the first time one of its members is animated, and is
+/// advanced once per frame no matter how many members or animation types it has.
+#[derive(Resource, Default, Debug)]
+pub struct AnimationSyncGroups {
+    clocks : HashMap<u32, SyncClock>,
+}
+
+#[derive(Debug)]
+struct SyncClock {
+    elapsed : f32,
+    previous : f32,
+    speed : f32,
+    updated : Duration,
+}
+
+impl AnimationSyncGroups {
+    /// The number of seconds the clock of the group has counted.
+    pub fn elapsed(&self, group : u32) -> f32 {
+        self.clocks.get(&group).map_or(0.0, |clock| clock.elapsed)
+    }
+
+    /// Sets the speed of a group's clock. 1.0 is normal speed, and 0.0 pauses every member of the group.
+    pub fn set_speed(&mut self, group : u32, speed : f32) {
+        self.clocks.entry(group).or_insert_with(SyncClock::new).speed = speed;
+    }
+
+    /// Restarts the clock of a group, so every member starts its animation over.
+    pub fn reset(&mut self, group : u32) {
+        if let Some(clock) = self.clocks.get_mut(&group) {
+            clock.elapsed = 0.0;
+            clock.previous = 0.0;
+        }
+    }
+
+    /// Advances the clock of the group by `delta` if it hasn't been advanced at the time `now` yet, and returns the seconds it
+    /// had counted before this advance and the seconds it has counted now.
+    pub(crate) fn advance(&mut self, group : u32, delta : f32, now : Duration) -> (f32, f32) {
+        let clock = self.clocks.entry(group).or_insert_with(SyncClock::new);
+        if clock.updated != now {
+            clock.updated = now;
+            clock.previous = clock.elapsed;
+            clock.elapsed += delta * clock.speed;
+        }
+        (clock.previous, clock.elapsed)
+    }
+}
+
+/// Where the current animation of a sync group member started on the clock of its group. Members count their repititions from
+/// here, so resetting a member restarts its animation without leaving the group.
+#[derive(Reflect, Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) enum SyncStart {
+    /// The member hasn't been advanced by its group yet, so it joins the group in phase.
+    #[default]
+    Join,
+    /// The member was reset, so its animation starts from the clock of the group as it read before the frame it next advances.
+    Restart,
+    /// The current animation started when the clock of the group read this many seconds.
+    At(f32),
+}
+
+impl SyncStart {
+    /// Works out when the current animation started, given the clock of the group before and after this frame and the
+    /// repititions the member counts per second. A member that joins starts at the last whole repitition of the clock. If the
+    /// clock was reset behind the start, the member joins again.
+    pub(crate) fn resolve(self, before : f32, elapsed : f32, rate : f32) -> f32 {
+        match self {
+            SyncStart::At(start) if start <= elapsed => start,
+            SyncStart::Restart => before.min(elapsed),
+            _ if rate > 0.0 => elapsed - (elapsed * rate).fract() / rate,
+            _ => elapsed,
+        }
+    }
+}
+
+impl SyncClock {
+    fn new() -> Self {
+        SyncClock { elapsed : 0.0, previous : 0.0, speed : 1.0, updated : Duration::ZERO }
+    }
+}
+
+//=================================================================================
+//    Tests
+//=================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn advances_once_per_frame() {
+        let mut groups = AnimationSyncGroups::default();
+        let frame = Duration::from_millis(250);
+        assert_eq!(groups.advance(0, 0.25, frame), (0.0, 0.25));
+        assert_eq!(groups.advance(0, 0.25, frame), (0.0, 0.25));
+        assert_eq!(groups.advance(1, 0.5, frame), (0.0, 0.5));
+        
+        groups.set_speed(0, 2.0);
+        assert_eq!(groups.advance(0, 0.25, frame * 2), (0.25, 0.75));
+        assert_eq!(groups.elapsed(0), 0.75);
+        assert_eq!(groups.elapsed(1), 0.5);
+        
+        groups.reset(0);
+        assert_eq!(groups.elapsed(0), 0.0);
+        assert_eq!(groups.advance(0, 0.25, frame * 3), (0.0, 0.5));
+    }
+    
+    #[test]
+    fn resolves_member_starts() {
+        // Joining members start at the last whole repitition, so they share the phase of the clock.
+        assert_eq!(SyncStart::Join.resolve(1.0, 1.25, 1.0), 1.0);
+        assert_eq!(SyncStart::Join.resolve(1.0, 1.25, 2.0), 1.0);
+        assert_eq!(SyncStart::Join.resolve(1.0, 1.25, 0.0), 1.25);
+        // Restarted members start from the clock before the frame, so they keep the delta of the frame.
+        assert_eq!(SyncStart::Restart.resolve(1.0, 1.25, 1.0), 1.0);
+        assert_eq!(SyncStart::At(0.5).resolve(1.0, 1.25, 1.0), 0.5);
+        // A clock that was reset behind the start is joined again.
+        assert_eq!(SyncStart::At(0.5).resolve(0.0, 0.25, 1.0), 0.0);
+    }
+}
@@ -0,0 +1,337 @@
+//=================================================================================
+// A headless app for testing animations without a window or a GPU. Time is only
+// advanced when the test asks for it, and by exactly the amount it asks for, so
+// the frame an animator lands on can be asserted on.
+//=================================================================================
+
+use std::time::Duration;
+use bevy::{prelude::*, time::TimeUpdateStrategy};
+
+use crate::{animation::{Animation, AnimationPlugin, Animator}, AnimatorPlugin};
+
+//=================================================================================
+//    Animation Test App
+//=================================================================================
+
+/// A minimal app with the `AnimatorPlugin` that runs without a window or renderer. Images and atlas layouts are stored as usual,
+/// but never uploaded to a GPU.
+///
+/// Time only moves with `advance`, and each call runs exactly one update. The virtual time doesn't cap its delta, so long steps
+/// aren't cut short. Events are kept for two updates, so read them with
+/// `events` after the update that sent them.
+pub struct AnimationTestApp {
+    pub app : App,
+}
+
+impl Default for AnimationTestApp {
+    fn default() -> Self {
+        AnimationTestApp::new()
+    }
+}
+
+impl AnimationTestApp {
+    /// Creates the app and runs its first update, so that the next call to `advance` moves time by the exact amount.
+    pub fn new() -> Self {
+        let mut app = App::new();
+        app
+            .add_plugins((MinimalPlugins, AssetPlugin::default(), TransformPlugin, HierarchyPlugin))
+            .init_asset::<Image>()
+            .init_asset::<TextureAtlasLayout>()
+            .add_plugins(AnimatorPlugin)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::ZERO))
+        ;
+        app.world.resource_mut::<Time<Virtual>>().set_max_delta(Duration::MAX);
+        app.update();
+        AnimationTestApp { app }
+    }
+    
+    /// Adds the `AnimationPlugin` for an animation type.
+    pub fn add_animation<A : Animation + Send + Sync + 'static>(&mut self) -> &mut Self {
+        self.app.add_plugins(AnimationPlugin::<A>::default());
+        self
+    }
+    
+    /// Runs one update that moves time forward by the given duration.
+    pub fn advance(&mut self, duration : Duration) -> &mut Self {
+        self.app.insert_resource(TimeUpdateStrategy::ManualDuration(duration));
+        self.app.update();
+        self
+    }
+    
+    /// Runs one update that moves time forward by the given number of seconds.
+    pub fn advance_secs(&mut self, seconds : f32) -> &mut Self {
+        self.advance(Duration::from_secs_f32(seconds))
+    }
+    
+    /// Runs an update that moves time forward by the given duration, `count` times.
+    pub fn advance_by(&mut self, duration : Duration, count : usize) -> &mut Self {
+        for _ in 0..count { self.advance(duration); }
+        self
+    }
+    
+    /// The animator of the given type on an entity. Panics if the entity doesn't have one.
+    pub fn animator<A : Animation + Send + Sync + 'static>(&self, entity : Entity) -> &Animator<A> {
+        self.app.world.get::<Animator<A>>(entity).expect("The entity doesn't have an animator of this type.")
+    }
+    
+    /// The animator of the given type on an entity, to change it between updates. Panics if the entity doesn't have one.
+    pub fn animator_mut<A : Animation + Send + Sync + 'static>(&mut self, entity : Entity) -> Mut<'_, Animator<A>> {
+        self.app.world.get_mut::<Animator<A>>(entity).expect("The entity doesn't have an animator of this type.")
+    }
+    
+    /// The atlas index that an entity is showing. Panics if the entity doesn't have a `TextureAtlas`.
+    pub fn atlas_index(&self, entity : Entity) -> usize {
+        self.app.world.get::<TextureAtlas>(entity).expect("The entity doesn't have a texture atlas.").index
+    }
+    
+    /// Takes every event of the given type that is still buffered.
+    pub fn events<E : Event>(&mut self) -> Vec<E> {
+        self.app.world.resource_mut::<Events<E>>().drain().collect()
+    }
+}
+
+//=================================================================================
+//    Aseprite Testing
+//=================================================================================
+
+#[cfg(feature = "aseprite")]
+impl AnimationTestApp {
+    /// Reads an aseprite file from its bytes and adds it to the app, for example with `include_bytes!`. Unlike the asset server
+    /// this happens right away, so the asset can be used in the next update. Panics if the file can't be read.
+    pub fn load_aseprite(&mut self, bytes : &[u8]) -> Handle<crate::aseprite::Aseprite> {
+        let data = crate::aseprite::AsepriteData::read(bytes).expect("Failed to read the aseprite file.");
+        let world = &mut self.app.world;
+        let aseprite = data.build(|_, _, image, layout| (
+            world.resource_mut::<Assets<Image>>().add(image),
+            world.resource_mut::<Assets<TextureAtlasLayout>>().add(layout),
+        )).expect("Failed to pack the aseprite file.");
+        world.resource_mut::<Assets<crate::aseprite::Aseprite>>().add(aseprite)
+    }
+    
+    /// Spawns an entity that plays the given aseprite animation, with the same components `Animation::spawn` gives it, but using
+    /// a handle to an aseprite that was already added instead of a path. Files in an atlas group are added to their group, and
+    /// get their atlas once the group has packed them.
+    pub fn spawn_aseprite<A : crate::aseprite::AsepriteAnimation + Send + Sync + 'static>(
+        &mut self, 
+        animation : A, 
+        aseprite : Handle<crate::aseprite::Aseprite>,
+    ) -> Entity {
+        let asset = self.app.world.resource::<Assets<crate::aseprite::Aseprite>>().get(&aseprite).expect("The aseprite hasn't been added.");
+        let (texture, layout) = match A::get_atlas_group() {
+            Some(_) => (Handle::default(), Handle::default()),
+            None => (asset.atlas_image().clone(), asset.atlas_layout().clone()),
+        };
+        if let Some(group) = A::get_atlas_group() {
+            self.app.world.resource_mut::<crate::aseprite::AsepriteAtlasGroups>().add(group, aseprite.clone());
+        }
+        let sprite = SpriteSheetBundle {
+            texture,
+            atlas : TextureAtlas { layout, index : 0 },
+            sprite : Sprite { anchor : crate::aseprite::anchor::<A>(A::get_dimensions()), ..Default::default() },
+            ..Default::default()
+        };
+        self.app.world.spawn((Animator::new(animation), aseprite, sprite)).id()
+    }
+    
+    /// The tag of the aseprite animation that an entity is playing. Panics if the entity doesn't have an animator of this type.
+    pub fn current_tag<A : crate::aseprite::AsepriteAnimation + Send + Sync + 'static>(&self, entity : Entity) -> &str {
+        self.animator::<A>(entity).animation.get_tag_name()
+    }
+}
+
+//=================================================================================
+//    Tests
+//=================================================================================
+
+#[cfg(all(test, feature = "aseprite"))]
+mod tests {
+    use bevy::sprite::Anchor;
+    use super::*;
+    use crate::{
+        animation::AnimationQueueEvent, 
+        aseprite::{Aseprite, AsepriteAnimation, AsepriteAtlasGroups, AsepriteTile, AsepriteTileGrid}, 
+        time::{AnimationHitStop, AnimationTime, AnimationTimeSource},
+    };
+    
+    #[derive(Clone, Copy, Debug, Default, PartialEq)]
+    struct Knight;
+    
+    impl AsepriteAnimation for Knight {
+        fn get_tag_name(&self) -> &str { "run" }
+        fn get_dimensions() -> UVec2 { UVec2::new(48, 48) }
+    }
+    
+    #[derive(Clone, Copy, Debug, Default, PartialEq)]
+    struct GroupedKnight;
+    
+    impl AsepriteAnimation for GroupedKnight {
+        fn get_tag_name(&self) -> &str { "run" }
+        fn get_anchor_pixel() -> Vec2 { Vec2::new(24.0, 48.0) }
+        fn get_dimensions() -> UVec2 { UVec2::new(48, 48) }
+        fn get_atlas_group() -> Option<&'static str> { Some("units") }
+    }
+    
+    #[derive(Clone, Copy, Debug, Default, PartialEq)]
+    enum Character {
+        #[default]
+        Walk,
+        Idle,
+    }
+    
+    impl AsepriteAnimation for Character {
+        fn get_tag_name(&self) -> &str {
+            match self {
+                Character::Walk => "walk-right",
+                Character::Idle => "idle-right",
+            }
+        }
+        fn get_dimensions() -> UVec2 { UVec2::new(16, 16) }
+    }
+    
+    #[test]
+    fn advances_by_exact_time() {
+        let mut test = AnimationTestApp::new();
+        test.add_animation::<Knight>();
+        let knight = test.load_aseprite(include_bytes!("../assets/knight.aseprite"));
+        let entity = test.spawn_aseprite(Knight, knight);
+        
+        test.advance(Duration::from_millis(250));
+        assert_eq!(test.atlas_index(entity), 2);
+        test.advance_by(Duration::from_millis(100), 5);
+        assert_eq!(test.atlas_index(entity), 7);
+        test.advance(Duration::from_millis(300));
+        assert_eq!(test.atlas_index(entity), 0);
+        assert_eq!(test.animator::<Knight>(entity).repititions(), 1);
+    }
+    
+    #[test]
+    fn spawns_like_animation_spawn() {
+        let mut test = AnimationTestApp::new();
+        test.add_animation::<GroupedKnight>();
+        let knight = test.load_aseprite(include_bytes!("../assets/knight.aseprite"));
+        let entity = test.spawn_aseprite(GroupedKnight, knight.clone());
+        assert_eq!(test.app.world.resource::<AsepriteAtlasGroups>().group_of(&knight), Some("units"));
+        assert_eq!(test.app.world.get::<Sprite>(entity).unwrap().anchor, Anchor::Custom(Vec2::new(0.0, -0.5)));
+        
+        test.advance_by(Duration::from_millis(250), 2);
+        let asset = test.app.world.resource::<Assets<Aseprite>>().get(&knight).unwrap();
+        let layout = asset.atlas_layout().clone();
+        assert_eq!(test.app.world.resource::<AsepriteAtlasGroups>().pages("units").next().map(|(_, page)| page), Some(&layout));
+        assert_eq!(test.app.world.get::<TextureAtlas>(entity).unwrap().layout, layout);
+        assert_eq!(test.atlas_index(entity), asset.atlas_index(5));
+    }
+    
+    #[test]
+    fn real_time_ignores_paused_virtual_time() {
+        let mut test = AnimationTestApp::new();
+        test.add_animation::<Knight>();
+        let knight = test.load_aseprite(include_bytes!("../assets/knight.aseprite"));
+        let virtual_knight = test.spawn_aseprite(Knight, knight.clone());
+        let real_knight = test.spawn_aseprite(Knight, knight);
+        test.app.world.entity_mut(real_knight).insert(AnimationTimeSource::Real);
+        
+        test.app.world.resource_mut::<Time<Virtual>>().pause();
+        test.advance(Duration::from_millis(250));
+        assert_eq!(test.atlas_index(virtual_knight), 0);
+        assert_eq!(test.atlas_index(real_knight), 2);
+        
+        test.app.world.resource_mut::<Time<Virtual>>().unpause();
+        test.advance(Duration::from_millis(250));
+        assert_eq!(test.atlas_index(virtual_knight), 2);
+        assert_eq!(test.atlas_index(real_knight), 5);
+    }
+    
+    #[test]
+    fn scales_animation_time() {
+        let mut test = AnimationTestApp::new();
+        test.add_animation::<Knight>();
+        let knight = test.load_aseprite(include_bytes!("../assets/knight.aseprite"));
+        let entity = test.spawn_aseprite(Knight, knight);
+        
+        test.app.world.resource_mut::<AnimationTime>().set_scale(0.5);
+        test.advance(Duration::from_millis(500));
+        assert_eq!(test.atlas_index(entity), 2);
+        
+        test.app.world.resource_mut::<AnimationTime>().pause();
+        test.advance(Duration::from_millis(500));
+        assert_eq!(test.atlas_index(entity), 2);
+    }
+    
+    #[test]
+    fn hit_stop_freezes_then_resumes() {
+        let mut test = AnimationTestApp::new();
+        test.add_animation::<Knight>();
+        let knight = test.load_aseprite(include_bytes!("../assets/knight.aseprite"));
+        let entity = test.spawn_aseprite(Knight, knight);
+        test.advance(Duration::from_millis(150));
+        assert_eq!(test.atlas_index(entity), 1);
+        
+        test.app.world.entity_mut(entity).insert(AnimationHitStop::new(0.15));
+        test.advance_by(Duration::from_millis(100), 2);
+        assert_eq!(test.atlas_index(entity), 1);
+        assert!(!test.app.world.get::<AnimationHitStop>(entity).unwrap().is_active());
+        
+        test.advance(Duration::from_millis(100));
+        assert_eq!(test.atlas_index(entity), 2);
+    }
+    
+    #[test]
+    fn fixed_source_advances_in_fixed_update() {
+        let mut test = AnimationTestApp::new();
+        test.add_animation::<Knight>();
+        let knight = test.load_aseprite(include_bytes!("../assets/knight.aseprite"));
+        let entity = test.spawn_aseprite(Knight, knight);
+        test.app.world.entity_mut(entity).insert(AnimationTimeSource::Fixed);
+        
+        // Less than one fixed timestep passes, so the animator doesn't move.
+        test.advance(Duration::from_millis(10));
+        assert_eq!(test.animator::<Knight>(entity).total_progress(), 0.0);
+        
+        // The remaining time adds up to 16 fixed timesteps of 1/64th of a second.
+        test.advance(Duration::from_millis(240));
+        assert_eq!(test.animator::<Knight>(entity).total_progress(), 0.25);
+        assert_eq!(test.atlas_index(entity), 2);
+    }
+    
+    #[test]
+    fn plays_queued_tags() {
+        let mut test = AnimationTestApp::new();
+        test.add_animation::<Character>();
+        let character = test.load_aseprite(include_bytes!("../assets/character.aseprite"));
+        let entity = test.spawn_aseprite(Character::Walk, character);
+        test.animator_mut::<Character>(entity).queue(Character::Idle);
+        
+        test.advance(Duration::from_millis(750));
+        assert_eq!(test.current_tag::<Character>(entity), "walk-right");
+        assert_eq!(test.atlas_index(entity), 7);
+        assert!(test.events::<AnimationQueueEvent>().is_empty());
+        
+        test.advance(Duration::from_millis(100));
+        assert_eq!(test.current_tag::<Character>(entity), "idle-right");
+        assert_eq!(test.atlas_index(entity), 64);
+        assert_eq!(test.events::<AnimationQueueEvent>(), vec![AnimationQueueEvent::Advanced { entity, remaining : 0 }]);
+    }
+    
+    #[test]
+    fn plays_tile_grids() {
+        let mut test = AnimationTestApp::new();
+        let tiles = test.load_aseprite(include_bytes!("../assets/tiles.aseprite"));
+        test.app.world.spawn((AsepriteTileGrid::new("ground"), tiles, SpatialBundle::default()));
+        // The tiles are spawned on the first update and shown on the second.
+        test.advance_by(Duration::ZERO, 2);
+        
+        let shown = |test : &mut AnimationTestApp| {
+            let mut cells = test.app.world.query::<(&AsepriteTile, &TextureAtlas, &Visibility)>()
+                .iter(&test.app.world)
+                .map(|(tile, atlas, visibility)| (tile.cell.x, (*visibility != Visibility::Hidden).then_some(atlas.index)))
+                .collect::<Vec<_>>();
+            cells.sort();
+            cells.into_iter().map(|(_, index)| index).collect::<Vec<_>>()
+        };
+        assert_eq!(shown(&mut test), vec![Some(1), Some(2)]);
+        test.advance(Duration::from_millis(150));
+        assert_eq!(shown(&mut test), vec![Some(2), None]);
+        test.advance(Duration::from_millis(200));
+        assert_eq!(shown(&mut test), vec![Some(1), Some(2)]);
+    }
+}
@@ -0,0 +1,436 @@
+//=================================================================================
+// Here we are defining the TexturePacker AssetFile and Animation Backend. This
+// loads the JSON (hash or array) that TexturePacker writes next to its packed
+// sheet. Clips come from an explicit clip table, or from the frame names.
+//=================================================================================
+
+use std::{collections::HashMap as StdHashMap, fmt::Display};
+use bevy::{asset::{AssetLoader, AsyncReadExt}, ecs::query::WorldQuery, prelude::*, sprite::Anchor, utils::HashMap};
+use serde::{Deserialize, Serialize};
+
+use crate::{animation::{Animation, AnimationBackend, Animator}, clip::FrameClip, util::{frame_image, sheets::sheet_frames}};
+
+//=================================================================================
+//    TexturePackerAnimationPlugin
+//=================================================================================
+
+pub(crate) struct TexturePackerAnimationPlugin;
+
+impl Plugin for TexturePackerAnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_asset_loader::<TexturePackerLoader>()
+            .init_asset::<TexturePackerAtlas>()
+        ;
+    }
+}
+
+//=================================================================================
+//    TexturePacker Asset
+//=================================================================================
+
+/// The TexturePacker Asset. The layout is built over the packed sheet, so trimmed frames are drawn at their trimmed size and kept
+/// in place by the pivot. Rotated frames are rotated back and copied below the sheet when loaded.
+#[derive(Asset, TypePath)]
+pub struct TexturePackerAtlas {
+    image : Handle<Image>,
+    layout : Handle<TextureAtlasLayout>,
+    frame_names : HashMap<String, usize>,
+    pivots : Vec<Vec2>,
+    clips : HashMap<String, FrameClip>,
+}
+
+impl TexturePackerAtlas {
+    /// The packed image.
+    pub fn image(&self) -> &Handle<Image> {
+        &self.image
+    }
+
+    /// The layout of the packed image.
+    pub fn layout(&self) -> &Handle<TextureAtlasLayout> {
+        &self.layout
+    }
+
+    /// Returns the atlas index of the frame with the given name. This is useful for drawing single frames, for example in UI.
+    pub fn frame_index(&self, name : &str) -> Option<usize> {
+        self.frame_names.get(name).copied()
+    }
+
+    /// Returns the clip with the given name.
+    pub fn clip(&self, name : &str) -> Option<&FrameClip> {
+        self.clips.get(name)
+    }
+
+    /// Returns the normalized pivot of a frame, where (0.0, 0.0) is the top left and (1.0, 1.0) is the bottom right of the trimmed
+    /// frame. The pivot of a trimmed frame can lie outside of it.
+    pub fn pivot(&self, frame : usize) -> Vec2 {
+        self.pivots.get(frame).copied().unwrap_or(Vec2::splat(0.5))
+    }
+}
+
+//=================================================================================
+//    TexturePacker JSON Format
+//=================================================================================
+
+#[derive(Deserialize)]
+struct TexturePackerJson {
+    frames : TexturePackerFrames,
+    #[serde(default)]
+    animations : StdHashMap<String, Vec<String>>,
+    meta : TexturePackerMeta,
+}
+
+/// TexturePacker can write frames either as an object keyed by name (hash) or as a list (array).
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TexturePackerFrames {
+    Hash(serde_json::Map<String, serde_json::Value>),
+    Array(Vec<NamedTexturePackerFrame>),
+}
+
+#[derive(Deserialize)]
+struct NamedTexturePackerFrame {
+    filename : String,
+    #[serde(flatten)]
+    frame : TexturePackerFrame,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TexturePackerFrame {
+    frame : JsonRect,
+    #[serde(default)]
+    rotated : bool,
+    sprite_source_size : Option<JsonRect>,
+    source_size : Option<JsonSize>,
+    pivot : Option<JsonPivot>,
+}
+
+#[derive(Deserialize)]
+struct TexturePackerMeta {
+    image : String,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+struct JsonRect { x : u32, y : u32, w : u32, h : u32 }
+
+#[derive(Deserialize, Clone, Copy)]
+struct JsonSize { w : u32, h : u32 }
+
+#[derive(Deserialize, Clone, Copy)]
+struct JsonPivot { x : f32, y : f32 }
+
+impl TexturePackerFrames {
+    fn into_frames(self) -> Result<Vec<NamedTexturePackerFrame>, serde_json::Error> {
+        match self {
+            TexturePackerFrames::Array(frames) => Ok(frames),
+            TexturePackerFrames::Hash(frames) => frames.into_iter()
+                .map(|(filename, frame)| Ok(NamedTexturePackerFrame { filename, frame : serde_json::from_value(frame)? }))
+                .collect(),
+        }
+    }
+}
+
+impl TexturePackerFrame {
+    /// The pivot relative to the trimmed frame. TexturePacker stores the pivot relative to the untrimmed frame.
+    fn trimmed_pivot(&self) -> Vec2 {
+        let trimmed = Vec2::new(self.frame.w as f32, self.frame.h as f32);
+        let size = self.source_size.map_or(trimmed, |size| Vec2::new(size.w as f32, size.h as f32));
+        let offset = self.sprite_source_size.map_or(Vec2::ZERO, |source| Vec2::new(source.x as f32, source.y as f32));
+        let pivot = self.pivot.map_or(Vec2::splat(0.5), |pivot| Vec2::new(pivot.x, pivot.y));
+        (pivot * size - offset) / trimmed
+    }
+}
+
+/// Splits a frame name like `walk_03.png` into the clip name `walk` and the frame number `3`.
+fn split_frame_name(name : &str) -> (&str, Option<u32>) {
+    let stem = name.rsplit_once('.').map_or(name, |(stem, _)| stem);
+    let clip = stem.trim_end_matches(|c : char| c.is_ascii_digit());
+    let number = stem[clip.len()..].parse().ok();
+    let clip = clip.trim_end_matches(['_', '-', ' ', '/', '.']);
+    (if clip.is_empty() { stem } else { clip }, number)
+}
+
+//=================================================================================
+//    TexturePacker Asset Loader
+//=================================================================================
+
+/// Settings for the TexturePacker loader.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TexturePackerLoaderSettings {
+    /// How long each frame of a clip is shown, in milliseconds. TexturePacker doesn't store frame durations.
+    pub frame_duration : u32,
+    /// An explicit clip table, mapping clip names to frame names. These take priority over the `animations` table in the
+    /// JSON file and over the clips derived from frame names.
+    pub clips : StdHashMap<String, Vec<String>>,
+}
+
+impl Default for TexturePackerLoaderSettings {
+    fn default() -> Self {
+        TexturePackerLoaderSettings {
+            frame_duration : 100,
+            clips : StdHashMap::new(),
+        }
+    }
+}
+
+/// Asset Loader for TexturePacker JSON files. The image is loaded from the `meta.image` path, relative to the JSON file. To keep it
+/// apart from other JSON files, the file has to be named `*.tp.json` or `*.texturepacker.json`.
+#[derive(Default)]
+pub struct TexturePackerLoader;
+
+/// The errors that can occur while loading a TexturePacker atlas.
+#[derive(Debug)]
+pub enum TexturePackerLoaderError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    ReadImage(bevy::asset::ReadAssetBytesError),
+    Image(image::ImageError),
+    UnknownFrame { clip : String, frame : String },
+}
+
+impl Display for TexturePackerLoaderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TexturePackerLoaderError::Io(error) => write!(f, "Could not read TexturePacker JSON: {}", error),
+            TexturePackerLoaderError::Json(error) => write!(f, "Could not parse TexturePacker JSON: {}", error),
+            TexturePackerLoaderError::ReadImage(error) => write!(f, "Could not read TexturePacker sheet: {}", error),
+            TexturePackerLoaderError::Image(error) => write!(f, "Could not decode TexturePacker sheet: {}", error),
+            TexturePackerLoaderError::UnknownFrame { clip, frame } => write!(f, "Clip '{}' uses frame '{}' which is not in the atlas", clip, frame),
+        }
+    }
+}
+
+impl std::error::Error for TexturePackerLoaderError {}
+
+impl From<std::io::Error> for TexturePackerLoaderError {
+    fn from(error: std::io::Error) -> Self {
+        TexturePackerLoaderError::Io(error)
+    }
+}
+
+impl AssetLoader for TexturePackerLoader {
+    type Asset = TexturePackerAtlas;
+
+    type Settings = TexturePackerLoaderSettings;
+
+    type Error = TexturePackerLoaderError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut bevy::asset::io::Reader,
+        settings: &'a Self::Settings,
+        load_context: &'a mut bevy::asset::LoadContext,
+    ) -> bevy::utils::BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            let json : TexturePackerJson = serde_json::from_slice(&bytes).map_err(TexturePackerLoaderError::Json)?;
+            let frames = json.frames.into_frames().map_err(TexturePackerLoaderError::Json)?;
+
+            let image_path = load_context.path().parent().map(|parent| parent.join(&json.meta.image)).unwrap_or(json.meta.image.clone().into());
+            let image_bytes = load_context.read_asset_bytes(image_path).await.map_err(TexturePackerLoaderError::ReadImage)?;
+            let sheet = image::load_from_memory(&image_bytes).map_err(TexturePackerLoaderError::Image)?.to_rgba8();
+
+            let sheet_rects : Vec<_> = frames.iter()
+                .map(|NamedTexturePackerFrame { frame, .. }| (URect::new(frame.frame.x, frame.frame.y, frame.frame.x + frame.frame.w, frame.frame.y + frame.frame.h), frame.rotated))
+                .collect();
+            let (sheet, rects) = sheet_frames(sheet, &sheet_rects);
+            let mut layout = TextureAtlasLayout::new_empty(Vec2::new(sheet.width() as f32, sheet.height() as f32));
+            for rect in rects { layout.add_texture(rect.as_rect()); }
+            let image = frame_image(UVec2::new(sheet.width(), sheet.height()), sheet.into_raw());
+            let image = load_context.add_labeled_asset("atlas".to_string(), image);
+            let layout = load_context.add_labeled_asset("layout".to_string(), layout);
+
+            let pivots = frames.iter().map(|frame| frame.frame.trimmed_pivot()).collect();
+            let frame_names = frames.iter().enumerate().map(|(index, frame)| (frame.filename.clone(), index)).collect();
+            let clips = build_clips(&frames, &frame_names, &json.animations, settings)?;
+
+            Ok(TexturePackerAtlas { image, layout, frame_names, pivots, clips })
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["tp.json", "texturepacker.json"]
+    }
+}
+
+/// Derives a clip for every frame name prefix, then adds the clips of the `animations` table and the settings, which replace
+/// derived clips of the same name.
+fn build_clips(
+    frames : &[NamedTexturePackerFrame],
+    frame_names : &HashMap<String, usize>,
+    animations : &StdHashMap<String, Vec<String>>,
+    settings : &TexturePackerLoaderSettings,
+) -> Result<HashMap<String, FrameClip>, TexturePackerLoaderError> {
+    let mut clip_frames : StdHashMap<String, Vec<(Option<u32>, usize)>> = StdHashMap::new();
+    for (index, frame) in frames.iter().enumerate() {
+        let (clip, number) = split_frame_name(&frame.filename);
+        clip_frames.entry(clip.to_string()).or_default().push((number, index));
+    }
+    let mut clips : HashMap<String, FrameClip> = clip_frames.into_iter()
+        .map(|(name, mut frames)| {
+            frames.sort_by_key(|(number, _)| *number);
+            let frames = frames.into_iter().map(|(_, index)| index).collect();
+            (name, FrameClip::new(frames, &[settings.frame_duration]))
+        })
+        .collect();
+
+    for (name, names) in animations.iter().chain(settings.clips.iter()) {
+        let frames = names.iter()
+            .map(|frame| frame_names.get(frame).copied().ok_or_else(|| TexturePackerLoaderError::UnknownFrame { clip : name.clone(), frame : frame.clone() }))
+            .collect::<Result<Vec<_>, _>>()?;
+        clips.insert(name.clone(), FrameClip::new(frames, &[settings.frame_duration]));
+    }
+    Ok(clips)
+}
+
+//=================================================================================
+//    TexturePacker Animation
+//=================================================================================
+
+/// This trait will allow you to animate a sprite with a TexturePacker atlas. Implement `Animation` for the type with
+/// `impl_animation!(MyAnimation, TexturePackerBackend)`.
+pub trait TexturePackerAnimation : Sized {
+
+    /// Clips are either listed in the JSON file or derived from frame names, so `explosion_01.png`, `explosion_02.png` and so on
+    /// become the clip `explosion`. This function will tell bevy what clip to use based on the current state of the struct.
+    fn get_clip_name(&self) -> &str;
+}
+
+/// The animation backend for TexturePacker atlases. See `TexturePackerAnimation`.
+pub struct TexturePackerBackend;
+
+impl <A : TexturePackerAnimation + Animation + FromWorld + Send + Sync + 'static> AnimationBackend<A> for TexturePackerBackend {
+    type AsociatedAsset = TexturePackerAtlas;
+
+    type Query<'w, 's> = (&'w mut TextureAtlas, &'w mut Sprite);
+
+    fn apply(
+        animator : &Animator<A>,
+        items : &mut <Self::Query<'_, '_> as WorldQuery>::Item<'_>,
+        asset : &Self::AsociatedAsset,
+    ) {
+        let (atlas, sprite) = items;
+        let Some(clip) = asset.clips.get(animator.animation.get_clip_name()) else { return };
+        atlas.index = clip.frame(animator.progress());
+
+        let pivot = asset.pivot(atlas.index);
+        let anchor = Anchor::Custom(Vec2::new(pivot.x - 0.5, 0.5 - pivot.y));
+        if sprite.anchor != anchor { sprite.anchor = anchor; }
+    }
+
+    fn spawn(animation : Option<A>, world : &mut World, path : String, entity : Entity) {
+        let animation_comp = animation.unwrap_or(A::from_world(world));
+        let asset_server = world.resource::<AssetServer>();
+        let atlas : Handle<TexturePackerAtlas> = asset_server.load(&path);
+        let image : Handle<Image> = asset_server.load(format!("{}#atlas", path));
+        let layout : Handle<TextureAtlasLayout> = asset_server.load(format!("{}#layout", path));
+
+        world.get_or_spawn(entity).unwrap()
+            .insert(Animator::new(animation_comp))
+            .insert(atlas)
+            .insert(SpriteSheetBundle {
+                texture : image,
+                atlas : TextureAtlas { layout, index : 0 },
+                ..Default::default()
+            })
+        ;
+    }
+
+    fn duration(animation : &A, asset : &Self::AsociatedAsset) -> f32 {
+        asset.clips.get(animation.get_clip_name()).map_or(0.0, |clip| clip.duration())
+    }
+    
+    fn debug_name(animation : &A) -> Option<&str> {
+        Some(animation.get_clip_name())
+    }
+    
+    fn debug_frame(animation : &A, progress : f32, asset : &Self::AsociatedAsset) -> Option<usize> {
+        asset.clips.get(animation.get_clip_name()).map(|clip| clip.frame(progress))
+    }
+}
+
+//=================================================================================
+//    Tests
+//=================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    const JSON : &str = r#"{
+        "frames" : {
+            "walk_02.png" : { "frame" : { "x" : 8, "y" : 0, "w" : 4, "h" : 8 }, "rotated" : false, "spriteSourceSize" : { "x" : 0, "y" : 0, "w" : 4, "h" : 8 }, "sourceSize" : { "w" : 8, "h" : 8 } },
+            "walk_01.png" : { "frame" : { "x" : 0, "y" : 0, "w" : 8, "h" : 8 }, "rotated" : false, "spriteSourceSize" : { "x" : 0, "y" : 0, "w" : 8, "h" : 8 }, "sourceSize" : { "w" : 8, "h" : 8 } },
+            "jump.png" : { "frame" : { "x" : 12, "y" : 0, "w" : 2, "h" : 4 }, "rotated" : true }
+        },
+        "animations" : { "hop" : ["jump.png", "walk_01.png"] },
+        "meta" : { "image" : "sheet.png" }
+    }"#;
+    
+    fn read(settings : &TexturePackerLoaderSettings) -> (Vec<NamedTexturePackerFrame>, Result<HashMap<String, FrameClip>, TexturePackerLoaderError>) {
+        let json : TexturePackerJson = serde_json::from_str(JSON).unwrap();
+        let frames = json.frames.into_frames().unwrap();
+        let frame_names = frames.iter().enumerate().map(|(index, frame)| (frame.filename.clone(), index)).collect();
+        let clips = build_clips(&frames, &frame_names, &json.animations, settings);
+        (frames, clips)
+    }
+    
+    fn index(frames : &[NamedTexturePackerFrame], name : &str) -> usize {
+        frames.iter().position(|frame| frame.filename == name).unwrap()
+    }
+    
+    #[test]
+    fn splits_frame_names() {
+        assert_eq!(split_frame_name("walk_03.png"), ("walk", Some(3)));
+        assert_eq!(split_frame_name("enemy/run-12"), ("enemy/run", Some(12)));
+        assert_eq!(split_frame_name("idle.png"), ("idle", None));
+        assert_eq!(split_frame_name("007.png"), ("007", Some(7)));
+        assert_eq!(split_frame_name("hero 1.2.png"), ("hero 1", Some(2)));
+    }
+    
+    #[test]
+    fn derives_clips_from_frame_names() {
+        let (frames, clips) = read(&TexturePackerLoaderSettings::default());
+        let clips = clips.unwrap();
+        assert_eq!(clips["walk"].frames(), &[index(&frames, "walk_01.png"), index(&frames, "walk_02.png")]);
+        assert_eq!(clips["walk"].duration(), 0.2);
+        assert_eq!(clips["jump"].frames(), &[index(&frames, "jump.png")]);
+        assert_eq!(clips["hop"].frames(), &[index(&frames, "jump.png"), index(&frames, "walk_01.png")]);
+    }
+    
+    #[test]
+    fn clip_table_replaces_derived_clips() {
+        let mut settings = TexturePackerLoaderSettings { frame_duration : 50, ..default() };
+        settings.clips.insert("walk".to_string(), vec!["walk_02.png".to_string()]);
+        let (frames, clips) = read(&settings);
+        let clips = clips.unwrap();
+        assert_eq!(clips["walk"].frames(), &[index(&frames, "walk_02.png")]);
+        assert_eq!(clips["walk"].duration(), 0.05);
+        
+        settings.clips.insert("fall".to_string(), vec!["fall_01.png".to_string()]);
+        assert!(matches!(read(&settings).1, Err(TexturePackerLoaderError::UnknownFrame { .. })));
+    }
+    
+    #[test]
+    fn lays_frames_out_over_the_sheet() {
+        let (frames, _) = read(&TexturePackerLoaderSettings::default());
+        let mut sheet = image::RgbaImage::new(16, 8);
+        // The jump frame is stored rotated, so its 2x4 frame takes up 4x2 pixels on the sheet.
+        sheet.put_pixel(12, 0, image::Rgba([255, 0, 0, 255]));
+        let sheet_rects : Vec<_> = frames.iter()
+            .map(|NamedTexturePackerFrame { frame, .. }| (URect::new(frame.frame.x, frame.frame.y, frame.frame.x + frame.frame.w, frame.frame.y + frame.frame.h), frame.rotated))
+            .collect();
+        let (image, rects) = sheet_frames(sheet, &sheet_rects);
+        assert_eq!(image.dimensions(), (16, 12));
+        assert_eq!(rects[index(&frames, "walk_02.png")], URect::new(8, 0, 12, 8));
+        assert_eq!(rects[index(&frames, "jump.png")], URect::new(0, 8, 2, 12));
+        assert_eq!(image.get_pixel(0, 11), &image::Rgba([255, 0, 0, 255]));
+        
+        let walk = &frames[index(&frames, "walk_02.png")].frame;
+        assert_eq!(walk.trimmed_pivot(), Vec2::new(1.0, 0.5));
+        let walk = &frames[index(&frames, "walk_01.png")].frame;
+        assert_eq!(walk.trimmed_pivot(), Vec2::new(0.5, 0.5));
+    }
+}
@@ -0,0 +1,100 @@
+//=================================================================================
+// Time sources decide which clock an animator counts its progress with. The
+// global animation time scales and pauses animations on its own, separate from
+// the virtual time that the rest of the game runs on.
+//=================================================================================
+
+use bevy::prelude::*;
+
+//=================================================================================
+//    Animation Time Resource
+//=================================================================================
+
+/// A global clock for animations, on top of the time source of each animator. Use this for slow motion or to pause animations
+/// without touching the virtual time that physics and game logic run on. Animators that use `AnimationTimeSource::Real` ignore it.
+#[derive(Resource, Reflect, Clone, Copy, Debug, PartialEq)]
+#[reflect(Resource)]
+pub struct AnimationTime {
+    pub scale : f32,
+    pub paused : bool,
+}
+
+impl Default for AnimationTime {
+    fn default() -> Self {
+        AnimationTime { scale : 1.0, paused : false }
+    }
+}
+
+impl AnimationTime {
+    /// Pauses every animator that uses this clock.
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    /// Unpauses every animator that uses this clock.
+    pub fn unpause(&mut self) {
+        self.paused = false;
+    }
+
+    /// Sets the scale of the clock. 1.0 is normal speed, 0.5 is half speed, 2.0 is double speed, etc.
+    pub fn set_scale(&mut self, scale : f32) {
+        self.scale = scale;
+    }
+
+    /// Scales a delta from a time source by this clock.
+    pub fn scale_delta(&self, delta : f32) -> f32 {
+        if self.paused { 0.0 } else { delta * self.scale }
+    }
+}
+
+//=================================================================================
+//    Animation Time Components
+//=================================================================================
+
+/// The clock that an animator counts its progress with. Animators without this component use `Virtual`. Members of a sync group
+/// should all use the same time source.
+#[derive(Component, Reflect, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[reflect(Component)]
+pub enum AnimationTimeSource {
+    /// The virtual time of the game, scaled by `AnimationTime`. This stops when the game is paused.
+    #[default]
+    Virtual,
+    /// The real time of the app. This keeps playing while the game is paused and ignores `AnimationTime`, which is useful for menus.
+    Real,
+    /// The fixed timestep, scaled by `AnimationTime`. The animator is advanced in `FixedUpdate` so that its progress is
+    /// deterministic, and applied with every other animator.
+    Fixed,
+}
+
+/// Freezes the animator on this entity for the given number of seconds, counted with its time source before any scaling. Once the
+/// time runs out the component does nothing, so it can be left on the entity and started again with `AnimationHitStop::start`.
+/// Members of a sync group follow the clock of their group, so they ignore hit-stop.
+#[derive(Component, Reflect, Clone, Copy, Debug, Default, PartialEq)]
+#[reflect(Component)]
+pub struct AnimationHitStop {
+    pub remaining : f32,
+}
+
+impl AnimationHitStop {
+    /// Creates a hit-stop that freezes the animator for the given number of seconds.
+    pub fn new(seconds : f32) -> Self {
+        AnimationHitStop { remaining : seconds }
+    }
+
+    /// Freezes the animator for the given number of seconds, unless it is already frozen for longer.
+    pub fn start(&mut self, seconds : f32) {
+        self.remaining = self.remaining.max(seconds);
+    }
+
+    /// Returns true while the animator is frozen.
+    pub fn is_active(&self) -> bool {
+        self.remaining > 0.0
+    }
+
+    /// Counts down the hit-stop by the unscaled delta, and returns the delta the animator should advance by.
+    pub(crate) fn apply(&mut self, unscaled : f32, delta : f32) -> f32 {
+        if !self.is_active() { return delta }
+        self.remaining -= unscaled;
+        0.0
+    }
+}
@@ -40,4 +40,75 @@ pub mod controls {
             east : KeyCode,
         }
     }
-}
\ No newline at end of file
+}
+
+/// Creates the image for a single frame from raw RGBA pixels.
+#[cfg(any(feature = "aseprite", feature = "texture_packer", feature = "animated_image", feature = "dragonbones"))]
+pub(crate) fn frame_image(dimensions : bevy::math::UVec2, pixels : Vec<u8>) -> bevy::render::texture::Image {
+    use bevy::render::{render_asset::RenderAssetUsages, render_resource::{Extent3d, TextureDimension, TextureFormat}, texture::{Image, ImageSampler}};
+    
+    let mut image = Image::new(
+        Extent3d {
+           width: dimensions.x,
+           height: dimensions.y,
+           depth_or_array_layers: 1,
+       }, 
+       TextureDimension::D2, 
+       pixels, 
+       TextureFormat::Rgba8UnormSrgb, 
+       RenderAssetUsages::all()
+    );
+    image.sampler = ImageSampler::nearest();
+    image
+}
+
+/// Helpers for cutting frames out of packed sprite sheets.
+#[cfg(any(feature = "aseprite_json", feature = "texture_packer", feature = "dragonbones"))]
+pub(crate) mod sheets {
+    use bevy::math::{URect, UVec2};
+    use image::{imageops, RgbaImage};
+    
+    /// Cuts a frame out of a sprite sheet and places it at `offset` in an image of `size`, which undoes trimming. If the frame
+    /// was rotated when it was packed, it is rotated back.
+    #[cfg(feature = "aseprite_json")]
+    pub fn untrim_frame(sheet : &RgbaImage, rect : URect, rotated : bool, offset : bevy::math::IVec2, size : UVec2) -> RgbaImage {
+        let (width, height) = if rotated { (rect.height(), rect.width()) } else { (rect.width(), rect.height()) };
+        let mut cut = imageops::crop_imm(sheet, rect.min.x, rect.min.y, width, height).to_image();
+        if rotated { cut = imageops::rotate270(&cut); }
+        
+        let mut image = RgbaImage::new(size.x, size.y);
+        imageops::replace(&mut image, &cut, offset.x as i64, offset.y as i64);
+        image
+    }
+    
+    /// Lays frames out over a packed sprite sheet without repacking it. Each frame is given as its rect on the sheet, sized as
+    /// the frame was before it was rotated, and whether it was rotated. Rotated frames are rotated back and copied into rows
+    /// below the sheet, so every frame can be drawn from the returned image with the returned rect.
+    #[cfg(any(feature = "texture_packer", feature = "dragonbones"))]
+    pub fn sheet_frames(sheet : RgbaImage, frames : &[(URect, bool)]) -> (RgbaImage, Vec<URect>) {
+        let mut rects = Vec::with_capacity(frames.len());
+        let mut cuts = Vec::new();
+        let (mut x, mut y, mut row_height, mut width) = (0, sheet.height(), 0, sheet.width());
+        for (rect, rotated) in frames.iter().copied() {
+            if !rotated { rects.push(rect); continue }
+            let size = rect.size();
+            if x > 0 && x + size.x > sheet.width() {
+                x = 0;
+                y += row_height;
+                row_height = 0;
+            }
+            let cut = imageops::rotate270(&imageops::crop_imm(&sheet, rect.min.x, rect.min.y, size.y, size.x).to_image());
+            rects.push(URect::from_corners(UVec2::new(x, y), UVec2::new(x, y) + size));
+            cuts.push((x, y, cut));
+            width = width.max(x + size.x);
+            x += size.x;
+            row_height = row_height.max(size.y);
+        }
+        if cuts.is_empty() { return (sheet, rects) }
+        
+        let mut image = RgbaImage::new(width, y + row_height);
+        imageops::replace(&mut image, &sheet, 0, 0);
+        for (x, y, cut) in cuts { imageops::replace(&mut image, &cut, x as i64, y as i64); }
+        (image, rects)
+    }
+}